@@ -1,17 +1,25 @@
+use std::collections::BTreeMap;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, to_json_binary};
+use cosmwasm_std::{Addr, Binary, Coin, Deps, DepsMut, Env, Event, MessageInfo, Order, Reply, Response, StdError, StdResult, Storage, SubMsgResult, Uint128, from_json, to_json_binary};
 use cw2::set_contract_version;
+use cw_storage_plus::Map;
 
 use crate::error::ContractError;
-use crate::execute::{store_proof, update_admin, verify_proof, whitelist_node, remove_node, update_node_reputation, update_min_reputation_threshold, configure_treasury, register_node, add_deposit, unlock_deposit, claim_unlocked_deposit};
-use crate::msg::{AdminExecuteMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, NodeExecuteMsg, QueryMsg};
+use crate::execute::{store_proof, update_admin, verify_proof, whitelist_node, remove_node, update_node_reputation, update_min_reputation_threshold, configure_treasury, register_node, add_deposit, unlock_deposit, claim_unlocked_deposit, add_region, remove_region, set_region, configure_facility_registry, set_plan, remove_plan, assign_owner_plan, subscribe_plan, configure_proof_bond, forfeit_proof_bond, claim_proof_bonds, configure_proof_limits, configure_legacy_store_proof, set_legacy_mapping, store_proof_legacy, set_deposit_denom, set_tier_capabilities, remove_tier_capabilities, decommission_worker, configure_conflicting_proofs, resolve_proof_conflict, pre_register, complete_registration, refund_expired_pre_registrations, configure_pre_registration, configure_referral_reward, fund_referral_pool, emergency_release, configure_admin_timelock, execute_pending_action, cancel_pending_action, check_deposit_shortfalls, configure_deposit_topup_grace, flag_proof, clear_proof_flags, configure_reputation_bounds, set_node_paused, configure_event_attribute_cap, configure_sequence_gap_tolerance, raise_dispute, resolve_dispute, configure_dispute_bond, configure_reputation_scoring, configure_dispute_hold, configure_proof_rewards, fund_rewards, claim_rewards, advance_onboarding, configure_proof_fee, sweep_fees, configure_alert_thresholds, check_alerts, store_proofs, verify_proofs, revoke_proof, set_contract_paused, update_config, configure_probation, attest_proof, set_role, configure_operator_limit, set_operator_node_limit, close_epoch, update_tier, configure_data_reference_schemes, approve_proofs, add_owner_delegate, remove_owner_delegate, slash_node, configure_reputation_delta_limit, update_did_contract, tracked_liabilities, add_did_prefix, remove_did_prefix, declare_gateway, assign_worker, configure_proof_retention, compact_proofs, configure_escrow_price, fund_worker_escrow, withdraw_worker_escrow, add_hook_subscriber, remove_hook_subscriber, configure_slash_distribution, configure_carry_over, jail_node, configure_auto_jail, unjail, whitelist_nodes, remove_nodes, configure_node_batch_limit, record_worker_energy, HOOK_REPLY_ID};
+use crate::helpers::{cw20_token, PreviewStorage, CW20_DENOM_PREFIX};
+use crate::msg::{Cw20HookMsg, Cw20ReceiveMsg, AlertThresholds, OnboardingStage, ProbationPolicy, ProofRewards, ReputationBounds, ReputationScoring, SlashDistribution, CarryOverRules, AdminExecuteMsg, DataOwnerExecuteMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, NodeExecuteMsg, ProofStatus, QueryMsg, QueryMsgV2};
 use crate::query;
-use crate::state::{Config, CONFIG};
+use crate::state::{
+    proofs, Config, GatewayProofEntry, Node, Proof, UnlockingDeposit, CONFIG, GATEWAY_PROOFS, GATEWAY_TIME_PROOFS,
+    HEIGHT_PROOFS, LIABILITIES, OWNER_PROOFS, PROOF_SCHEMA_VERSION, REGION_NODES, UNLOCKING_DEPOSITS, WHITELISTED_NODES, WORKER_ENERGY, WORKER_PROOF_COUNTS, WORKER_WINDOWS,
+    OPEN_DISPUTES, OPEN_DISPUTE_COUNT, UNLOCKING_TOTAL,
+};
 
 // Contract name and version information
-const CONTRACT_NAME: &str = "crates.io:detrack-node-contract";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub(crate) const CONTRACT_NAME: &str = "crates.io:detrack-node-contract";
+pub(crate) const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Handles contract instantiation.
 /// Initializes the contract with admin, version, and other configurable parameters.
@@ -34,7 +42,9 @@ pub fn instantiate(
     let config = Config {
         admin,
         proof_count: 0,
-        min_reputation_threshold: 0, // Default minimum reputation threshold
+        min_reputation_tier1: 0, // Default minimum reputation thresholds
+        min_reputation_tier2: 0,
+        min_reputation_tier3: 0,
         treasury: None, // Initialize treasury as None
         did_contract_address: deps.api.addr_validate(&msg.did_contract_address)?,
         // Initialize new config fields from InstantiateMsg
@@ -47,6 +57,48 @@ pub fn instantiate(
         use_whitelist: msg.use_whitelist,
         deposit_unlock_period_blocks: msg.deposit_unlock_period_blocks,
         max_batch_size: msg.max_batch_size,
+        facility_registry_address: msg
+            .facility_registry_address
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+        proof_bond: Uint128::zero(),
+        proof_finalization_period_blocks: 0,
+        max_snapshots_per_proof: None,
+        max_batch_metadata_bytes: None,
+        legacy_store_proof_enabled: false,
+        deposit_denoms: vec![],
+        tier_capabilities: vec![],
+        reject_conflicting_proofs: false,
+        pre_registration_period_blocks: 0,
+        referral_reward: Uint128::zero(),
+        referral_pool: Uint128::zero(),
+        admin_timelock_blocks: 0,
+        pending_action_count: 0,
+        deposit_topup_grace_blocks: 0,
+        reputation_bounds: ReputationBounds::default(),
+        max_event_attribute_bytes: None,
+        max_sequence_gap: None,
+        dispute_bond: Uint128::zero(),
+        dispute_count: 0,
+        reputation_scoring: ReputationScoring::default(),
+        dispute_hold_bps: None,
+        proof_rewards: ProofRewards::default(),
+        reward_pool: Uint128::zero(),
+        proof_fee: None,
+        collected_fees: Uint128::zero(),
+        alert_thresholds: AlertThresholds::default(),
+        paused: false,
+        probation: ProbationPolicy::default(),
+        max_nodes_per_operator: None,
+        data_reference_schemes: None,
+        max_reputation_delta: None,
+        did_prefixes: None,
+        proof_retention_seconds: None,
+        escrow_price_per_batch: Uint128::zero(),
+        slash_distribution: SlashDistribution::default(),
+        carry_over: CarryOverRules::default(),
+        auto_jail: None,
+        node_batch_limit: None,
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -60,28 +112,191 @@ pub fn instantiate(
 
 /// Handles contract execution.
 /// Routes incoming `ExecuteMsg` to the appropriate handler function based on whether
-/// it\'s an `AdminExecuteMsg` or a `NodeExecuteMsg`.
+/// it\'s an `AdminExecuteMsg`, a `NodeExecuteMsg` or a `DataOwnerExecuteMsg`.
 /// Admin messages are for administrative tasks like managing nodes and configuration.
 /// Node messages are for core DeTrack operations like storing proofs and registering.
+/// Data owner messages are for owners of the measured facilities (e.g., plan subscriptions).
+/// CW20 deposits (`Receive`) are dispatched as the node message of their hook, with the tokens
+/// as funds under the `cw20:<token address>` denom.
+/// While the contract is paused, pausable node messages are rejected before dispatch.
+/// With the `debug-invariants` feature, state invariants are checked after every successful handler.
 /// TODO: Add governance-related execute messages once HLD for governance is implemented.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let (info, msg) = match msg {
+        ExecuteMsg::Receive(receive) => cw20_hook(deps.as_ref(), info, receive)?,
+        msg => {
+            // `cw20:` denoms only ever come from a token contract's `Receive`
+            if let Some(coin) = info.funds.iter().find(|c| cw20_token(&c.denom).is_some()) {
+                return Err(ContractError::UnsupportedDepositDenom { denom: coin.denom.clone() });
+            }
+            (info, msg)
+        }
+    };
+    if let ExecuteMsg::Node(node_msg) = &msg {
+        if node_msg.is_pausable() && CONFIG.load(deps.storage)?.paused {
+            return Err(ContractError::ContractPaused {});
+        }
+    }
+
+    let response = dispatch_execute(deps.branch(), env.clone(), info, msg)?;
+
+    #[cfg(feature = "debug-invariants")]
+    crate::invariants::check(deps.as_ref(), &env)?;
+
+    Ok(response)
+}
+
+/// Turns a CW20 `Receive` into the node message of its hook, sent by the token sender with the
+/// received tokens as funds. Whether the token is accepted is checked with the other deposit
+/// denoms when the deposit is valued.
+fn cw20_hook(deps: Deps, info: MessageInfo, receive: Cw20ReceiveMsg) -> Result<(MessageInfo, ExecuteMsg), ContractError> {
+    // Only the tokens are credited, so native coins sent along would be kept without a record
+    if !info.funds.is_empty() {
+        return Err(ContractError::InvalidInput("Native funds cannot be sent with a cw20 receive".to_string()));
+    }
+    let funds = vec![Coin { denom: format!("{}{}", CW20_DENOM_PREFIX, info.sender), amount: receive.amount }];
+    let info = MessageInfo { sender: deps.api.addr_validate(&receive.sender)?, funds };
+    let node_msg = match from_json(&receive.msg)? {
+        Cw20HookMsg::RegisterNode { referrer, operator_did } => NodeExecuteMsg::RegisterNode { referrer, operator_did },
+        Cw20HookMsg::AddDeposit {} => NodeExecuteMsg::AddDeposit {},
+        Cw20HookMsg::UpdateTier {} => NodeExecuteMsg::UpdateTier {},
+    };
+    Ok((info, ExecuteMsg::Node(node_msg)))
+}
+
+fn dispatch_execute(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
+        // Unwrapped by `execute` before dispatch
+        ExecuteMsg::Receive(_) => Err(ContractError::InvalidInput("Nested cw20 receive".to_string())),
         ExecuteMsg::Admin(admin_msg) => match admin_msg {
             AdminExecuteMsg::UpdateAdmin { new_admin } => update_admin(deps, info, new_admin),
-            AdminExecuteMsg::WhitelistNode { node_address } => whitelist_node(deps, env, info, node_address),
-            AdminExecuteMsg::RemoveNode { node_address } => remove_node(deps, info, node_address),
+            AdminExecuteMsg::GrantRole { role, address } => set_role(deps, info, role, address, true),
+            AdminExecuteMsg::RevokeRole { role, address } => set_role(deps, info, role, address, false),
+            AdminExecuteMsg::Pause {} => set_contract_paused(deps, info, true),
+            AdminExecuteMsg::Unpause {} => set_contract_paused(deps, info, false),
+            AdminExecuteMsg::WhitelistNode { node_address, initial_tier, initial_reputation } =>
+                whitelist_node(deps, env, info, node_address, initial_tier, initial_reputation),
+            AdminExecuteMsg::RemoveNode { node_address } => remove_node(deps, env, info, node_address),
+            AdminExecuteMsg::WhitelistNodes { node_addresses } => whitelist_nodes(deps, env, info, node_addresses),
+            AdminExecuteMsg::RemoveNodes { node_addresses } => remove_nodes(deps, env, info, node_addresses),
+            AdminExecuteMsg::ConfigureNodeBatchLimit { max_nodes } => configure_node_batch_limit(deps, info, max_nodes),
             AdminExecuteMsg::UpdateNodeReputation { node_address, reputation } => 
-                update_node_reputation(deps, info, node_address, reputation),
-            AdminExecuteMsg::UpdateMinReputationThreshold { threshold } =>
-                update_min_reputation_threshold(deps, info, threshold),
+                update_node_reputation(deps, env, info, node_address, reputation),
+            AdminExecuteMsg::UpdateMinReputationThreshold { threshold, tier } =>
+                update_min_reputation_threshold(deps, info, threshold, tier),
+            AdminExecuteMsg::UpdateConfig {
+                min_stake_tier1,
+                min_stake_tier2,
+                min_stake_tier3,
+                deposit_tier1,
+                deposit_tier2,
+                deposit_tier3,
+                deposit_unlock_period_blocks,
+                max_batch_size,
+                use_whitelist,
+                preview,
+            } => with_preview(deps, preview, |deps| update_config(
+                deps,
+                info,
+                [min_stake_tier1, min_stake_tier2, min_stake_tier3],
+                [deposit_tier1, deposit_tier2, deposit_tier3],
+                deposit_unlock_period_blocks,
+                max_batch_size,
+                use_whitelist,
+            )),
             AdminExecuteMsg::ConfigureTreasury { treasury_address } =>
                 configure_treasury(deps, info, treasury_address),
+            AdminExecuteMsg::AddRegion { region } => add_region(deps, info, region),
+            AdminExecuteMsg::RemoveRegion { region } => remove_region(deps, info, region),
+            AdminExecuteMsg::ConfigureFacilityRegistry { registry_address } =>
+                configure_facility_registry(deps, info, registry_address),
+            AdminExecuteMsg::UpdateDidContract { address } => update_did_contract(deps, info, address),
+            AdminExecuteMsg::AddDidPrefix { prefix } => add_did_prefix(deps, info, prefix),
+            AdminExecuteMsg::RemoveDidPrefix { prefix } => remove_did_prefix(deps, info, prefix),
+            AdminExecuteMsg::ConfigureProofBond { amount, finalization_period_blocks, preview } =>
+                with_preview(deps, preview, |deps| configure_proof_bond(deps, info, amount, finalization_period_blocks)),
+            AdminExecuteMsg::ConfigureProofFee { fee, preview } =>
+                with_preview(deps, preview, |deps| configure_proof_fee(deps, info, fee)),
+            AdminExecuteMsg::SweepFees {} => sweep_fees(deps, env, info),
+            AdminExecuteMsg::ConfigureProbation { policy, preview } =>
+                with_preview(deps, preview, |deps| configure_probation(deps, info, policy)),
+            AdminExecuteMsg::ConfigureOperatorLimit { max_nodes_per_operator } =>
+                configure_operator_limit(deps, info, max_nodes_per_operator),
+            AdminExecuteMsg::SetOperatorNodeLimit { operator_did, max_nodes } =>
+                set_operator_node_limit(deps, info, operator_did, max_nodes),
+            AdminExecuteMsg::ConfigureAlertThresholds { thresholds, preview } =>
+                with_preview(deps, preview, |deps| configure_alert_thresholds(deps, info, thresholds)),
+            AdminExecuteMsg::RevokeProof { proof_id, reason } => revoke_proof(deps, env, info, proof_id, reason),
+            AdminExecuteMsg::ForfeitProofBond { proof_id } => forfeit_proof_bond(deps, env, info, proof_id),
+            AdminExecuteMsg::ConfigureLegacyStoreProof { enabled } => configure_legacy_store_proof(deps, info, enabled),
+            AdminExecuteMsg::SetLegacyMapping { node_address, mapping } =>
+                set_legacy_mapping(deps, info, node_address, mapping),
+            AdminExecuteMsg::ConfigureProofLimits { max_snapshots_per_proof, max_batch_metadata_bytes, preview } =>
+                with_preview(deps, preview, |deps| {
+                    configure_proof_limits(deps, info, max_snapshots_per_proof, max_batch_metadata_bytes)
+                }),
+            AdminExecuteMsg::EmergencyRelease { node_address } => emergency_release(deps, env, info, node_address),
+            AdminExecuteMsg::ConfigureAdminTimelock { blocks } => configure_admin_timelock(deps, env, info, blocks),
+            AdminExecuteMsg::ConfigureReputationDeltaLimit { max_delta } =>
+                configure_reputation_delta_limit(deps, env, info, max_delta),
+            AdminExecuteMsg::ExecutePendingAction { action_id } => execute_pending_action(deps, env, info, action_id),
+            AdminExecuteMsg::CancelPendingAction { action_id } => cancel_pending_action(deps, env, info, action_id),
+            AdminExecuteMsg::ConfigureReferralReward { amount } => configure_referral_reward(deps, info, amount),
+            AdminExecuteMsg::FundReferralPool {} => fund_referral_pool(deps, info),
+            AdminExecuteMsg::ConfigureProofRewards { rewards, preview } =>
+                with_preview(deps, preview, |deps| configure_proof_rewards(deps, info, rewards)),
+            AdminExecuteMsg::FundRewards {} => fund_rewards(deps, info),
+            AdminExecuteMsg::ConfigureDepositTopUpGrace { blocks } => configure_deposit_topup_grace(deps, info, blocks),
+            AdminExecuteMsg::ConfigureReputationBounds { bounds, preview } =>
+                with_preview(deps, preview, |deps| configure_reputation_bounds(deps, info, bounds)),
+            AdminExecuteMsg::ConfigureReputationScoring { scoring, preview } =>
+                with_preview(deps, preview, |deps| configure_reputation_scoring(deps, info, scoring)),
+            AdminExecuteMsg::ConfigureEventAttributeCap { max_bytes } => configure_event_attribute_cap(deps, info, max_bytes),
+            AdminExecuteMsg::ConfigureDataReferenceSchemes { schemes } =>
+                configure_data_reference_schemes(deps, info, schemes),
+            AdminExecuteMsg::ConfigureProofRetention { retention_seconds } =>
+                configure_proof_retention(deps, info, retention_seconds),
+            AdminExecuteMsg::ConfigureEscrowPrice { price_per_batch } => configure_escrow_price(deps, info, price_per_batch),
+            AdminExecuteMsg::AddHookSubscriber { contract_addr, events } => add_hook_subscriber(deps, info, contract_addr, events),
+            AdminExecuteMsg::RemoveHookSubscriber { contract_addr } => remove_hook_subscriber(deps, info, contract_addr),
+            AdminExecuteMsg::ConfigureSequenceGapTolerance { max_gap } => configure_sequence_gap_tolerance(deps, info, max_gap),
+            AdminExecuteMsg::ResolveDispute { dispute_id, verdict } => resolve_dispute(deps, env, info, dispute_id, verdict),
+            AdminExecuteMsg::SlashNode { node_address, bps, reason } => slash_node(deps, env, info, node_address, bps, reason),
+            AdminExecuteMsg::ConfigureDisputeBond { amount } => configure_dispute_bond(deps, info, amount),
+            AdminExecuteMsg::ConfigureDisputeHold { bps } => configure_dispute_hold(deps, info, bps),
+            AdminExecuteMsg::ConfigureSlashDistribution { distribution, preview } =>
+                with_preview(deps, preview, |deps| configure_slash_distribution(deps, info, distribution)),
+            AdminExecuteMsg::ConfigureCarryOver { rules, preview } =>
+                with_preview(deps, preview, |deps| configure_carry_over(deps, info, rules)),
+            AdminExecuteMsg::JailNode { node_address, blocks } => jail_node(deps, env, info, node_address, blocks),
+            AdminExecuteMsg::ConfigureAutoJail { policy, preview } =>
+                with_preview(deps, preview, |deps| configure_auto_jail(deps, info, policy)),
+            AdminExecuteMsg::ClearProofFlags { proof_id } => clear_proof_flags(deps, info, proof_id),
+            AdminExecuteMsg::ConfigurePreRegistration { period_blocks } =>
+                configure_pre_registration(deps, info, period_blocks),
+            AdminExecuteMsg::ResolveProofConflict { conflicting_proof_id, canonical_proof_id, reputation_delta } =>
+                resolve_proof_conflict(deps, env, info, conflicting_proof_id, canonical_proof_id, reputation_delta),
+            AdminExecuteMsg::ConfigureConflictingProofs { reject } => configure_conflicting_proofs(deps, info, reject),
+            AdminExecuteMsg::SetTierCapabilities { capabilities, preview } =>
+                with_preview(deps, preview, |deps| set_tier_capabilities(deps, info, capabilities)),
+            AdminExecuteMsg::RemoveTierCapabilities { tier } => remove_tier_capabilities(deps, info, tier),
+            AdminExecuteMsg::SetDepositDenom { denom, weight } => set_deposit_denom(deps, info, denom, weight),
+            AdminExecuteMsg::SetPlan { plan_id, proofs_per_period, max_metadata_bytes, price, retention_seconds } =>
+                set_plan(deps, info, plan_id, proofs_per_period, max_metadata_bytes, price, retention_seconds),
+            AdminExecuteMsg::RemovePlan { plan_id } => remove_plan(deps, info, plan_id),
+            AdminExecuteMsg::AssignOwnerPlan { data_owner, plan_id, expires_at } =>
+                assign_owner_plan(deps, info, data_owner, plan_id, expires_at),
         },
         ExecuteMsg::Node(node_msg) => match node_msg {
             NodeExecuteMsg::StoreProof { 
@@ -92,6 +307,13 @@ pub fn execute(
                 batch_metadata,
                 original_data_reference,
                 metadata_json,
+                region,
+                site,
+                data_owner,
+                owner_shares,
+                value_in,
+                value_out,
+                unit,
             } => store_proof(
                 deps, 
                 env, 
@@ -103,14 +325,100 @@ pub fn execute(
                 batch_metadata,
                 original_data_reference,
                 metadata_json,
+                region,
+                site,
+                data_owner,
+                owner_shares,
+                value_in,
+                value_out,
+                unit,
             ),
-            NodeExecuteMsg::RegisterNode {} => register_node(deps, env, info),
+            NodeExecuteMsg::StoreProofs { proofs } => store_proofs(deps, env, info, proofs),
+            NodeExecuteMsg::StoreProofLegacy {
+                data_hash,
+                original_data_reference,
+                data_owner,
+                metadata_json,
+                tw_start,
+                tw_end,
+                value_in,
+                value_out,
+                unit,
+            } => store_proof_legacy(
+                deps,
+                env,
+                info,
+                data_hash,
+                original_data_reference,
+                data_owner,
+                metadata_json,
+                tw_start,
+                tw_end,
+                value_in,
+                value_out,
+                unit,
+            ),
+            NodeExecuteMsg::RegisterNode { referrer, operator_did } => register_node(deps, env, info, referrer, operator_did),
+            NodeExecuteMsg::PreRegister {} => pre_register(deps, env, info),
+            NodeExecuteMsg::CompleteRegistration {} => complete_registration(deps, env, info),
+            NodeExecuteMsg::CheckDepositShortfalls { start_after, limit } =>
+                check_deposit_shortfalls(deps, env, start_after, limit),
+            NodeExecuteMsg::CheckAlerts {} => check_alerts(deps),
+            NodeExecuteMsg::RefundExpiredPreRegistrations { limit } => refund_expired_pre_registrations(deps, env, limit),
             NodeExecuteMsg::AddDeposit {} => add_deposit(deps, env, info), // Added
+            NodeExecuteMsg::UpdateTier {} => update_tier(deps, env, info),
             NodeExecuteMsg::VerifyProof { data_hash } => verify_proof(deps, env, info, data_hash),
+            NodeExecuteMsg::VerifyProofs { data_hashes } => verify_proofs(deps, info, data_hashes),
             NodeExecuteMsg::UnlockDeposit {} => unlock_deposit(deps, env, info),
             NodeExecuteMsg::ClaimUnlockedDeposit {} => claim_unlocked_deposit(deps, env, info),
+            NodeExecuteMsg::SetRegion { region } => set_region(deps, env, info, region),
+            NodeExecuteMsg::PauseSelf {} => set_node_paused(deps, env, info, true),
+            NodeExecuteMsg::ResumeSelf {} => set_node_paused(deps, env, info, false),
+            NodeExecuteMsg::Unjail {} => unjail(deps, env, info),
+            NodeExecuteMsg::ClaimProofBonds { proof_ids } => claim_proof_bonds(deps, env, info, proof_ids),
+            NodeExecuteMsg::DecommissionWorker { worker_did } => decommission_worker(deps, env, info, worker_did),
+            NodeExecuteMsg::RaiseDispute { proof_id, evidence_uri } => raise_dispute(deps, env, info, proof_id, evidence_uri),
+            NodeExecuteMsg::ClaimRewards {} => claim_rewards(deps, env, info),
+            NodeExecuteMsg::AttestProof { proof_id } => attest_proof(deps, env, info, proof_id),
+            NodeExecuteMsg::CloseEpoch { epoch } => close_epoch(deps, env, epoch),
+            NodeExecuteMsg::DeclareGateway { gateway_did, reporting_interval_seconds } =>
+                declare_gateway(deps, env, info, gateway_did, reporting_interval_seconds),
+            NodeExecuteMsg::AssignWorker { worker_did, node_address } => assign_worker(deps, env, info, worker_did, node_address),
+            NodeExecuteMsg::CompactProofs { start_after, limit } => compact_proofs(deps, env, start_after, limit),
         },
+        ExecuteMsg::DataOwner(owner_msg) => match owner_msg {
+            DataOwnerExecuteMsg::SubscribePlan { plan_id } => subscribe_plan(deps, env, info, plan_id),
+            DataOwnerExecuteMsg::FlagProof { proof_id, reason, owner } => flag_proof(deps, env, info, proof_id, reason, owner),
+            DataOwnerExecuteMsg::ApproveProofs { proof_ids, owner } => approve_proofs(deps, env, info, proof_ids, owner),
+            DataOwnerExecuteMsg::AddDelegate { delegate } => add_owner_delegate(deps, info, delegate),
+            DataOwnerExecuteMsg::RemoveDelegate { delegate } => remove_owner_delegate(deps, info, delegate),
+            DataOwnerExecuteMsg::FundWorkerEscrow { worker_did } => fund_worker_escrow(deps, info, worker_did),
+            DataOwnerExecuteMsg::WithdrawWorkerEscrow { worker_did, amount } =>
+                withdraw_worker_escrow(deps, env, info, worker_did, amount),
+        },
+    }
+}
+
+/// Runs a configuration handler. With `preview`, its writes go to a `PreviewStorage` and are
+/// dropped: the response keeps the handler's attributes and events and carries the resulting
+/// `ConfigResponse` as data.
+fn with_preview(
+    deps: DepsMut,
+    preview: bool,
+    handler: impl FnOnce(DepsMut) -> Result<Response, ContractError>,
+) -> Result<Response, ContractError> {
+    if !preview {
+        return handler(deps);
     }
+    let mut storage = PreviewStorage::new(deps.storage);
+    let response = handler(DepsMut { storage: &mut storage, api: deps.api, querier: deps.querier })?;
+    let config = query::config(Deps { storage: &storage, api: deps.api, querier: deps.querier })?;
+
+    Ok(Response::new()
+        .add_attributes(response.attributes)
+        .add_events(response.events)
+        .add_attribute("preview", "true")
+        .set_data(to_json_binary(&config)?))
 }
 
 /// Handles contract queries.
@@ -120,24 +428,132 @@ pub fn execute(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(
     deps: Deps,
-    _env: Env,
+    env: Env,
     msg: QueryMsg,
 ) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&query::config(deps)?),
         QueryMsg::Proof { id } => to_json_binary(&query::proof(deps, id)?),
         QueryMsg::ProofByHash { data_hash } => to_json_binary(&query::proof_by_hash(deps, data_hash)?),
+        QueryMsg::VerifyHash { data_hash } => to_json_binary(&query::verify_hash(deps, env, data_hash)?),
         QueryMsg::Proofs { start_after, limit } => to_json_binary(&query::query_proofs(deps, start_after, limit)?),
         QueryMsg::ProofsByWorker { worker_did, start_after, limit } => 
             to_json_binary(&query::query_proofs_by_worker(deps, worker_did, start_after, limit)?),
-        QueryMsg::ProofsByGateway { gateway_did, start_after, limit } =>
-            to_json_binary(&query::query_proofs_by_gateway(deps, gateway_did, start_after, limit)?),
+        QueryMsg::ProofsByGateway { gateway_did, start_after, limit, from, to } =>
+            to_json_binary(&query::query_proofs_by_gateway(deps, gateway_did, start_after, limit, from, to)?),
+        QueryMsg::GatewayProofSummaries { gateway_did, start_after, limit, from, to } =>
+            to_json_binary(&query::gateway_proof_summaries(deps, gateway_did, start_after, limit, from, to)?),
         QueryMsg::IsWhitelisted { address } => to_json_binary(&query::is_whitelisted(deps, address)?),
         QueryMsg::NodeReputation { address } => to_json_binary(&query::node_reputation(deps, address)?),
         QueryMsg::NodeInfo { address } => to_json_binary(&query::node_info(deps, address)?),
+        QueryMsg::OnboardingStatus { address } => to_json_binary(&query::onboarding_status(deps, address)?),
+        QueryMsg::SimulateSlash { node_address, bps } => to_json_binary(&query::simulate_slash(deps, node_address, bps)?),
+        QueryMsg::Regions {} => to_json_binary(&query::regions(deps)?),
+        QueryMsg::ProofsByRegion { region, start_after, limit } =>
+            to_json_binary(&query::query_proofs_by_region(deps, region, start_after, limit)?),
+        QueryMsg::ProofsByOwner { owner, start_after, limit } =>
+            to_json_binary(&query::query_proofs_by_owner(deps, owner, start_after, limit)?),
+        QueryMsg::UnapprovedProofs { data_owner, start_after, limit } =>
+            to_json_binary(&query::query_unapproved_proofs(deps, data_owner, start_after, limit)?),
+        QueryMsg::OwnerDelegates { owner } => to_json_binary(&query::owner_delegates(deps, owner)?),
+        QueryMsg::ProofsByGridConnectionPoint { grid_connection_point, start_after, limit } =>
+            to_json_binary(&query::query_proofs_by_grid_connection_point(deps, grid_connection_point, start_after, limit)?),
+        QueryMsg::Plans {} => to_json_binary(&query::plans(deps)?),
+        QueryMsg::OwnerQuota { data_owner } => to_json_binary(&query::owner_quota(deps, env, data_owner)?),
+        QueryMsg::LegacyMapping { node_address } => to_json_binary(&query::legacy_mapping(deps, node_address)?),
+        QueryMsg::ProofBond { proof_id } => to_json_binary(&query::proof_bond(deps, proof_id)?),
+        QueryMsg::ProofConflicts { start_after, limit } =>
+            to_json_binary(&query::proof_conflicts(deps, start_after, limit)?),
+        QueryMsg::ProofsSinceHeight { height, start_after, limit } =>
+            to_json_binary(&query::query_proofs_since_height(deps, height, start_after, limit)?),
+        QueryMsg::ProofFlags { start_after, limit } =>
+            to_json_binary(&query::proof_flags(deps, start_after, limit)?),
+        QueryMsg::Dispute { dispute_id } => to_json_binary(&query::dispute(deps, dispute_id)?),
+        QueryMsg::Disputes { start_after, limit } => to_json_binary(&query::disputes(deps, start_after, limit)?),
+        QueryMsg::HeldDeposit { node_address } => to_json_binary(&query::held_deposit(deps, node_address)?),
+        QueryMsg::MigrationPreview { target_version } =>
+            to_json_binary(&query::migration_preview(deps, target_version)?),
+        QueryMsg::AdminAuditLog { start_after, limit } =>
+            to_json_binary(&query::admin_audit_log(deps, start_after, limit)?),
+        QueryMsg::PendingActions { start_after, limit } =>
+            to_json_binary(&query::pending_actions(deps, start_after, limit)?),
+        QueryMsg::AuditCommittee { proof_id } => to_json_binary(&query::audit_committee(deps, proof_id)?),
+        QueryMsg::OperatorNodes { operator_did } => to_json_binary(&query::operator_nodes(deps, operator_did)?),
+        QueryMsg::Roles { address } => to_json_binary(&query::roles(deps, address)?),
+        QueryMsg::PreRegistration { address } => to_json_binary(&query::pre_registration(deps, address)?),
+        QueryMsg::WorkerStatus { worker_did } => to_json_binary(&query::worker_status(deps, worker_did)?),
+        QueryMsg::WorkerActivity { worker_did, from, to } => {
+            to_json_binary(&query::worker_activity(deps, worker_did, from, to)?)
+        }
+        QueryMsg::WorkerEscrow { worker_did } => to_json_binary(&query::worker_escrow(deps, worker_did)?),
+        QueryMsg::HookSubscribers {} => to_json_binary(&query::hook_subscribers(deps)?),
+        QueryMsg::WorkerAssignment { worker_did } => to_json_binary(&query::worker_assignment(deps, worker_did)?),
+        QueryMsg::GatewaysMissingData { since, start_after, limit } =>
+            to_json_binary(&query::gateways_missing_data(deps, since, start_after, limit)?),
+        QueryMsg::GatewaySequence { gateway_did } => to_json_binary(&query::gateway_sequence(deps, gateway_did)?),
+        QueryMsg::NodesByRegion { region, start_after, limit } =>
+            to_json_binary(&query::query_nodes_by_region(deps, region, start_after, limit)?),
+        QueryMsg::NodesByReferrer { referrer, start_after, limit } =>
+            to_json_binary(&query::query_nodes_by_referrer(deps, referrer, start_after, limit)?),
+        QueryMsg::ProofsByHashes { data_hashes } => to_json_binary(&query::proofs_by_hashes(deps, data_hashes)?),
+        QueryMsg::EnergyStats { worker_did, from, to } =>
+            to_json_binary(&query::energy_stats(deps, worker_did, from, to)?),
+        QueryMsg::ProofsByNode { node_address, start_after, limit } =>
+            to_json_binary(&query::query_proofs_by_node(deps, node_address, start_after, limit)?),
+        QueryMsg::ProofsByTimeRange { start, end, start_after, limit } =>
+            to_json_binary(&query::query_proofs_by_time_range(deps, start, end, start_after, limit)?),
+        QueryMsg::Nodes { start_after, limit, tier, min_reputation } =>
+            to_json_binary(&query::query_nodes(deps, start_after, limit, tier, min_reputation)?),
+        QueryMsg::ApiVersions {} => to_json_binary(&query::api_versions()),
+        QueryMsg::V2(msg) => query_v2(deps, msg),
     }
 }
 
+/// Dispatches version 2 queries (`{"v2": {...}}`).
+fn query_v2(deps: Deps, msg: QueryMsgV2) -> StdResult<Binary> {
+    match msg {
+        QueryMsgV2::Proof { id } => to_json_binary(&query::proof_v2(deps, id)?),
+        QueryMsgV2::ProofByHash { data_hash } => to_json_binary(&query::proof_by_hash_v2(deps, data_hash)?),
+        QueryMsgV2::Proofs { start_after, limit } => to_json_binary(&query::query_proofs_v2(deps, start_after, limit)?),
+    }
+}
+
+/// Handles replies to submessages. Only failed hook notifications reply; they are recorded in a
+/// `detrack_hook_failed` event instead of failing the notifying operation.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match (msg.id, msg.result) {
+        (HOOK_REPLY_ID, SubMsgResult::Err(error)) => {
+            Ok(Response::new().add_event(Event::new("detrack_hook_failed").add_attribute("error", error)))
+        }
+        (id, _) => Err(ContractError::InvalidInput(format!("Unexpected reply {}", id))),
+    }
+}
+
+/// Namespace of a node map never read by any handler; `migrate` drains it.
+pub(crate) const LEGACY_NODES_NAMESPACE: &str = "nodes";
+
+/// Rewrites a config stored with the single `min_reputation_threshold` of older versions into
+/// the per-tier `min_reputation_tier1..3` fields, each holding the old threshold. Runs before
+/// anything loads `CONFIG`, which no longer parses the old layout.
+fn migrate_reputation_thresholds(storage: &mut dyn Storage) -> StdResult<()> {
+    let Some(raw) = storage.get(CONFIG.as_slice()) else {
+        return Ok(());
+    };
+    let mut config: serde_json::Value = serde_json::from_slice(&raw).map_err(|e| StdError::parse_err("Config", e.to_string()))?;
+    let Some(fields) = config.as_object_mut() else {
+        return Ok(());
+    };
+    if let Some(threshold) = fields.remove("min_reputation_threshold") {
+        for tier in 1..=3 {
+            fields.insert(format!("min_reputation_tier{}", tier), threshold.clone());
+        }
+        let migrated = serde_json::to_vec(&config).map_err(|e| StdError::serialize_err("Config", e.to_string()))?;
+        storage.set(CONFIG.as_slice(), &migrated);
+    }
+    Ok(())
+}
+
 /// Handles contract migration.
 /// Updates the contract to a new version using cw2 version management.
 /// Add custom migration logic here if state structure changes between versions.
@@ -147,12 +563,130 @@ pub fn migrate(
     _env: Env,
     _msg: MigrateMsg,
 ) -> Result<Response, ContractError> {
-    // Update contract version using cw2
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    migrate_reputation_thresholds(deps.storage)?;
+
+    // Rebuild the gateway indexes (older versions stored `()` in GATEWAY_PROOFS and had no
+    // time-window index), the worker window, height and owner indexes and the per-worker proof
+    // counts. Every proof is saved
+    // again so its secondary indexes added since (e.g. `tw_start`, `node`) are written.
+    let stored: Vec<(u64, Proof)> = proofs()
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    let heights: BTreeMap<u64, u64> = HEIGHT_PROOFS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(height, id)| (id, height)))
+        .collect::<StdResult<_>>()?;
+    // The energy totals are summed from scratch below
+    let energy_keys: Vec<(String, u64, String)> = WORKER_ENERGY
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (worker_did, bucket, unit) in &energy_keys {
+        WORKER_ENERGY.remove(deps.storage, (worker_did, *bucket, unit));
+    }
+    let mut worker_proof_counts: BTreeMap<String, u64> = BTreeMap::new();
+    for (id, mut proof) in stored {
+        // A record written by a newer build cannot be rewritten to this version
+        if proof.schema_version > PROOF_SCHEMA_VERSION {
+            return Err(ContractError::MigrationIncomplete {
+                reason: format!(
+                    "proof {} is on schema version {}, newer than {}",
+                    id, proof.schema_version, PROOF_SCHEMA_VERSION
+                ),
+            });
+        }
+        if proof.schema_version < PROOF_SCHEMA_VERSION {
+            // Heights were first only kept in HEIGHT_PROOFS; proofs older than that get height 0
+            if proof.stored_height == 0 {
+                proof.stored_height = heights.get(&id).copied().unwrap_or(0);
+            }
+            proof.schema_version = PROOF_SCHEMA_VERSION;
+        }
+        proofs().save(deps.storage, id, &proof)?;
+        HEIGHT_PROOFS.save(deps.storage, (proof.stored_height, id), &())?;
+
+        let entry = GatewayProofEntry::from_proof(&proof);
+        for batch in &proof.batch_metadata {
+            GATEWAY_PROOFS.save(deps.storage, (&batch.gateway_did, id), &entry)?;
+            GATEWAY_TIME_PROOFS.save(deps.storage, (&batch.gateway_did, proof.tw_start.nanos(), id), &())?;
+        }
+        let window_key = (proof.worker_did.as_str(), proof.tw_start.nanos(), proof.tw_end.nanos());
+        if !WORKER_WINDOWS.has(deps.storage, window_key) {
+            WORKER_WINDOWS.save(deps.storage, window_key, &id)?;
+        }
+        for (owner, share) in proof.ownership() {
+            OWNER_PROOFS.save(deps.storage, (&owner, id), &share)?;
+        }
+        if proof.status == ProofStatus::Active && !proof.rejected {
+            record_worker_energy(deps.storage, &proof, false)?;
+        }
+        *worker_proof_counts.entry(proof.worker_did).or_default() += 1;
+    }
+    for (worker_did, count) in &worker_proof_counts {
+        WORKER_PROOF_COUNTS.save(deps.storage, worker_did, count)?;
+    }
     
-    // TODO: Add state migration logic here if needed
-    // Example: If Config structure changed, load old config and save new format
+    // Nothing reads the old `"nodes"` namespace; merge any leftovers into the node registry
+    let legacy_nodes: Map<&Addr, Node> = Map::new(LEGACY_NODES_NAMESPACE);
+    let leftovers: Vec<(Addr, Node)> = legacy_nodes
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (address, node) in leftovers {
+        if !WHITELISTED_NODES.has(deps.storage, address.to_string()) {
+            if let Some(region) = &node.region {
+                REGION_NODES.save(deps.storage, (region, &address), &())?;
+            }
+            WHITELISTED_NODES.save(deps.storage, address.to_string(), &node)?;
+        }
+        legacy_nodes.remove(deps.storage, &address);
+    }
+
+    // Deposits made before multi-denom support were uc4e only
+    let config = CONFIG.load(deps.storage)?;
+    let nodes: Vec<(String, Node)> = WHITELISTED_NODES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (address, mut node) in nodes {
+        if node.deposit_coins.is_empty() && !node.deposit.is_zero() {
+            node.deposit_coins = vec![Coin { denom: "uc4e".to_string(), amount: node.deposit }];
+        }
+        // Records from before onboarding stages default to `Whitelisted`; nodes that already
+        // stored proofs have completed onboarding
+        if node.proof_count > 0 {
+            node.onboarding_stage = OnboardingStage::Operational;
+        }
+        advance_onboarding(&config, &mut node);
+        WHITELISTED_NODES.save(deps.storage, address, &node)?;
+    }
+    let unlocking: Vec<(String, UnlockingDeposit)> = UNLOCKING_DEPOSITS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    let mut unlocking_total = Uint128::zero();
+    for (address, mut entry) in unlocking {
+        unlocking_total += entry.amount;
+        if entry.coins.is_empty() && !entry.amount.is_zero() {
+            entry.coins = vec![Coin { denom: "uc4e".to_string(), amount: entry.amount }];
+            UNLOCKING_DEPOSITS.save(deps.storage, address, &entry)?;
+        }
+    }
+    // Counters read by the alert checks instead of scanning their maps
+    UNLOCKING_TOTAL.save(deps.storage, &unlocking_total)?;
+    let open_disputes = OPEN_DISPUTES.keys(deps.storage, None, None, Order::Ascending).count() as u64;
+    OPEN_DISPUTE_COUNT.save(deps.storage, &open_disputes)?;
+
+    // Rebuild the liability ledger from the records holding funds
+    let stale: Vec<String> = LIABILITIES
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for denom in stale {
+        LIABILITIES.remove(deps.storage, &denom);
+    }
+    for (denom, owed) in tracked_liabilities(deps.storage)? {
+        LIABILITIES.save(deps.storage, &denom, &owed)?;
+    }
     
+    // Update contract version using cw2
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     Ok(Response::new()
         .add_attribute("method", "migrate")
         .add_attribute("version", CONTRACT_VERSION))
@@ -1,3 +1,4 @@
+use crate::msg::{OnboardingStage, Role};
 use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
@@ -12,6 +13,9 @@ pub enum ContractError {
     #[error("Admin only operation")]
     AdminOnlyOperation {},
 
+    #[error("Operation requires the {role:?} role")]
+    MissingRole { role: Role },
+
     #[error("Node not whitelisted: {0}")]
     NodeNotWhitelisted(String),
 
@@ -21,9 +25,33 @@ pub enum ContractError {
     #[error("Insufficient node reputation: {0} (required: {1})")]
     InsufficientNodeReputation(i32, i32),
 
+    #[error("Node {address} is paused")]
+    NodePaused { address: String },
+
+    #[error("Node {address} is not paused")]
+    NodeNotPaused { address: String },
+
+    #[error("Node {address} is jailed (term ends at block {until_block})")]
+    NodeJailed { address: String, until_block: u64 },
+
+    #[error("Node {address} is not jailed")]
+    NodeNotJailed { address: String },
+
+    #[error("Contract is paused")]
+    ContractPaused {},
+
+    #[error("Contract is not paused")]
+    ContractNotPaused {},
+
+    #[error("Reputation {reputation} outside the configured bounds [{min}, {max}]")]
+    ReputationOutOfBounds { reputation: i32, min: i32, max: i32 },
+
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
+    #[error("Invalid {field}: {reason}")]
+    InvalidText { field: String, reason: String },
+
     #[error("Proof already exists: {0}")]
     ProofAlreadyExists(String),
     
@@ -42,6 +70,12 @@ pub enum ContractError {
     #[error("Custom error: {0}")]
     CustomError(String),
 
+    #[error("State invariant violated: {invariant}")]
+    InvariantViolation { invariant: String },
+
+    #[error("Migration incomplete: {reason}")]
+    MigrationIncomplete { reason: String },
+
     #[error("Deposit already unlocking")]
     DepositAlreadyUnlocking {},
 
@@ -54,6 +88,24 @@ pub enum ContractError {
     #[error("No unlocked deposit to claim")]
     NoUnlockedDepositToClaim {},
 
+    #[error("Deposit is frozen while {open_disputes} disputes against the node's proofs are open")]
+    DepositFrozenByDisputes { open_disputes: u64 },
+
+    #[error("No rewards to claim")]
+    NoRewardsToClaim {},
+
+    #[error("Escrow of worker {worker_did} is funded by {owner}")]
+    EscrowOwnedByOther { worker_did: String, owner: String },
+
+    #[error("Escrow of worker {worker_did} holds {balance}, {required} required")]
+    InsufficientEscrow { worker_did: String, balance: Uint128, required: Uint128 },
+
+    #[error("No fees to sweep")]
+    NoFeesToSweep {},
+
+    #[error("No treasury configured")]
+    TreasuryNotConfigured {},
+
     #[error("Insufficient stake. Required: {required}, provided: {provided}")]
     InsufficientStake { required: Uint128, provided: Uint128 },
 
@@ -66,15 +118,117 @@ pub enum ContractError {
     #[error("Node {address} not registered")]
     NodeNotRegistered { address: String },
 
+    #[error("Node {address} was tombstoned by dispute {dispute_id}")]
+    NodeTombstoned { address: String, dispute_id: u64 },
+
     #[error("Node tier {current_tier} is not operational")]
     NodeTierNotOperational { current_tier: u8 },
 
+    #[error("Node {address} has not completed onboarding (stage {stage:?}); see the OnboardingStatus query")]
+    OnboardingIncomplete { address: String, stage: OnboardingStage },
+
     #[error("Node has insufficient deposit. Current: {current_deposit}, Required: {required_deposit} for tier {tier}")]
     NodeHasInsufficientDeposit { current_deposit: Uint128, required_deposit: Uint128, tier: u8 },
 
+    #[error("Tier {tier} proof limit reached ({limit} per period)")]
+    TierProofLimitReached { tier: u8, limit: u64 },
+
+    #[error("Probation proof limit reached ({limit} per period)")]
+    ProbationProofLimitReached { limit: u64 },
+
+    #[error("Operator {operator_did} already runs its limit of {limit} nodes")]
+    OperatorNodeLimitReached { operator_did: String, limit: u32 },
+
+    #[error("Node {address} is on probation")]
+    NodeOnProbation { address: String },
+
+    #[error("Proof {proof_id} does not await an attestation")]
+    AttestationNotRequired { proof_id: u64 },
+
+    #[error("Epoch {epoch} has not ended yet")]
+    EpochNotEnded { epoch: u64 },
+
+    #[error("Epoch {epoch} is already closed")]
+    EpochAlreadyClosed { epoch: u64 },
+
+    #[error("No pending action with ID {action_id}")]
+    PendingActionNotFound { action_id: u64 },
+
+    #[error("Pending action is timelocked until block {executable_at_block}")]
+    PendingActionTimelocked { executable_at_block: u64 },
+
+    #[error("Pre-registration is disabled")]
+    PreRegistrationDisabled {},
+
+    #[error("Address {address} already has an open pre-registration")]
+    PreRegistrationExists { address: String },
+
+    #[error("No pre-registration found for {address}")]
+    PreRegistrationNotFound { address: String },
+
+    #[error("Pre-registration expired at block {expires_at_block}")]
+    PreRegistrationExpired { expires_at_block: u64 },
+
+    #[error("Denomination {denom} is not accepted for deposits")]
+    UnsupportedDepositDenom { denom: String },
+
+    #[error("Invalid deposit denomination config for {denom}: {reason}")]
+    InvalidDepositDenom { denom: String, reason: String },
+
+    #[error("Transfer of {requested}{denom} would spend tracked liabilities (balance: {balance}, liabilities: {liabilities})")]
+    InsufficientFreeBalance { denom: String, balance: Uint128, liabilities: Uint128, requested: Uint128 },
+
     // ============================================================================
     // Phase 1b: DID-Related Errors
     // ============================================================================
+
+    #[error("Worker {worker_did} window is already covered by proof {existing_proof_id} from another node")]
+    ConflictingProof { worker_did: String, existing_proof_id: u64 },
+
+    #[error("Gateway {gateway_did} batch sequence {sequence} is out of order (expected {expected} or later)")]
+    SequenceOutOfOrder { gateway_did: String, sequence: u64, expected: u64 },
+
+    #[error("Gateway {gateway_did} batch sequence {sequence} skips {gap} sequences (max {max_gap})")]
+    SequenceGapTooLarge { gateway_did: String, sequence: u64, gap: u64, max_gap: u64 },
+
+    #[error("Dispute {dispute_id} not found")]
+    DisputeNotFound { dispute_id: u64 },
+
+    #[error("Dispute {dispute_id} is already resolved")]
+    DisputeNotOpen { dispute_id: u64 },
+
+    #[error("{raised_by} already has an open dispute on proof {proof_id}")]
+    DisputeAlreadyOpen { proof_id: u64, raised_by: String },
+
+    #[error("No conflict flagged for proof {proof_id}")]
+    ConflictNotFound { proof_id: u64 },
+
+    #[error("Conflict for proof {proof_id} is already resolved")]
+    ConflictAlreadyResolved { proof_id: u64 },
+
+    #[error("Proof {proof_id} was rejected")]
+    ProofRejected { proof_id: u64 },
+
+    #[error("Proof {proof_id} was revoked")]
+    ProofRevoked { proof_id: u64 },
+
+    #[error("Proof {proof_id} is not attributed to {owner}")]
+    NotProofOwner { proof_id: u64, owner: String },
+
+    #[error("{delegate} is not a delegate of {owner}")]
+    NotOwnerDelegate { owner: String, delegate: String },
+
+    #[error("Proof {proof_id} is already flagged by {owner}")]
+    ProofAlreadyFlagged { proof_id: u64, owner: String },
+
+    #[error("Proof {proof_id} is not flagged")]
+    ProofNotFlagged { proof_id: u64 },
+
+    #[error("Worker {worker_did} is decommissioned")]
+    WorkerDecommissioned { worker_did: String },
+
+    #[error("Worker {worker_did} is assigned to node {node}")]
+    WorkerAssignedToOtherNode { worker_did: String, node: String },
     
     #[error("DID not found: {did}")]
     DidNotFound { did: String },
@@ -82,9 +236,18 @@ pub enum ContractError {
     #[error("Invalid DID format: {did}")]
     InvalidDidFormat { did: String },
 
+    #[error("Gateway {gateway_did} is controlled by {controller}, not by node {node} or its operator")]
+    GatewayNotControlledByNode { gateway_did: String, controller: String, node: String },
+
+    #[error("Gateway {gateway_did} has no reporting cadence declared by {node}")]
+    GatewayNotDeclared { gateway_did: String, node: String },
+
     #[error("DID contract query failed: {reason}")]
     DidContractQueryFailed { reason: String },
 
+    #[error("Invalid data reference: {reason}")]
+    InvalidDataReference { reason: String },
+
     #[error("Empty batch metadata not allowed")]
     EmptyBatchMetadata {},
 
@@ -102,4 +265,73 @@ pub enum ContractError {
 
     #[error("Invalid timestamp format: {timestamp}")]
     InvalidTimestamp { timestamp: String },
-}
\ No newline at end of file
+
+    #[error("Too many snapshots in proof: {total} (max {max})")]
+    TooManySnapshots { total: u64, max: u64 },
+
+    #[error("Batch metadata too large: {size} bytes (max {max})")]
+    BatchMetadataTooLarge { size: usize, max: u32 },
+
+    #[error("Legacy StoreProof submissions are disabled")]
+    LegacyStoreProofDisabled {},
+
+    #[error("No legacy DID mapping for node {node_address}")]
+    LegacyMappingNotFound { node_address: String },
+
+    // ============================================================================
+    // Region Errors
+    // ============================================================================
+
+    #[error("Region not allowed: {region}")]
+    RegionNotAllowed { region: String },
+
+    #[error("Region already exists: {region}")]
+    RegionAlreadyExists { region: String },
+
+    #[error("Invalid region code: {region}")]
+    InvalidRegion { region: String },
+
+    #[error("Invalid site: {reason}")]
+    InvalidSite { reason: String },
+
+    #[error("Invalid owner shares: {reason}")]
+    InvalidOwnerShares { reason: String },
+
+    #[error("Facility not found in registry: {facility_id}")]
+    FacilityNotFound { facility_id: String },
+
+    // ============================================================================
+    // Data Owner Plan Errors
+    // ============================================================================
+
+    #[error("Plan not found: {plan_id}")]
+    PlanNotFound { plan_id: String },
+
+    #[error("Plan {plan_id} cannot be purchased")]
+    PlanNotPurchasable { plan_id: String },
+
+    #[error("Invalid payment. Required: {required}, provided: {provided}")]
+    InvalidPayment { required: Uint128, provided: Uint128 },
+
+    #[error("Data owner {data_owner} has no active plan")]
+    NoActivePlan { data_owner: String },
+
+    #[error("Proof quota exceeded for {data_owner} (limit: {limit} per period)")]
+    ProofQuotaExceeded { data_owner: String, limit: u64 },
+
+    #[error("Metadata allowance exceeded: {size} bytes (limit: {limit})")]
+    MetadataAllowanceExceeded { size: usize, limit: u32 },
+
+    // ============================================================================
+    // Proof Bond Errors
+    // ============================================================================
+
+    #[error("No bond held for proof {proof_id}")]
+    ProofBondNotFound { proof_id: u64 },
+
+    #[error("Bond for proof {proof_id} is locked until block {release_at_block}")]
+    ProofBondLocked { proof_id: u64, release_at_block: u64 },
+
+    #[error("Bond for proof {proof_id} is held while the proof is under review or disputed")]
+    ProofBondContested { proof_id: u64 },
+}
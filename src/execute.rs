@@ -1,10 +1,13 @@
 use crate::error::ContractError;
-use crate::state::{Node, CONFIG, WHITELISTED_NODES, UNLOCKING_DEPOSITS, UnlockingDeposit, proofs, GATEWAY_PROOFS, PROOF_BY_HASH, Proof};
-use crate::msg::BatchInfo;
-use crate::helpers::get_native_staked_amount; // Added import
-use cosmwasm_std::{BankMsg, Event, Coin, Uint128, Timestamp, DepsMut, Env, MessageInfo, Response};
+use crate::state::{Config, Node, CONFIG, WHITELISTED_NODES, UNLOCKING_DEPOSITS, UnlockingDeposit, proofs, GatewayProofEntry, GATEWAY_PROOFS, GATEWAY_TIME_PROOFS, PROOF_BY_HASH, REVOKED_PROOF_HASHES, Proof, REGIONS, REGION_PROOFS, REGION_NODES, OPERATOR_NODES, OPERATOR_NODE_LIMITS, GRID_POINT_PROOFS, HEIGHT_PROOFS, OWNER_PROOFS, ProofOwnerShare, PROOF_SCHEMA_VERSION, Plan, OwnerPlan, QuotaUsage, PLANS, OWNER_PLANS, QUOTA_USAGE, NODE_QUOTA_USAGE, QUOTA_PERIOD_SECONDS, EnergyTotal, EpochStats, EPOCH_STATS, ProofBond, PROOF_BONDS, LEGACY_MAPPINGS, WorkerRetirement, RETIRED_WORKERS, ProofConflict, ConflictResolution, PROBATION_PROOFS, WORKER_WINDOWS, PROOF_CONFLICTS, ProofFlag, PROOF_FLAGS, PROOF_APPROVALS, OWNER_DELEGATES, Dispute, DISPUTES, OPEN_DISPUTES, NODE_OPEN_DISPUTES, HeldDeposit, HELD_DEPOSITS, GatewaySequence, GATEWAY_SEQUENCES, PreRegistration, PRE_REGISTRATIONS, REFERRALS, ROLES, PendingAction, PENDING_ACTIONS, AdminAuditEntry, ADMIN_AUDIT_LOG, LIABILITIES, GatewayCadence, GATEWAY_CADENCES, WorkerAssignment, WORKER_ASSIGNMENTS, WorkerEscrow, WORKER_ESCROWS, HOOK_SUBSCRIBERS, REWARD_EPOCHS, TOMBSTONED_NODES, WORKER_ENERGY, ENERGY_BUCKET_SECONDS, OPEN_DISPUTE_COUNT, UNLOCKING_TOTAL, WORKER_PROOF_COUNTS, AUDIT_COMMITTEES};
+use crate::msg::{AlertThresholds, AuditEvent, AutoJailPolicy, BatchInfo, CarryOverRules, DepositDenom, DetrackHookMsg, DisputeStatus, DisputeVerdict, HookEvent, LegacyMapping, OnboardingStage, OwnerShare, ProbationPolicy, ProofFee, ProofRewards, ProofStatus, ProofVerification, ReputationBounds, ReputationScoring, Role, SequenceGap, Site, SlashDistribution, StoreProofData, TierCapabilities, TimelockedAction, VerifyProofsResponse};
+use crate::contract::CONTRACT_VERSION;
+use crate::helpers::{burn_coins, cw20_token, escape_attribute_value, get_native_staked_amount, is_bidi_control, query_denom_balance, select_audit_committee, send_coins, sha256_hex, PreviewStorage}; // Added import
+use cosmwasm_std::{coins, Addr, CosmosMsg, Event, Coin, Decimal, Uint128, Timestamp, Deps, DepsMut, Env, MessageInfo, Response, Order, StdResult, Storage, SubMsg, to_json_binary, to_json_vec};
+use cw_storage_plus::Bound;
+use std::collections::{BTreeMap, BTreeSet};
 
-/// ADMIN OPERATIONS
+// ADMIN OPERATIONS
 
 /// Validates that the sender is the admin
 fn validate_admin(
@@ -18,6 +21,42 @@ fn validate_admin(
     Ok(())
 }
 
+/// Validates that the sender is the admin or has been granted `role`
+fn validate_role(
+    deps: &DepsMut,
+    info: &MessageInfo,
+    role: Role,
+) -> Result<(), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin && !ROLES.has(deps.storage, (role.key(), &info.sender)) {
+        return Err(ContractError::MissingRole { role });
+    }
+    Ok(())
+}
+
+/// Grants or revokes a role. Only the admin manages roles; it holds every role itself.
+pub fn set_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+    granted: bool,
+) -> Result<Response, ContractError> {
+    validate_admin(&deps, &info)?;
+    let address = deps.api.addr_validate(&address)?;
+
+    if granted {
+        ROLES.save(deps.storage, (role.key(), &address), &())?;
+    } else {
+        ROLES.remove(deps.storage, (role.key(), &address));
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", if granted { "grant_role" } else { "revoke_role" })
+        .add_attribute("role", role.key())
+        .add_attribute("address", address.to_string()))
+}
+
 /// Updates the admin address
 pub fn update_admin(
     deps: DepsMut,
@@ -39,14 +78,17 @@ pub fn update_admin(
         .add_attribute("new_admin", new_admin))
 }
 
-/// Adds a node to the whitelist
+/// Adds a node to the whitelist, at tier 0 (non-operational) unless `initial_tier` onboards it
+/// directly. Either way the node starts without a deposit.
 pub fn whitelist_node(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     node_address: String,
+    initial_tier: Option<u8>,
+    initial_reputation: Option<i32>,
 ) -> Result<Response, ContractError> {
-    validate_admin(&deps, &info)?;
+    validate_role(&deps, &info, Role::Operator)?;
 
     // Validate node address
     let validated_node = deps.api.addr_validate(&node_address)?;
@@ -56,33 +98,66 @@ pub fn whitelist_node(
     if WHITELISTED_NODES.has(deps.storage, node_str.clone()) {
         return Err(ContractError::NodeAlreadyWhitelisted(node_str));
     }
+    ensure_not_tombstoned(deps.storage, &validated_node)?;
+
+    if initial_tier.is_some_and(|tier| !(1..=3).contains(&tier)) {
+        return Err(ContractError::InvalidInput("Tier must be 1, 2 or 3".to_string()));
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let bounds = &config.reputation_bounds;
+    if let Some(reputation) = initial_reputation.filter(|r| !bounds.contains(*r)) {
+        return Err(ContractError::ReputationOutOfBounds { reputation, min: bounds.min, max: bounds.max });
+    }
+    let tier = initial_tier.unwrap_or(0);
+    let reputation = initial_reputation.unwrap_or(0);
     
     // Add node to whitelist with initial reputation
-    let node = Node {
+    let mut node = Node {
         address: validated_node.clone(),
-        reputation: 0,
+        reputation,
         added_at: env.block.time,
-        deposit: Uint128::zero(), // Initialize deposit as zero
-        tier: 0, // Initialize tier as 0
+        deposit: Uint128::zero(), // Initialize deposit as zero; tiered nodes top it up with AddDeposit
+        deposit_coins: vec![],
+        tier, // Tier 0 (non-operational) unless onboarded at a tier
         proof_count: 0,
         disputed_proofs: 0,
         last_updated: env.block.time,
+        region: None,
+        referrer: None,
+        referral_count: 0,
+        topup_deadline_block: None,
+        paused: false,
+        accrued_rewards: Uint128::zero(),
+        escrow_earnings: Uint128::zero(),
+        onboarding_stage: OnboardingStage::Whitelisted,
+        probation_until_block: None,
+        required_attestations: 0,
+        received_attestations: 0,
+        operator_did: None,
+        jailed_until: None,
     };
+    advance_onboarding(&config, &mut node);
     
     WHITELISTED_NODES.save(deps.storage, node_str.clone(), &node)?;
     
     Ok(Response::new()
         .add_attribute("action", "whitelist_node")
-        .add_attribute("node_address", node_str))
+        .add_attribute("node_address", node_str)
+        .add_attribute("tier", tier.to_string())
+        .add_attribute("reputation", reputation.to_string())
+        .add_attribute("onboarding_stage", format!("{:?}", node.onboarding_stage)))
 }
 
-/// Removes a node from the whitelist
+/// Removes a node from the whitelist and refunds its deposit, holding back
+/// `Config.dispute_hold_bps` per open dispute against its proofs until those disputes resolve.
+/// Unclaimed proof rewards return to the reward pool; unclaimed escrow payments are paid to the node.
 pub fn remove_node(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     node_address: String,
 ) -> Result<Response, ContractError> {
-    validate_admin(&deps, &info)?;
+    validate_role(&deps, &info, Role::Operator)?;
     
     // Validate node address
     let validated_node = deps.api.addr_validate(&node_address)?;
@@ -93,36 +168,255 @@ pub fn remove_node(
         return Err(ContractError::NodeNotWhitelisted(node_str.clone()));
     }
     
-    // Remove node from whitelist
-    WHITELISTED_NODES.remove(deps.storage, node_str.clone());
-    
-    Ok(Response::new()
+    let node = WHITELISTED_NODES.load(deps.storage, node_str.clone())?;
+    let mut config = CONFIG.load(deps.storage)?;
+    let escrow_earnings = deregister_node(deps.storage, &mut config, &node)?;
+
+    // Hold back the share of the deposit covering open disputes against the node's proofs and
+    // refund the rest
+    let open_disputes = NODE_OPEN_DISPUTES
+        .prefix(&node.address)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count() as u64;
+    let hold_bps = match (open_disputes, config.dispute_hold_bps) {
+        (0, _) => 0,
+        (_, None) => 10_000,
+        (open, Some(bps)) => open.saturating_mul(u64::from(bps)).min(10_000) as u16,
+    };
+    let mut refund = node.deposit_coins.clone();
+    let held_coins = slash_coins(&mut refund, hold_bps);
+    let held_amount = node.deposit.multiply_ratio(u128::from(hold_bps), 10_000u128);
+    if !held_coins.is_empty() || !held_amount.is_zero() {
+        let mut held = HELD_DEPOSITS.may_load(deps.storage, &node.address)?.unwrap_or(HeldDeposit {
+            amount: Uint128::zero(),
+            coins: vec![],
+            held_at: env.block.time,
+        });
+        held.amount += held_amount;
+        add_coins(&mut held.coins, held_coins.clone());
+        HELD_DEPOSITS.save(deps.storage, &node.address, &held)?;
+    }
+
+    let format_coins = |coins: &[Coin]| {
+        if coins.is_empty() {
+            "none".to_string()
+        } else {
+            coins.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(",")
+        }
+    };
+    let response = Response::new()
         .add_attribute("action", "remove_node")
-        .add_attribute("node_address", node_str))
+        .add_attribute("node_address", node_str)
+        .add_attribute("open_disputes", open_disputes.to_string())
+        .add_attribute("held", format_coins(&held_coins))
+        .add_attribute("refunded", format_coins(&refund))
+        .add_attribute("escrow_earnings_paid", format_coins(&escrow_earnings));
+    settle(deps.storage, &refund)?;
+    add_coins(&mut refund, escrow_earnings);
+    Ok(response.add_messages(checked_payouts(deps.as_ref(), &env, vec![(node.address, refund)])?))
+}
+
+/// Default for `Config.node_batch_limit`
+pub const DEFAULT_NODE_BATCH_LIMIT: u32 = 50;
+
+/// Whitelists each address as `whitelist_node` does at tier 0. Addresses are handled
+/// independently: one that fails (e.g. already whitelisted or tombstoned) is skipped with its
+/// error as result and the others are still whitelisted. Each address gets a
+/// `detrack_node_batch_result` event (see `add_batch_result`) with `whitelisted` or the error.
+///
+/// Errors:
+/// - `InvalidInput` if the list is empty, has duplicates or exceeds `Config.node_batch_limit`
+pub fn whitelist_nodes(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    node_addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::Operator)?;
+    validate_node_batch(deps.storage, &node_addresses)?;
+
+    let mut response = Response::new().add_attribute("action", "whitelist_nodes");
+    let mut whitelisted = 0;
+    for (index, node_address) in node_addresses.into_iter().enumerate() {
+        let result = run_isolated(&mut deps, |deps| whitelist_node(deps, env.clone(), info.clone(), node_address.clone(), None, None));
+        if result.is_ok() {
+            whitelisted += 1;
+        }
+        response = add_batch_result(response, index, &node_address, result, "whitelisted");
+    }
+    Ok(response.add_attribute("whitelisted", whitelisted.to_string()))
+}
+
+/// Removes each node as `remove_node` does, refunding its deposit. Addresses are handled
+/// independently: one that fails (e.g. not whitelisted) is skipped with its error as result and
+/// the others are still removed. Each address gets a `detrack_node_batch_result` event (see
+/// `add_batch_result`) with `removed` or the error.
+///
+/// Errors:
+/// - `InvalidInput` if the list is empty, has duplicates or exceeds `Config.node_batch_limit`
+pub fn remove_nodes(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    node_addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::Operator)?;
+    validate_node_batch(deps.storage, &node_addresses)?;
+
+    let mut response = Response::new().add_attribute("action", "remove_nodes");
+    let mut removed = 0;
+    for (index, node_address) in node_addresses.into_iter().enumerate() {
+        let result = run_isolated(&mut deps, |deps| remove_node(deps, env.clone(), info.clone(), node_address.clone()));
+        if result.is_ok() {
+            removed += 1;
+        }
+        response = add_batch_result(response, index, &node_address, result, "removed");
+    }
+    Ok(response.add_attribute("removed", removed.to_string()))
+}
+
+/// Adds the `detrack_node_batch_result` event of one batch address, with the fixed keys `index`
+/// (position in the list), `address` (omitted when empty, as attribute values cannot be) and
+/// `result` (`success` or the error), since caller input cannot be an attribute key. A
+/// successful item's attributes are carried on the event and its messages and events forwarded,
+/// as the single-address message would have returned them.
+fn add_batch_result(
+    response: Response,
+    index: usize,
+    node_address: &str,
+    result: Result<Response, ContractError>,
+    success: &str,
+) -> Response {
+    let mut event = Event::new("detrack_node_batch_result").add_attribute("index", index.to_string());
+    if !node_address.is_empty() {
+        event = event.add_attribute("address", escape_attribute_value(node_address));
+    }
+    match result {
+        Ok(item) => response
+            .add_submessages(item.messages)
+            .add_event(event.add_attribute("result", success).add_attributes(item.attributes))
+            .add_events(item.events),
+        Err(err) => response.add_event(event.add_attribute("result", escape_attribute_value(&err.to_string()))),
+    }
+}
+
+/// Checks a `WhitelistNodes`/`RemoveNodes` address list against `Config.node_batch_limit`
+fn validate_node_batch(storage: &dyn Storage, node_addresses: &[String]) -> Result<(), ContractError> {
+    let limit = CONFIG.load(storage)?.node_batch_limit.unwrap_or(DEFAULT_NODE_BATCH_LIMIT) as usize;
+    if node_addresses.is_empty() {
+        return Err(ContractError::InvalidInput("At least one node address is required".to_string()));
+    }
+    if node_addresses.len() > limit {
+        return Err(ContractError::InvalidInput(format!("At most {} node addresses can be listed at once", limit)));
+    }
+    if node_addresses.iter().collect::<BTreeSet<_>>().len() != node_addresses.len() {
+        return Err(ContractError::InvalidInput("Duplicate node address".to_string()));
+    }
+    Ok(())
+}
+
+/// Runs `handler` against a `PreviewStorage` over the storage and applies its writes only if it
+/// succeeds, so a failed item of a batch leaves nothing behind.
+fn run_isolated(
+    deps: &mut DepsMut,
+    handler: impl FnOnce(DepsMut) -> Result<Response, ContractError>,
+) -> Result<Response, ContractError> {
+    let mut overlay = PreviewStorage::new(&*deps.storage);
+    let response = handler(DepsMut { storage: &mut overlay, api: deps.api, querier: deps.querier })?;
+    for (key, value) in overlay.into_writes() {
+        match value {
+            Some(value) => deps.storage.set(&key, &value),
+            None => deps.storage.remove(&key),
+        }
+    }
+    Ok(response)
+}
+
+/// Sets how many addresses one `WhitelistNodes` or `RemoveNodes` message may list. `None`
+/// restores `DEFAULT_NODE_BATCH_LIMIT`.
+pub fn configure_node_batch_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_nodes: Option<u32>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+    if max_nodes == Some(0) {
+        return Err(ContractError::InvalidInput("max_nodes must be positive".to_string()));
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.node_batch_limit = max_nodes;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_node_batch_limit")
+        .add_attribute("max_nodes", max_nodes.map_or_else(|| "none".to_string(), |v| v.to_string())))
+}
+
+/// Removes `node` from the registry and the region and operator indexes. Unclaimed proof rewards
+/// go back to the reward pool; the config is saved if they do. Unclaimed escrow payments were
+/// earned from data owners, so they are settled and returned for the caller to pay to the node.
+/// The deposit is left to the caller.
+fn deregister_node(storage: &mut dyn Storage, config: &mut Config, node: &Node) -> StdResult<Vec<Coin>> {
+    if let Some(region) = &node.region {
+        REGION_NODES.remove(storage, (region, &node.address));
+    }
+    if let Some(operator_did) = &node.operator_did {
+        OPERATOR_NODES.remove(storage, (operator_did, &node.address));
+    }
+    WHITELISTED_NODES.remove(storage, node.address.to_string());
+    if !node.accrued_rewards.is_zero() {
+        config.reward_pool += node.accrued_rewards;
+        CONFIG.save(storage, config)?;
+    }
+    if node.escrow_earnings.is_zero() {
+        return Ok(vec![]);
+    }
+    let earnings = coins(node.escrow_earnings.u128(), "uc4e");
+    settle(storage, &earnings)?;
+    Ok(earnings)
+}
+
+/// Fails with `NodeTombstoned` if a dispute ruling permanently barred `address`.
+fn ensure_not_tombstoned(storage: &dyn Storage, address: &Addr) -> Result<(), ContractError> {
+    match TOMBSTONED_NODES.may_load(storage, address)? {
+        Some(dispute_id) => Err(ContractError::NodeTombstoned { address: address.to_string(), dispute_id }),
+        None => Ok(()),
+    }
 }
 
-/// Updates a node's reputation
+/// Updates a node's reputation; the new value must lie within `Config.reputation_bounds`.
+/// A change larger than `Config.max_reputation_delta` is scheduled as a timelocked action
+/// instead, so a compromised key cannot reset reputations across the network at once.
 pub fn update_node_reputation(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     node_address: String,
     reputation: i32,
 ) -> Result<Response, ContractError> {
-    validate_admin(&deps, &info)?;
+    validate_role(&deps, &info, Role::Operator)?;
     
     // Validate node address
     let validated_node = deps.api.addr_validate(&node_address)?;
     let node_str = validated_node.to_string();
     
     // Check if node is whitelisted
-    if !WHITELISTED_NODES.has(deps.storage, node_str.clone()) {
+    let Some(node) = WHITELISTED_NODES.may_load(deps.storage, node_str.clone())? else {
         return Err(ContractError::NodeNotWhitelisted(node_str));
+    };
+
+    let config = CONFIG.load(deps.storage)?;
+    let bounds = &config.reputation_bounds;
+    if !bounds.contains(reputation) {
+        return Err(ContractError::ReputationOutOfBounds { reputation, min: bounds.min, max: bounds.max });
     }
-    
-    // Update node reputation
-    let mut node = WHITELISTED_NODES.load(deps.storage, node_str.clone())?;
-    node.reputation = reputation;
-    WHITELISTED_NODES.save(deps.storage, node_str.clone(), &node)?;
+
+    let delta = (i64::from(reputation) - i64::from(node.reputation)).unsigned_abs();
+    if config.max_reputation_delta.is_some_and(|max| delta > u64::from(max)) {
+        return schedule_action(deps, &env, &info, TimelockedAction::SetNodeReputation { node_address: node_str, reputation });
+    }
+    set_node_reputation(deps.storage, &config, &node_str, reputation)?;
     
     Ok(Response::new()
         .add_attribute("action", "update_node_reputation")
@@ -130,24 +424,115 @@ pub fn update_node_reputation(
         .add_attribute("reputation", reputation.to_string()))
 }
 
-/// Updates the minimum reputation threshold
+/// Sets a whitelisted node's reputation, re-checking `Config.reputation_bounds`, and advances
+/// its onboarding.
+fn set_node_reputation(storage: &mut dyn Storage, config: &Config, node_address: &str, reputation: i32) -> Result<(), ContractError> {
+    let mut node = WHITELISTED_NODES
+        .may_load(storage, node_address.to_string())?
+        .ok_or_else(|| ContractError::NodeNotWhitelisted(node_address.to_string()))?;
+    let bounds = &config.reputation_bounds;
+    if !bounds.contains(reputation) {
+        return Err(ContractError::ReputationOutOfBounds { reputation, min: bounds.min, max: bounds.max });
+    }
+    node.reputation = reputation;
+    advance_onboarding(config, &mut node);
+    WHITELISTED_NODES.save(storage, node_address.to_string(), &node)?;
+    Ok(())
+}
+
+/// Updates the minimum reputation of one tier (1-3), or of every tier when `tier` is `None`.
+/// Nodes below their tier's minimum can no longer store proofs.
 pub fn update_min_reputation_threshold(
     deps: DepsMut,
     info: MessageInfo,
     threshold: i32,
+    tier: Option<u8>,
 ) -> Result<Response, ContractError> {
-    validate_admin(&deps, &info)?;
+    validate_role(&deps, &info, Role::ConfigManager)?;
     
     // Update the threshold in config
     let mut config = CONFIG.load(deps.storage)?;
-    config.min_reputation_threshold = threshold;
+    match tier {
+        None => {
+            config.min_reputation_tier1 = threshold;
+            config.min_reputation_tier2 = threshold;
+            config.min_reputation_tier3 = threshold;
+        }
+        Some(1) => config.min_reputation_tier1 = threshold,
+        Some(2) => config.min_reputation_tier2 = threshold,
+        Some(3) => config.min_reputation_tier3 = threshold,
+        Some(_) => return Err(ContractError::InvalidInput("Tier must be 1, 2 or 3".to_string())),
+    }
     CONFIG.save(deps.storage, &config)?;
     
     Ok(Response::new()
         .add_attribute("action", "update_min_reputation_threshold")
+        .add_attribute("tier", tier.map_or("all".to_string(), |t| t.to_string()))
         .add_attribute("threshold", threshold.to_string()))
 }
 
+/// Updates instantiation parameters without a migration. `min_stakes` and `deposits` hold the
+/// Tier 1-3 values; `None` entries and fields keep their current value. Existing nodes keep
+/// their tier and deposit: raised deposits surface as shortfalls (see `check_deposit_shortfalls`)
+/// and changed stake thresholds apply when a node's tier is next evaluated.
+///
+/// Errors:
+/// - `InvalidInput` if the resulting stake thresholds or deposits decrease from Tier 1 to
+///   Tier 3, or `max_batch_size` is 0
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    min_stakes: [Option<Uint128>; 3],
+    deposits: [Option<Uint128>; 3],
+    deposit_unlock_period_blocks: Option<u64>,
+    max_batch_size: Option<u32>,
+    use_whitelist: Option<bool>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut response = Response::new().add_attribute("action", "update_config");
+    let stake_fields = [&mut config.min_stake_tier1, &mut config.min_stake_tier2, &mut config.min_stake_tier3];
+    for (tier, (field, value)) in stake_fields.into_iter().zip(min_stakes).enumerate() {
+        if let Some(value) = value {
+            *field = value;
+            response = response.add_attribute(format!("min_stake_tier{}", tier + 1), value.to_string());
+        }
+    }
+    let deposit_fields = [&mut config.deposit_tier1, &mut config.deposit_tier2, &mut config.deposit_tier3];
+    for (tier, (field, value)) in deposit_fields.into_iter().zip(deposits).enumerate() {
+        if let Some(value) = value {
+            *field = value;
+            response = response.add_attribute(format!("deposit_tier{}", tier + 1), value.to_string());
+        }
+    }
+    if let Some(blocks) = deposit_unlock_period_blocks {
+        config.deposit_unlock_period_blocks = blocks;
+        response = response.add_attribute("deposit_unlock_period_blocks", blocks.to_string());
+    }
+    if let Some(size) = max_batch_size {
+        if size == 0 {
+            return Err(ContractError::InvalidInput("max_batch_size must be at least 1".to_string()));
+        }
+        config.max_batch_size = size;
+        response = response.add_attribute("max_batch_size", size.to_string());
+    }
+    if let Some(use_whitelist) = use_whitelist {
+        config.use_whitelist = use_whitelist;
+        response = response.add_attribute("use_whitelist", use_whitelist.to_string());
+    }
+
+    if config.min_stake_tier1 > config.min_stake_tier2 || config.min_stake_tier2 > config.min_stake_tier3 {
+        return Err(ContractError::InvalidInput("Tier stake thresholds must not decrease from Tier 1 to Tier 3".to_string()));
+    }
+    if config.deposit_tier1 > config.deposit_tier2 || config.deposit_tier2 > config.deposit_tier3 {
+        return Err(ContractError::InvalidInput("Tier deposits must not decrease from Tier 1 to Tier 3".to_string()));
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(response)
+}
+
 /// Configures the treasury address
 pub fn configure_treasury(
     deps: DepsMut,
@@ -169,604 +554,4974 @@ pub fn configure_treasury(
         .add_attribute("treasury", treasury_address))
 }
 
-/// NODE OPERATIONS
+/// Sets or clears the facility registry contract.
+/// When set, `store_proof` verifies that `site.facility_id` exists in the registry.
+pub fn configure_facility_registry(
+    deps: DepsMut,
+    info: MessageInfo,
+    registry_address: Option<String>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
 
-/// Validates that the sender is a whitelisted node with sufficient reputation
-fn validate_node(
-    deps: &DepsMut,
-    info: &MessageInfo,
-) -> Result<(), ContractError> {
-    let sender = info.sender.to_string();
-    
-    // Check if node is whitelisted
-    if !WHITELISTED_NODES.has(deps.storage, sender.clone()) {
-        return Err(ContractError::NodeNotWhitelisted(sender));
-    }
-    
-    // Check if node has sufficient reputation
-    let node = WHITELISTED_NODES.load(deps.storage, sender.clone())?;
-    let config = CONFIG.load(deps.storage)?;
-    
-    if node.reputation < config.min_reputation_threshold {
-        return Err(ContractError::InsufficientNodeReputation(node.reputation, config.min_reputation_threshold));
-    }
-    
-    // Check if node tier is operational (tier 0 is for whitelisted but non-operational nodes)
-    if node.tier == 0 {
-        return Err(ContractError::NodeTierNotOperational { current_tier: node.tier });
-    }
-    
-    Ok(())
+    let validated_registry = registry_address
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.facility_registry_address = validated_registry.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_facility_registry")
+        .add_attribute(
+            "registry_address",
+            validated_registry.map_or_else(|| "none".to_string(), |addr| addr.to_string()),
+        ))
 }
 
-// ============================================================================
-// NODE OPERATIONS - Phase 1b (DID-First Architecture)
-// ============================================================================
+/// Replaces the DID contract every DID is verified against. Existing proofs and nodes keep
+/// their DIDs; only verifications from now on query the new contract.
+pub fn update_did_contract(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    validate_admin(&deps, &info)?;
 
-/// Verify DID exists and is active in the DID Contract
-/// 
-/// This function queries the DID Contract to ensure the provided DID is registered
-/// and follows the correct format for the expected type (worker or gateway).
-/// 
-/// # Arguments
-/// * `deps` - Dependencies for querying
-/// * `did` - The W3C DID to verify (e.g., "did:c4e:worker:detrack1")
-/// * `expected_type` - Expected DID type ("worker" or "gateway")
-/// 
-/// # Returns
-/// * `Ok(())` if DID is valid and registered
-/// * `Err(ContractError)` if DID is invalid or not found
-fn verify_did(
-    _deps: &cosmwasm_std::Deps,
-    did: &str,
-    expected_type: &str,
-) -> Result<(), ContractError> {
-    // Validate DID format
-    if !did.starts_with(&format!("did:c4e:{}:", expected_type)) {
-        return Err(ContractError::InvalidDidFormat { did: did.to_string() });
-    }
-    
-    // Skip DID Contract query in test mode (no real DID Contract available)
-    #[cfg(test)]
-    {
-        return Ok(());
+    let validated = deps.api.addr_validate(&address)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    let previous = std::mem::replace(&mut config.did_contract_address, validated.clone());
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_did_contract")
+        .add_attribute("previous_address", previous.to_string())
+        .add_attribute("did_contract_address", validated.to_string()))
+}
+
+/// Sets (or clears) the aggregate per-proof limits checked by `store_proof`:
+/// the total `snapshot_count` across batches and the serialized size of `batch_metadata`.
+pub fn configure_proof_limits(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_snapshots_per_proof: Option<u64>,
+    max_batch_metadata_bytes: Option<u32>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.max_snapshots_per_proof = max_snapshots_per_proof;
+    config.max_batch_metadata_bytes = max_batch_metadata_bytes;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_proof_limits")
+        .add_attribute(
+            "max_snapshots_per_proof",
+            max_snapshots_per_proof.map_or_else(|| "none".to_string(), |v| v.to_string()),
+        )
+        .add_attribute(
+            "max_batch_metadata_bytes",
+            max_batch_metadata_bytes.map_or_else(|| "none".to_string(), |v| v.to_string()),
+        ))
+}
+
+/// Resolves a flagged conflict by admin ruling. The canonical proof must be one of the two
+/// proofs of the conflict; the other is marked rejected. The canonical submitter gains and
+/// the rejected submitter loses `reputation_delta` (clamped to `Config.reputation_bounds`), and the rejected submitter's
+/// `disputed_proofs` is incremented. Submitters no longer registered are skipped. The rejected
+/// proof's bond, if still held, is forfeited as `forfeit_proof_bond` does, whether or not the
+/// proof is final.
+pub fn resolve_proof_conflict(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    conflicting_proof_id: u64,
+    canonical_proof_id: u64,
+    reputation_delta: u32,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::Slasher)?;
+
+    let mut conflict = PROOF_CONFLICTS
+        .may_load(deps.storage, conflicting_proof_id)?
+        .ok_or(ContractError::ConflictNotFound { proof_id: conflicting_proof_id })?;
+    if conflict.resolution.is_some() {
+        return Err(ContractError::ConflictAlreadyResolved { proof_id: conflicting_proof_id });
     }
-    
-    // Production: Query DID Contract to verify DID exists
-    #[cfg(not(test))]
-    {
-    use cosmwasm_std::{to_json_binary, WasmQuery, QueryRequest};
-    use serde::{Deserialize, Serialize};
-    
-    // Load DID contract address from config
-    let config = CONFIG.load(_deps.storage)?;
-    
-    // Query DID contract to verify DID exists
-    #[derive(Serialize)]
-    #[serde(rename_all = "snake_case")]
-    enum DidQueryMsg {
-        GetDidDocument { did: String },
+    let rejected_proof_id = if canonical_proof_id == conflicting_proof_id {
+        conflict.original_proof_id
+    } else if canonical_proof_id == conflict.original_proof_id {
+        conflicting_proof_id
+    } else {
+        return Err(ContractError::InvalidInput(format!(
+            "Proof {} is not part of the conflict for proof {}",
+            canonical_proof_id, conflicting_proof_id
+        )));
+    };
+
+    let mut rejected = proofs().load(deps.storage, rejected_proof_id)?;
+    if !rejected.rejected && rejected.status == ProofStatus::Active {
+        record_worker_energy(deps.storage, &rejected, true)?;
     }
-    
-    #[derive(Deserialize)]
-    #[allow(dead_code)]
-    struct DidDocumentResponse {
-        id: String,
-        controller: String,
-        service: Vec<serde_json::Value>,
+    rejected.rejected = true;
+    proofs().save(deps.storage, rejected_proof_id, &rejected)?;
+    let canonical = proofs().load(deps.storage, canonical_proof_id)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut payouts = vec![];
+    let forfeited_bond = match PROOF_BONDS.may_load(deps.storage, rejected_proof_id)? {
+        Some(bond) => {
+            PROOF_BONDS.remove(deps.storage, rejected_proof_id);
+            let forfeited = coins(bond.amount.u128(), "uc4e");
+            settle(deps.storage, &forfeited)?;
+            if let Some(treasury) = config.treasury.clone() {
+                payouts.push((treasury, forfeited));
+            }
+            bond.amount
+        }
+        None => Uint128::zero(),
+    };
+
+    let bounds = config.reputation_bounds;
+    let delta = i64::from(reputation_delta);
+    if let Some(mut node) = WHITELISTED_NODES.may_load(deps.storage, canonical.stored_by.to_string())? {
+        node.reputation = bounds.apply(node.reputation, delta);
+        node.last_updated = env.block.time;
+        WHITELISTED_NODES.save(deps.storage, canonical.stored_by.to_string(), &node)?;
     }
-    
-    let query_msg = DidQueryMsg::GetDidDocument { did: did.to_string() };
-    let query_request: QueryRequest<cosmwasm_std::Empty> = QueryRequest::Wasm(WasmQuery::Smart {
-        contract_addr: config.did_contract_address.to_string(),
-        msg: to_json_binary(&query_msg)?,
-    });
-    
-    let response: Result<DidDocumentResponse, _> = _deps.querier.query(&query_request);
-    
-    match response {
-        Ok(_doc) => Ok(()),
-        Err(_) => Err(ContractError::DidNotFound { did: did.to_string() }),
+    if let Some(mut node) = WHITELISTED_NODES.may_load(deps.storage, rejected.stored_by.to_string())? {
+        node.reputation = bounds.apply(node.reputation, -delta);
+        node.disputed_proofs += 1;
+        node.last_updated = env.block.time;
+        WHITELISTED_NODES.save(deps.storage, rejected.stored_by.to_string(), &node)?;
     }
-    } // end cfg(not(test))
+
+    conflict.resolution = Some(ConflictResolution { canonical_proof_id, resolved_at: env.block.time });
+    PROOF_CONFLICTS.save(deps.storage, conflicting_proof_id, &conflict)?;
+
+    Ok(Response::new()
+        .add_messages(checked_payouts(deps.as_ref(), &env, payouts)?)
+        .add_attribute("action", "resolve_proof_conflict")
+        .add_attribute("conflicting_proof_id", conflicting_proof_id.to_string())
+        .add_attribute("canonical_proof_id", canonical_proof_id.to_string())
+        .add_attribute("rejected_proof_id", rejected_proof_id.to_string())
+        .add_attribute("reputation_delta", reputation_delta.to_string())
+        .add_attribute("forfeited_bond", forfeited_bond.to_string()))
 }
 
-/// Stores a new proof on the blockchain (Phase 1b: Multi-batch aggregation)
-/// 
-/// Access Control: Only whitelisted nodes with sufficient reputation can store proofs.
-/// DID Verification: Verifies worker_did and all gateway_dids in batch_metadata.
-/// 
-/// Logic:
-/// - Validates the calling node (whitelist + reputation)
-/// - Verifies Worker DID exists in DID Contract
-/// - Verifies all Gateway DIDs in batch_metadata
-/// - Validates batch_metadata (not empty, not too many batches)
-/// - Checks data hash validity and uniqueness
-/// - Creates and saves proof with IndexedMap
-/// - Indexes by gateway DIDs for efficient queries
-/// 
-/// Events: Emits attributes for "store_proof", "proof_id", "worker_did", "data_hash", etc.
-/// 
-/// Errors:
-/// - `InvalidDidFormat` if DIDs don't match expected format
-/// - `DidNotFound` if any DID is not registered
-/// - `EmptyBatchMetadata` if no batches provided
-/// - `TooManyBatches` if more than 100 batches
-/// - `ProofAlreadyExists` if hash already exists
-/// - `InvalidInput` for validation failures
-pub fn store_proof(
+/// Closes the review of a flagged proof: removes its owner flags and clears `under_review`.
+/// Acting on the flags (e.g. ruling a conflict) is a separate step.
+pub fn clear_proof_flags(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
-    worker_did: String,
-    data_hash: String,
-    tw_start: Timestamp,
-    tw_end: Timestamp,
-    batch_metadata: Vec<BatchInfo>,
-    original_data_reference: Option<String>,
-    metadata_json: Option<String>,
+    proof_id: u64,
 ) -> Result<Response, ContractError> {
-    // Validate calling node
-    validate_node(&deps, &info)?;
+    validate_role(&deps, &info, Role::Operator)?;
+
+    let flags = PROOF_FLAGS
+        .may_load(deps.storage, proof_id)?
+        .ok_or(ContractError::ProofNotFlagged { proof_id })?;
+    PROOF_FLAGS.remove(deps.storage, proof_id);
+    let mut proof = proofs().load(deps.storage, proof_id)?;
+    proof.under_review = false;
+    proofs().save(deps.storage, proof_id, &proof)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "clear_proof_flags")
+        .add_attribute("proof_id", proof_id.to_string())
+        .add_attribute("flag_count", flags.len().to_string()))
+}
+
+/// Chooses whether proofs conflicting with another node's proof for the same worker window
+/// are rejected or stored and flagged.
+pub fn configure_conflicting_proofs(
+    deps: DepsMut,
+    info: MessageInfo,
+    reject: bool,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.reject_conflicting_proofs = reject;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_conflicting_proofs")
+        .add_attribute("reject", reject.to_string()))
+}
+
+/// Sets the capability limits of one node tier, replacing any previous entry.
+pub fn set_tier_capabilities(
+    deps: DepsMut,
+    info: MessageInfo,
+    capabilities: TierCapabilities,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    if !(1..=3).contains(&capabilities.tier) {
+        return Err(ContractError::InvalidInput("Tier must be 1, 2 or 3".to_string()));
+    }
+    if capabilities.max_batch_size == 0 {
+        return Err(ContractError::InvalidInput("Tier max_batch_size must be positive".to_string()));
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.tier_capabilities.retain(|c| c.tier != capabilities.tier);
+    config.tier_capabilities.push(capabilities.clone());
+    config.tier_capabilities.sort_by_key(|c| c.tier);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_tier_capabilities")
+        .add_attribute("tier", capabilities.tier.to_string())
+        .add_attribute("max_batch_size", capabilities.max_batch_size.to_string())
+        .add_attribute(
+            "max_proofs_per_period",
+            capabilities.max_proofs_per_period.map_or_else(|| "none".to_string(), |v| v.to_string()),
+        ))
+}
+
+/// Removes a tier's capability entry, returning it to the global defaults.
+pub fn remove_tier_capabilities(
+    deps: DepsMut,
+    info: MessageInfo,
+    tier: u8,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.tier_capabilities.retain(|c| c.tier != tier);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_tier_capabilities")
+        .add_attribute("tier", tier.to_string()))
+}
+
+/// Accepts a non-native deposit denom at the given uc4e-equivalent weight, or stops
+/// accepting it when `weight` is `None`. A CW20 token is accepted as `cw20:<token address>`
+/// and deposited through `ExecuteMsg::Receive`. Coins already deposited in a removed denom stay
+/// counted and are still refunded in that denom.
+pub fn set_deposit_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    weight: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    if denom == "uc4e" {
+        return Err(ContractError::InvalidDepositDenom {
+            denom,
+            reason: "the native denom is always accepted at face value".to_string(),
+        });
+    }
+    if denom.is_empty() {
+        return Err(ContractError::InvalidDepositDenom { denom, reason: "denom is empty".to_string() });
+    }
+    if let Some(token) = cw20_token(&denom) {
+        if deps.api.addr_validate(token).is_err() {
+            return Err(ContractError::InvalidDepositDenom { denom, reason: "invalid CW20 token address".to_string() });
+        }
+    }
+    if weight.is_some_and(|w| w.is_zero()) {
+        return Err(ContractError::InvalidDepositDenom { denom, reason: "weight must be positive".to_string() });
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.deposit_denoms.retain(|d| d.denom != denom);
+    if let Some(weight) = weight {
+        config.deposit_denoms.push(DepositDenom { denom: denom.clone(), weight });
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_deposit_denom")
+        .add_attribute("denom", denom)
+        .add_attribute("weight", weight.map_or_else(|| "none".to_string(), |w| w.to_string())))
+}
+
+/// Maximum length of a region/zone code
+const MAX_REGION_LENGTH: usize = 64;
+
+/// Adds a region/zone code to the list accepted on nodes and proofs.
+/// Region codes are short identifiers (e.g. "PL", "EU-CENTRAL", "PL:PSE") made of
+/// ASCII alphanumerics, '-', '_' and ':'.
+pub fn add_region(
+    deps: DepsMut,
+    info: MessageInfo,
+    region: String,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    if region.is_empty()
+        || region.len() > MAX_REGION_LENGTH
+        || !region.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ':')
+    {
+        return Err(ContractError::InvalidRegion { region });
+    }
+
+    if REGIONS.has(deps.storage, &region) {
+        return Err(ContractError::RegionAlreadyExists { region });
+    }
+
+    REGIONS.save(deps.storage, &region, &())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_region")
+        .add_attribute("region", region))
+}
+
+/// Removes a region/zone code from the accepted list.
+/// Nodes and proofs already tagged with the region keep it and remain queryable by it;
+/// only new assignments are rejected.
+pub fn remove_region(
+    deps: DepsMut,
+    info: MessageInfo,
+    region: String,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    if !REGIONS.has(deps.storage, &region) {
+        return Err(ContractError::RegionNotAllowed { region });
+    }
+
+    REGIONS.remove(deps.storage, &region);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_region")
+        .add_attribute("region", region))
+}
+
+/// Checks that an optional region code is in the admin-managed region list
+fn ensure_region_allowed(
+    storage: &dyn Storage,
+    region: &Option<String>,
+) -> Result<(), ContractError> {
+    if let Some(region) = region {
+        if !REGIONS.has(storage, region) {
+            return Err(ContractError::RegionNotAllowed { region: region.clone() });
+        }
+    }
+    Ok(())
+}
+
+// NODE OPERATIONS
+
+/// Validates that the sender is a whitelisted, unpaused and unjailed node with the reputation its
+/// tier requires
+fn validate_node(
+    deps: &DepsMut,
+    info: &MessageInfo,
+) -> Result<(), ContractError> {
+    let sender = info.sender.to_string();
     
-    let node = WHITELISTED_NODES.load(deps.storage, info.sender.to_string())
-        .map_err(|_| ContractError::NodeNotRegistered { address: info.sender.to_string() })?;
+    // Check if node is whitelisted
+    if !WHITELISTED_NODES.has(deps.storage, sender.clone()) {
+        return Err(ContractError::NodeNotWhitelisted(sender));
+    }
     
-    let mut config = CONFIG.load(deps.storage)?;
+    // Check if node has sufficient reputation
+    let node = WHITELISTED_NODES.load(deps.storage, sender.clone())?;
+    let config = CONFIG.load(deps.storage)?;
     
-    // Validate node tier and deposit
-    if !(1..=3).contains(&node.tier) {
-        return Err(ContractError::NodeTierNotOperational { current_tier: node.tier });
+    let required = tier_reputation_requirement(&config, node.tier);
+    if node.reputation < required {
+        return Err(ContractError::InsufficientNodeReputation(node.reputation, required));
     }
     
-    let required_deposit_for_tier = match node.tier {
-        3 => config.deposit_tier3,
-        2 => config.deposit_tier2,
-        1 => config.deposit_tier1,
-        _ => return Err(ContractError::NodeTierNotOperational { current_tier: node.tier }),
-    };
+    // Check if node tier is operational (tier 0 is for whitelisted but non-operational nodes)
+    if node.tier == 0 {
+        return Err(ContractError::OnboardingIncomplete { address: sender, stage: node.onboarding_stage });
+    }
+
+    if node.paused {
+        return Err(ContractError::NodePaused { address: sender });
+    }
+
+    if let Some(until_block) = node.jailed_until {
+        return Err(ContractError::NodeJailed { address: sender, until_block });
+    }
     
-    if node.deposit < required_deposit_for_tier {
-        return Err(ContractError::NodeHasInsufficientDeposit {
-            required_deposit: required_deposit_for_tier,
-            current_deposit: node.deposit,
-            tier: node.tier,
-        });
+    Ok(())
+}
+
+// ============================================================================
+// NODE OPERATIONS - Phase 1b (DID-First Architecture)
+// ============================================================================
+
+/// Maximum length of a DID
+const MAX_DID_LENGTH: usize = 256;
+
+/// DID prefixes accepted while `Config.did_prefixes` is unset
+pub const DEFAULT_DID_PREFIXES: [&str; 3] = ["did:c4e:worker:", "did:c4e:gateway:", "did:c4e:operator:"];
+
+/// Maximum length of an accepted DID prefix
+const MAX_DID_PREFIX_LENGTH: usize = 64;
+
+/// Whether a DID of `expected_type` may use `prefix`. `did:c4e:` prefixes name the DID type
+/// and only match it; prefixes of other DID methods (e.g. `did:web:`) match every type.
+fn did_prefix_matches(prefix: &str, expected_type: &str) -> bool {
+    match prefix.strip_prefix("did:c4e:") {
+        Some(did_type) => did_type.strip_suffix(':') == Some(expected_type),
+        None => true,
+    }
+}
+
+/// Checks that a DID starts with one of the configured prefixes accepted for `expected_type`
+/// and has a non-empty identifier of DID characters (ASCII alphanumerics, '.', '-', '_', ':'
+/// and '%' escapes), without querying the DID Contract
+fn validate_did_format(config: &Config, did: &str, expected_type: &str) -> Result<(), ContractError> {
+    let has_identifier = |prefix: &str| {
+        did.strip_prefix(prefix).is_some_and(|id| {
+            !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':' | '%'))
+        })
+    };
+    let accepts = |prefix: &str| did_prefix_matches(prefix, expected_type) && has_identifier(prefix);
+    let valid = did.len() <= MAX_DID_LENGTH
+        && match &config.did_prefixes {
+            Some(prefixes) => prefixes.iter().any(|p| accepts(p)),
+            None => DEFAULT_DID_PREFIXES.iter().any(|p| accepts(p)),
+        };
+    if !valid {
+        return Err(ContractError::InvalidDidFormat { did: did.to_string() });
+    }
+    Ok(())
+}
+
+/// Maximum size of a `metadata_json` document, for the proof and for each batch
+const MAX_METADATA_JSON_BYTES: usize = 8192;
+
+/// Maximum length of a batch ID
+const MAX_BATCH_ID_LENGTH: usize = 128;
+
+/// Returns why `text` is unsafe to store and emit, if it is: control characters (except the
+/// line breaks and tabs of `multiline` text) and bidirectional formatting characters, which
+/// make text display differently from the bytes indexers store.
+fn text_problem(text: &str, multiline: bool) -> Option<&'static str> {
+    if text.chars().any(|c| c.is_control() && !(multiline && matches!(c, '\n' | '\r' | '\t'))) {
+        return Some("contains control characters");
+    }
+    if text.chars().any(is_bidi_control) {
+        return Some("contains bidirectional formatting characters");
+    }
+    None
+}
+
+/// Validates a free-text input: at most `max_bytes` bytes and free of the characters
+/// `text_problem` rejects.
+fn validate_text(field: &str, text: &str, max_bytes: usize, multiline: bool) -> Result<(), ContractError> {
+    let reason = if text.len() > max_bytes {
+        Some(format!("longer than {} bytes", max_bytes))
+    } else {
+        text_problem(text, multiline).map(str::to_string)
+    };
+    match reason {
+        Some(reason) => Err(ContractError::InvalidText { field: field.to_string(), reason }),
+        None => Ok(()),
     }
+}
+
+/// Verify DID exists and is active in the DID Contract
+/// 
+/// This function queries the DID Contract to ensure the provided DID is registered
+/// and follows the correct format for the expected type (worker or gateway).
+/// 
+/// # Arguments
+/// * `deps` - Dependencies for querying
+/// * `did` - The W3C DID to verify (e.g., "did:c4e:worker:detrack1")
+/// * `expected_type` - Expected DID type ("worker" or "gateway")
+/// 
+/// # Returns
+/// * `Ok(controller)` with the DID document's controller if DID is valid and registered
+/// * `Err(ContractError)` if DID is invalid or not found
+fn verify_did(
+    deps: &cosmwasm_std::Deps,
+    did: &str,
+    expected_type: &str,
+) -> Result<String, ContractError> {
+    use cosmwasm_std::{to_json_binary, WasmQuery, QueryRequest};
+    use serde::{Deserialize, Serialize};
+
+    // Load DID contract address from config
+    let config = CONFIG.load(deps.storage)?;
+    validate_did_format(&config, did, expected_type)?;
     
-    // Phase 1b: Verify Worker DID
-    verify_did(&deps.as_ref(), &worker_did, "worker")?;
+    // Query DID contract to verify DID exists
+    #[derive(Serialize)]
+    #[serde(rename_all = "snake_case")]
+    enum DidQueryMsg {
+        GetDidDocument { did: String },
+    }
     
-    // Phase 1b: Validate batch_metadata
-    if batch_metadata.is_empty() {
-        return Err(ContractError::EmptyBatchMetadata {});
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct DidDocumentResponse {
+        id: String,
+        controller: String,
+        service: Vec<serde_json::Value>,
     }
     
-    if batch_metadata.len() > config.max_batch_size as usize {
-        return Err(ContractError::TooManyBatches { count: batch_metadata.len() });
+    let query_msg = DidQueryMsg::GetDidDocument { did: did.to_string() };
+    let query_request: QueryRequest<cosmwasm_std::Empty> = QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: config.did_contract_address.to_string(),
+        msg: to_json_binary(&query_msg)?,
+    });
+    
+    let response: Result<DidDocumentResponse, _> = deps.querier.query(&query_request);
+    
+    match response {
+        Ok(doc) => Ok(doc.controller),
+        Err(_) => Err(ContractError::DidNotFound { did: did.to_string() }),
+    }
+}
+
+/// Checks that the gateway's DID document is controlled by the submitting node, either by its
+/// address or by the operator DID it registered with
+fn verify_gateway_controller(deps: &cosmwasm_std::Deps, gateway_did: &str, node: &Node) -> Result<(), ContractError> {
+    let controller = verify_did(deps, gateway_did, "gateway")?;
+    if controller != node.address.as_str() && node.operator_did.as_deref() != Some(controller.as_str()) {
+        return Err(ContractError::GatewayNotControlledByNode {
+            gateway_did: gateway_did.to_string(),
+            controller,
+            node: node.address.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Maximum length of a measurement unit
+const MAX_UNIT_LENGTH: usize = 16;
+
+/// Validates the optional energy values of a proof: a unit is required exactly when a value is set
+fn validate_energy_values(
+    value_in: &Option<Uint128>,
+    value_out: &Option<Uint128>,
+    unit: &Option<String>,
+) -> Result<(), ContractError> {
+    let has_value = value_in.is_some() || value_out.is_some();
+    match unit {
+        None if has_value => Err(ContractError::InvalidInput("Unit is required when value_in or value_out is set".to_string())),
+        Some(_) if !has_value => Err(ContractError::InvalidInput("Unit given without value_in or value_out".to_string())),
+        Some(unit) if unit.is_empty() || unit.len() > MAX_UNIT_LENGTH || !unit.chars().all(|c| c.is_ascii_graphic()) => {
+            Err(ContractError::InvalidInput(format!("Unit must be 1-{} printable ASCII characters", MAX_UNIT_LENGTH)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Maximum length of site identifiers (facility ID, grid connection point)
+const MAX_SITE_ID_LENGTH: usize = 128;
+
+/// Validates the structured site attached to a proof
+fn validate_site(site: &Site) -> Result<(), ContractError> {
+    if site.facility_id.trim().is_empty() || site.facility_id.len() > MAX_SITE_ID_LENGTH {
+        return Err(ContractError::InvalidSite {
+            reason: format!("facility_id must be 1-{} characters", MAX_SITE_ID_LENGTH),
+        });
+    }
+    if let Some(problem) = text_problem(&site.facility_id, false) {
+        return Err(ContractError::InvalidSite { reason: format!("facility_id {}", problem) });
+    }
+
+    if let Some(point) = &site.grid_connection_point {
+        if point.trim().is_empty() || point.len() > MAX_SITE_ID_LENGTH {
+            return Err(ContractError::InvalidSite {
+                reason: format!("grid_connection_point must be 1-{} characters", MAX_SITE_ID_LENGTH),
+            });
+        }
+        if let Some(problem) = text_problem(point, false) {
+            return Err(ContractError::InvalidSite { reason: format!("grid_connection_point {}", problem) });
+        }
+    }
+
+    if let Some(hash) = &site.coordinates_hash {
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ContractError::InvalidSite {
+                reason: "coordinates_hash must be 64 hex characters".to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// URI schemes accepted for `original_data_reference` while `Config.data_reference_schemes` is unset.
+pub const DEFAULT_DATA_REFERENCE_SCHEMES: [&str; 3] = ["ipfs", "ar", "https"];
+
+/// Maximum length of an `original_data_reference`
+const MAX_DATA_REFERENCE_LENGTH: usize = 256;
+
+/// Maximum length of an accepted URI scheme
+const MAX_SCHEME_LENGTH: usize = 16;
+
+/// Validates an `original_data_reference` as a `scheme://...` URI of bounded length whose
+/// scheme is accepted by the config
+fn validate_data_reference(config: &Config, reference: &str) -> Result<(), ContractError> {
+    let invalid = |reason: String| Err(ContractError::InvalidDataReference { reason });
+    if reference.len() > MAX_DATA_REFERENCE_LENGTH {
+        return invalid(format!("longer than {} characters", MAX_DATA_REFERENCE_LENGTH));
+    }
+    if !reference.chars().all(|c| c.is_ascii_graphic()) {
+        return invalid("contains whitespace, control or non-ASCII characters".to_string());
+    }
+    let Some((scheme, rest)) = reference.split_once("://") else {
+        return invalid("not a scheme://... URI".to_string());
+    };
+    if rest.is_empty() {
+        return invalid("empty URI after the scheme".to_string());
+    }
+    let accepted = match &config.data_reference_schemes {
+        Some(schemes) => schemes.iter().any(|s| s == scheme),
+        None => DEFAULT_DATA_REFERENCE_SCHEMES.contains(&scheme),
+    };
+    if !accepted {
+        return invalid(format!("scheme {} is not accepted", scheme));
+    }
+    Ok(())
+}
+
+/// Maximum number of co-owners of one proof
+const MAX_OWNER_SHARES: usize = 20;
+
+/// Attribute cap used by `store_proof` events while `Config.max_event_attribute_bytes` is unset.
+pub const DEFAULT_MAX_EVENT_ATTRIBUTE_BYTES: u32 = 512;
+
+/// Validates the co-owners of a proof: distinct addresses with positive shares summing to 1,
+/// including the data owner when one is set
+fn validate_owner_shares(
+    api: &dyn cosmwasm_std::Api,
+    owner_shares: Vec<OwnerShare>,
+    data_owner: &Option<Addr>,
+) -> Result<Vec<ProofOwnerShare>, ContractError> {
+    let invalid = |reason: String| ContractError::InvalidOwnerShares { reason };
+    if owner_shares.len() > MAX_OWNER_SHARES {
+        return Err(invalid(format!("at most {} co-owners allowed", MAX_OWNER_SHARES)));
+    }
+
+    let mut validated: Vec<ProofOwnerShare> = Vec::with_capacity(owner_shares.len());
+    let mut total = Decimal::zero();
+    for OwnerShare { owner, share } in owner_shares {
+        let owner = api.addr_validate(&owner).map_err(|_| ContractError::InvalidDataOwner(owner))?;
+        if share.is_zero() {
+            return Err(invalid(format!("share of {} must be positive", owner)));
+        }
+        if validated.iter().any(|s| s.owner == owner) {
+            return Err(invalid(format!("{} listed more than once", owner)));
+        }
+        total += share;
+        validated.push(ProofOwnerShare { owner, share });
+    }
+
+    if !validated.is_empty() {
+        if total != Decimal::one() {
+            return Err(invalid(format!("shares sum to {}, expected 1", total)));
+        }
+        if let Some(owner) = data_owner {
+            if !validated.iter().any(|s| s.owner == *owner) {
+                return Err(invalid(format!("data owner {} is not a co-owner", owner)));
+            }
+        }
+    }
+    Ok(validated)
+}
+
+/// Verify a facility exists in the configured facility registry contract
+///
+/// Queries `{"facility": {"facility_id": ...}}` on the registry; any error
+/// (including a registry that does not know the facility) is reported as
+/// `FacilityNotFound`.
+fn verify_facility(
+    deps: &cosmwasm_std::Deps,
+    registry: &cosmwasm_std::Addr,
+    facility_id: &str,
+) -> Result<(), ContractError> {
+    use cosmwasm_std::{to_json_binary, WasmQuery, QueryRequest};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "snake_case")]
+    enum FacilityQueryMsg {
+        Facility { facility_id: String },
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct FacilityResponse {
+        facility_id: String,
+    }
+
+    let query_request: QueryRequest<cosmwasm_std::Empty> = QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: registry.to_string(),
+        msg: to_json_binary(&FacilityQueryMsg::Facility { facility_id: facility_id.to_string() })?,
+    });
+
+    let response: Result<FacilityResponse, _> = deps.querier.query(&query_request);
+
+    match response {
+        Ok(facility) if facility.facility_id == facility_id => Ok(()),
+        _ => Err(ContractError::FacilityNotFound { facility_id: facility_id.to_string() }),
+    }
+}
+
+/// Stores a new proof on the blockchain (Phase 1b: Multi-batch aggregation)
+/// 
+/// Access Control: Only whitelisted nodes with sufficient reputation can store proofs.
+/// DID Verification: Verifies worker_did and all gateway_dids in batch_metadata.
+/// 
+/// Logic:
+/// - Validates the calling node (whitelist + reputation)
+/// - Checks data hash validity and uniqueness
+/// - Validates batch_metadata (not empty, not too many batches)
+/// - Verifies Worker DID exists in DID Contract
+/// - Verifies all Gateway DIDs in batch_metadata
+/// - Creates and saves proof with IndexedMap
+/// - Indexes by gateway DIDs for efficient queries
+/// 
+/// Events: Emits attributes for "store_proof", "proof_id", "worker_did", "data_hash", etc.
+/// 
+/// Errors:
+/// - `InvalidDidFormat` if DIDs don't match expected format
+/// - `DidNotFound` if any DID is not registered
+/// - `GatewayNotControlledByNode` if a gateway DID's controller is neither the node nor its operator DID
+/// - `EmptyBatchMetadata` if no batches provided
+/// - `TooManyBatches` if more than 100 batches
+/// - `TooManySnapshots` / `BatchMetadataTooLarge` if the configured aggregate limits are exceeded
+/// - `ProofAlreadyExists` if hash already exists
+/// - `FacilityNotFound` if a facility registry is configured and does not know `site.facility_id`
+/// - `WorkerAssignedToOtherNode` if the worker is assigned to another node
+/// - `InvalidOwnerShares` if the co-owner shares are duplicated, zero, don't sum to 1 or omit `data_owner`
+/// - `InvalidInput` for validation failures
+#[allow(clippy::too_many_arguments)]
+pub fn store_proof(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    worker_did: String,
+    data_hash: String,
+    tw_start: Timestamp,
+    tw_end: Timestamp,
+    batch_metadata: Vec<BatchInfo>,
+    original_data_reference: Option<String>,
+    metadata_json: Option<String>,
+    region: Option<String>,
+    site: Option<Site>,
+    data_owner: Option<String>,
+    owner_shares: Vec<OwnerShare>,
+    value_in: Option<Uint128>,
+    value_out: Option<Uint128>,
+    unit: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_migrated(deps.storage)?;
+
+    // Validate calling node
+    validate_node(&deps, &info)?;
+    
+    let mut node = WHITELISTED_NODES.load(deps.storage, info.sender.to_string())
+        .map_err(|_| ContractError::NodeNotRegistered { address: info.sender.to_string() })?;
+    
+    let mut config = CONFIG.load(deps.storage)?;
+    
+    // Validate node tier and deposit
+    if !(1..=3).contains(&node.tier) {
+        return Err(ContractError::NodeTierNotOperational { current_tier: node.tier });
+    }
+    
+    let required_deposit_for_tier = tier_deposit_requirement(&config, node.tier);
+
+    // A shortfall is tolerated until the top-up deadline set when it was detected
+    let within_topup_grace = node.topup_deadline_block.is_some_and(|deadline| env.block.height < deadline);
+    if node.deposit < required_deposit_for_tier && !within_topup_grace {
+        return Err(ContractError::NodeHasInsufficientDeposit {
+            required_deposit: required_deposit_for_tier,
+            current_deposit: node.deposit,
+            tier: node.tier,
+        });
+    }
+    
+    ensure_region_allowed(deps.storage, &region)?;
+    if let Some(site) = &site {
+        validate_site(site)?;
+    }
+    validate_energy_values(&value_in, &value_out, &unit)?;
+    for reference in original_data_reference.iter().chain(batch_metadata.iter().filter_map(|b| b.original_data_reference.as_ref())) {
+        validate_data_reference(&config, reference)?;
+    }
+    for metadata in metadata_json.iter().chain(batch_metadata.iter().filter_map(|b| b.metadata_json.as_ref())) {
+        validate_text("metadata_json", metadata, MAX_METADATA_JSON_BYTES, true)?;
+    }
+    for batch in &batch_metadata {
+        validate_text("batch_id", &batch.batch_id, MAX_BATCH_ID_LENGTH, false)?;
+    }
+
+    // Local checks run first so malformed or duplicate proofs fail before paying for the
+    // DID (and facility) queries below
+    if data_hash.is_empty() {
+        return Err(ContractError::InvalidInput("Data hash cannot be empty".to_string()));
+    }
+    
+    if data_hash.len() != 64 || !data_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ContractError::InvalidInput("Data hash must be 64 hex characters".to_string()));
+    }
+    
+    // Check if proof already exists
+    if PROOF_BY_HASH.has(deps.storage, &data_hash) {
+        return Err(ContractError::ProofAlreadyExists(data_hash));
+    }
+    if let Some(proof_id) = REVOKED_PROOF_HASHES.may_load(deps.storage, &data_hash)? {
+        return Err(ContractError::ProofRevoked { proof_id });
+    }
+
+    // Phase 1b: Validate batch_metadata
+    if batch_metadata.is_empty() {
+        return Err(ContractError::EmptyBatchMetadata {});
+    }
+    
+    let capabilities = config.tier_capabilities.iter().find(|c| c.tier == node.tier).cloned();
+    let max_batch_size = capabilities.as_ref().map_or(config.max_batch_size, |c| c.max_batch_size);
+    if batch_metadata.len() > max_batch_size as usize {
+        return Err(ContractError::TooManyBatches { count: batch_metadata.len() });
+    }
+
+    // Bound the aggregate work per proof independently of the batch count
+    if let Some(max) = config.max_snapshots_per_proof {
+        let total = batch_metadata.iter().map(|b| b.snapshot_count as u64).sum::<u64>();
+        if total > max {
+            return Err(ContractError::TooManySnapshots { total, max });
+        }
+    }
+    if let Some(max) = config.max_batch_metadata_bytes {
+        let size = to_json_vec(&batch_metadata)?.len();
+        if size > max as usize {
+            return Err(ContractError::BatchMetadataTooLarge { size, max });
+        }
+    }
+
+    if RETIRED_WORKERS.has(deps.storage, &worker_did) {
+        return Err(ContractError::WorkerDecommissioned { worker_did });
+    }
+    if let Some(assignment) = WORKER_ASSIGNMENTS.may_load(deps.storage, &worker_did)? {
+        if assignment.node != info.sender {
+            return Err(ContractError::WorkerAssignedToOtherNode { worker_did, node: assignment.node.to_string() });
+        }
+    }
+    validate_did_format(&config, &worker_did, "worker")?;
+    for batch in &batch_metadata {
+        validate_did_format(&config, &batch.gateway_did, "gateway")?;
+    }
+    let (gateway_sequences, sequence_gaps) = advance_gateway_sequences(deps.storage, &config, &batch_metadata, config.proof_count)?;
+
+    // A second node covering the same worker window means conflicting measurements
+    let window_key = (worker_did.as_str(), tw_start.nanos(), tw_end.nanos());
+    let conflicts_with = match WORKER_WINDOWS.may_load(deps.storage, window_key)? {
+        Some(existing_id) if proofs().load(deps.storage, existing_id)?.stored_by != info.sender => Some(existing_id),
+        _ => None,
+    };
+    if let Some(existing_proof_id) = conflicts_with.filter(|_| config.reject_conflicting_proofs) {
+        return Err(ContractError::ConflictingProof { worker_did, existing_proof_id });
+    }
+
+    // Escrow the per-proof bond and collect the protocol fee when configured; both are paid
+    // together, and no funds may be attached when neither is configured
+    let fee = config.proof_fee.as_ref().map_or(Uint128::zero(), |f| f.for_batches(batch_metadata.len()));
+    let required_payment = config.proof_bond + fee;
+    ensure_exact_payment(&info, required_payment)?;
+    owe(deps.storage, &info.funds)?;
+
+    // Phase 1b: Verify the Worker DID and that the node controls all Gateway DIDs in batch_metadata
+    verify_did(&deps.as_ref(), &worker_did, "worker")?;
+    for batch in &batch_metadata {
+        verify_gateway_controller(&deps.as_ref(), &batch.gateway_did, &node)?;
+    }
+
+    // Verify the referenced facility when a registry is configured
+    if let (Some(registry), Some(site)) = (&config.facility_registry_address, &site) {
+        verify_facility(&deps.as_ref(), registry, &site.facility_id)?;
+    }
+
+    // Count the proof against the node tier's per-period cap, and the probation cap while on probation
+    let on_probation = node.on_probation(env.block.height);
+    let tier_limit = capabilities.and_then(|c| c.max_proofs_per_period);
+    let probation_limit = config.probation.max_proofs_per_period.filter(|_| on_probation);
+    let quota = match (tier_limit, probation_limit) {
+        (_, Some(limit)) if tier_limit.is_none_or(|tier_limit| limit < tier_limit) =>
+            Some((limit, ContractError::ProbationProofLimitReached { limit })),
+        (Some(limit), _) => Some((limit, ContractError::TierProofLimitReached { tier: node.tier, limit })),
+        _ => None,
+    };
+    if let Some((limit, limit_reached)) = quota {
+        consume_node_quota(deps.storage, &env, &node, limit, limit_reached)?;
+    }
+
+    // Attribute the proof to a data owner and count it against their plan
+    let data_owner = data_owner
+        .map(|owner| deps.api.addr_validate(&owner).map_err(|_| ContractError::InvalidDataOwner(owner)))
+        .transpose()?;
+    let owner_shares = validate_owner_shares(deps.api, owner_shares, &data_owner)?;
+    let mut retention_seconds = config.proof_retention_seconds;
+    if let Some(owner) = &data_owner {
+        let metadata_bytes = metadata_json.as_ref().map_or(0, |m| m.len())
+            + batch_metadata.iter().map(|b| b.metadata_json.as_ref().map_or(0, |m| m.len())).sum::<usize>();
+        let plan = consume_owner_quota(deps.storage, &env, owner, metadata_bytes)?;
+        retention_seconds = plan.retention_seconds.or(retention_seconds);
+    }
+    
+    // Pay the node from the data owner's escrow for the worker, when one is funded
+    let escrow_payment = match WORKER_ESCROWS.may_load(deps.storage, &worker_did)? {
+        Some(mut escrow) if !config.escrow_price_per_batch.is_zero() => {
+            let required = config.escrow_price_per_batch * Uint128::from(batch_metadata.len() as u64);
+            if escrow.balance < required {
+                return Err(ContractError::InsufficientEscrow { worker_did, balance: escrow.balance, required });
+            }
+            escrow.balance -= required;
+            WORKER_ESCROWS.save(deps.storage, &worker_did, &escrow)?;
+            required
+        }
+        _ => Uint128::zero(),
+    };
+
+    // Increment proof count
+    let proof_id = config.proof_count;
+    config.proof_count += 1;
+    // Accrue the proof reward, limited to what the pool still holds
+    let reward = config.proof_rewards.for_tier(node.tier).min(config.reward_pool);
+    config.reward_pool -= reward;
+    config.collected_fees += fee;
+    CONFIG.save(deps.storage, &config)?;
+    
+    // Create new proof (Phase 1b structure)
+    let proof = Proof {
+        id: proof_id,
+        worker_did: worker_did.clone(),
+        data_hash: data_hash.clone(),
+        tw_start,
+        tw_end,
+        batch_metadata: batch_metadata.clone(),
+        original_data_reference,
+        metadata_json,
+        stored_at: env.block.time,
+        stored_height: env.block.height,
+        stored_by: info.sender.clone(),
+        region: region.clone(),
+        site: site.clone(),
+        data_owner: data_owner.clone(),
+        owner_shares,
+        value_in,
+        value_out,
+        unit: unit.clone(),
+        rejected: false,
+        under_review: false,
+        schema_version: PROOF_SCHEMA_VERSION,
+        status: ProofStatus::Active,
+        retain_until: retention_seconds.map(|seconds| env.block.time.plus_seconds(seconds)),
+        compacted: false,
+    };
+    
+    // Save proof with IndexedMap (auto-indexes by worker_did)
+    proofs().save(deps.storage, proof_id, &proof)?;
+    record_worker_energy(deps.storage, &proof, false)?;
+    let worker_proofs = WORKER_PROOF_COUNTS.may_load(deps.storage, &worker_did)?.unwrap_or_default();
+    WORKER_PROOF_COUNTS.save(deps.storage, &worker_did, &(worker_proofs + 1))?;
+    let committee = select_audit_committee(deps.storage, &proof)?;
+    AUDIT_COMMITTEES.save(deps.storage, proof_id, &committee)?;
+    
+    // Index proof by hash
+    PROOF_BY_HASH.save(deps.storage, &data_hash, &proof_id)?;
+    HEIGHT_PROOFS.save(deps.storage, (env.block.height, proof_id), &())?;
+    
+    // Phase 1b: Index by gateway DIDs (manual index)
+    let gateway_entry = GatewayProofEntry::from_proof(&proof);
+    for batch in &batch_metadata {
+        GATEWAY_PROOFS.save(
+            deps.storage,
+            (&batch.gateway_did, proof_id),
+            &gateway_entry,
+        )?;
+        GATEWAY_TIME_PROOFS.save(
+            deps.storage,
+            (&batch.gateway_did, tw_start.nanos(), proof_id),
+            &(),
+        )?;
+    }
+
+    for (gateway_did, sequence) in &gateway_sequences {
+        GATEWAY_SEQUENCES.save(deps.storage, gateway_did, sequence)?;
+    }
+
+    // A new node's first proofs await attestation by other nodes
+    if node.proof_count < u64::from(node.required_attestations) {
+        PROBATION_PROOFS.save(deps.storage, proof_id, &info.sender)?;
+    }
+
+    record_epoch_stats(deps.storage, &env, |stats| {
+        stats.proofs_stored += 1;
+        stats.rewards_distributed += reward;
+        if let Some(unit) = &unit {
+            stats.add_energy(unit, value_in, value_out);
+        }
+    })?;
+
+    // Credit the node for the accepted proof
+    node.proof_count += 1;
+    node.accrued_rewards += reward;
+    node.escrow_earnings += escrow_payment;
+    if !reward.is_zero() {
+        REWARD_EPOCHS.save(deps.storage, &info.sender, &(env.block.time.seconds() / QUOTA_PERIOD_SECONDS))?;
+    }
+    advance_onboarding(&config, &mut node);
+    node.reputation = config.reputation_scoring.after_proof(&config.reputation_bounds, node.reputation);
+    node.last_updated = env.block.time;
+    WHITELISTED_NODES.save(deps.storage, info.sender.to_string(), &node)?;
+
+    if !WORKER_WINDOWS.has(deps.storage, window_key) {
+        WORKER_WINDOWS.save(deps.storage, window_key, &proof_id)?;
+    }
+    if let Some(original_proof_id) = conflicts_with {
+        PROOF_CONFLICTS.save(deps.storage, proof_id, &ProofConflict {
+            original_proof_id,
+            detected_at: env.block.time,
+            resolution: None,
+        })?;
+    }
+
+    if let Some(region) = &region {
+        REGION_PROOFS.save(deps.storage, (region, proof_id), &())?;
+    }
+
+    if let Some(point) = site.as_ref().and_then(|s| s.grid_connection_point.as_ref()) {
+        GRID_POINT_PROOFS.save(deps.storage, (point, proof_id), &())?;
+    }
+
+    for (owner, share) in proof.ownership() {
+        OWNER_PROOFS.save(deps.storage, (&owner, proof_id), &share)?;
+    }
+
+    if !config.proof_bond.is_zero() {
+        PROOF_BONDS.save(deps.storage, proof_id, &ProofBond {
+            node: info.sender.clone(),
+            amount: config.proof_bond,
+            release_at_block: env.block.height + config.proof_finalization_period_blocks,
+        })?;
+    }
+    
+    // Build event attributes
+    let mut event = Event::new("store_proof")
+        .add_attribute("action", "store_proof")
+        .add_attribute("proof_id", proof_id.to_string())
+        .add_attribute("worker_did", worker_did)
+        .add_attribute("data_hash", data_hash)
+        .add_attribute("stored_by", info.sender.to_string())
+        .add_attribute("node_reputation", node.reputation.to_string())
+        .add_attribute("batch_count", batch_metadata.len().to_string())
+        .add_attribute("tw_start", tw_start.to_string())
+        .add_attribute("tw_end", tw_end.to_string());
+    
+    // Gateway DIDs (one per batch, so `batch_count` of them) are always summarized by a digest;
+    // the full comma-separated list and the owner shares are only emitted while they fit the cap.
+    let max_attribute_bytes = config.max_event_attribute_bytes.unwrap_or(DEFAULT_MAX_EVENT_ATTRIBUTE_BYTES) as usize;
+    let gateway_dids = batch_metadata.iter()
+        .map(|b| b.gateway_did.as_str())
+        .collect::<Vec<&str>>()
+        .join(",");
+    event = event.add_attribute("gateway_dids_digest", sha256_hex(gateway_dids.as_bytes()));
+    if gateway_dids.len() <= max_attribute_bytes {
+        event = event.add_attribute("gateway_dids", gateway_dids);
+    }
+    if let Some(region) = region {
+        event = event.add_attribute("region", region);
+    }
+    if let Some(site) = site {
+        event = event.add_attribute("facility_id", escape_attribute_value(&site.facility_id));
+    }
+    if let Some(owner) = data_owner {
+        event = event.add_attribute("data_owner", owner.to_string());
+    }
+    if !proof.owner_shares.is_empty() {
+        let shares: Vec<String> = proof.owner_shares.iter()
+            .map(|s| format!("{}:{}", s.owner, s.share))
+            .collect();
+        let shares = shares.join(",");
+        if shares.len() <= max_attribute_bytes {
+            event = event.add_attribute("owner_shares", shares);
+        }
+    }
+    if !config.proof_rewards.reward_per_proof.is_zero() {
+        event = event.add_attribute("reward", reward.to_string());
+    }
+    if !fee.is_zero() {
+        event = event.add_attribute("fee", fee.to_string());
+    }
+    if !escrow_payment.is_zero() {
+        event = event.add_attribute("escrow_payment", escrow_payment.to_string());
+    }
+    if !config.proof_bond.is_zero() {
+        event = event.add_attribute("bond", config.proof_bond.to_string());
+    }
+    if let Some(value_in) = value_in {
+        event = event.add_attribute("value_in", value_in.to_string());
+    }
+    if let Some(value_out) = value_out {
+        event = event.add_attribute("value_out", value_out.to_string());
+    }
+    if let Some(unit) = unit {
+        event = event.add_attribute("unit", unit);
+    }
+
+    let mut response = Response::new().add_event(event);
+    if let Some(original_proof_id) = conflicts_with {
+        response = response.add_event(
+            Event::new("detrack_proof_conflict")
+                .add_attribute("proof_id", proof_id.to_string())
+                .add_attribute("original_proof_id", original_proof_id.to_string())
+                .add_attribute("stored_by", info.sender.to_string()),
+        );
+    }
+    for (gateway_did, gap) in sequence_gaps {
+        response = response.add_event(
+            Event::new("detrack_sequence_gap")
+                .add_attribute("proof_id", proof_id.to_string())
+                .add_attribute("gateway_did", gateway_did)
+                .add_attribute("first_missing", gap.first_missing.to_string())
+                .add_attribute("last_missing", gap.last_missing.to_string()),
+        );
+    }
+    let hooks = hook_messages(deps.storage, DetrackHookMsg::ProofStored {
+        proof_id,
+        node: info.sender.to_string(),
+        worker_did: proof.worker_did.clone(),
+        data_hash: proof.data_hash.clone(),
+    })?;
+
+    Ok(response.add_submessages(hooks))
+}
+
+/// Maximum number of proofs in one `store_proofs` submission
+const MAX_PROOFS_PER_SUBMISSION: usize = 50;
+
+/// Stores several proofs in one transaction. Each proof goes through `store_proof` in order, so
+/// later proofs see the earlier ones (e.g. duplicate hashes are rejected). Any rejected proof
+/// fails the whole submission and nothing is stored. The attached uc4e must equal the sum of
+/// the bond and fee of every proof; each proof's `store_proof` event is emitted.
+pub fn store_proofs(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proofs: Vec<StoreProofData>,
+) -> Result<Response, ContractError> {
+    if proofs.is_empty() {
+        return Err(ContractError::InvalidInput("At least one proof is required".to_string()));
+    }
+    if proofs.len() > MAX_PROOFS_PER_SUBMISSION {
+        return Err(ContractError::InvalidInput(format!(
+            "At most {} proofs can be submitted at once",
+            MAX_PROOFS_PER_SUBMISSION
+        )));
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let payments: Vec<Uint128> = proofs
+        .iter()
+        .map(|p| config.proof_bond + config.proof_fee.as_ref().map_or(Uint128::zero(), |f| f.for_batches(p.batch_metadata.len())))
+        .collect();
+    let total_payment: Uint128 = payments.iter().copied().sum();
+    ensure_exact_payment(&info, total_payment)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "store_proofs")
+        .add_attribute("proof_count", proofs.len().to_string());
+    for (proof, payment) in proofs.into_iter().zip(payments) {
+        // Each proof pays its own share of the attached funds
+        let proof_info = MessageInfo {
+            sender: info.sender.clone(),
+            funds: if payment.is_zero() { vec![] } else { coins(payment.u128(), "uc4e") },
+        };
+        let stored = store_proof(
+            deps.branch(),
+            env.clone(),
+            proof_info,
+            proof.worker_did,
+            proof.data_hash,
+            proof.tw_start,
+            proof.tw_end,
+            proof.batch_metadata,
+            proof.original_data_reference,
+            proof.metadata_json,
+            proof.region,
+            proof.site,
+            proof.data_owner,
+            proof.owner_shares,
+            proof.value_in,
+            proof.value_out,
+            proof.unit,
+        )?;
+        response = response.add_events(stored.events).add_submessages(stored.messages);
+    }
+
+    Ok(response)
+}
+
+/// Gaps found in one proof's batches, as (gateway_did, gap) in batch order.
+type SequenceGaps = Vec<(String, SequenceGap)>;
+
+/// Checks the `sequence` of each sequenced batch against its gateway's expected next sequence
+/// and returns the updated tracking records (not yet saved) with the gaps found, in batch order.
+/// A gateway's first sequenced batch starts its tracking. Lower sequences are out of order;
+/// forward jumps are gaps and must not skip more than `Config.max_sequence_gap` numbers.
+fn advance_gateway_sequences(
+    storage: &dyn Storage,
+    config: &Config,
+    batch_metadata: &[BatchInfo],
+    proof_id: u64,
+) -> Result<(BTreeMap<String, GatewaySequence>, SequenceGaps), ContractError> {
+    let mut sequences: BTreeMap<String, GatewaySequence> = BTreeMap::new();
+    let mut gaps = vec![];
+    for batch in batch_metadata {
+        let Some(sequence) = batch.sequence else { continue };
+        let tracked = match sequences.get(&batch.gateway_did) {
+            Some(tracked) => Some(tracked.clone()),
+            None => GATEWAY_SEQUENCES.may_load(storage, &batch.gateway_did)?,
+        };
+        let mut tracked = tracked.unwrap_or(GatewaySequence { next_sequence: sequence, missed_sequences: 0, last_gap: None });
+        if sequence < tracked.next_sequence {
+            return Err(ContractError::SequenceOutOfOrder {
+                gateway_did: batch.gateway_did.clone(),
+                sequence,
+                expected: tracked.next_sequence,
+            });
+        }
+        let gap = sequence - tracked.next_sequence;
+        if let Some(max_gap) = config.max_sequence_gap.filter(|max| gap > *max) {
+            return Err(ContractError::SequenceGapTooLarge { gateway_did: batch.gateway_did.clone(), sequence, gap, max_gap });
+        }
+        if gap > 0 {
+            let skipped = SequenceGap { first_missing: tracked.next_sequence, last_missing: sequence - 1, proof_id };
+            tracked.missed_sequences += gap;
+            tracked.last_gap = Some(skipped.clone());
+            gaps.push((batch.gateway_did.clone(), skipped));
+        }
+        tracked.next_sequence = sequence.checked_add(1)
+            .ok_or_else(|| ContractError::InvalidInput("Batch sequence overflows".to_string()))?;
+        sequences.insert(batch.gateway_did.clone(), tracked);
+    }
+    Ok((sequences, gaps))
+}
+
+
+/// Verifies a proof's existence by its data hash.
+/// 
+pub fn verify_proof(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    data_hash: String,
+) -> Result<Response, ContractError> {
+    // Check that sender is a whitelisted node
+    validate_node(&deps, &info)?;
+    
+    // Check if proof exists
+    if let Some(proof_id) = REVOKED_PROOF_HASHES.may_load(deps.storage, &data_hash)? {
+        return Err(ContractError::ProofRevoked { proof_id });
+    }
+    if !PROOF_BY_HASH.has(deps.storage, &data_hash) {
+        return Err(ContractError::ProofNotFound(data_hash));
+    }
+
+    // Get proof ID
+    let proof_id = PROOF_BY_HASH.load(deps.storage, &data_hash)?;
+    if proofs().load(deps.storage, proof_id)?.rejected {
+        return Err(ContractError::ProofRejected { proof_id });
+    }
+    
+    Ok(Response::new()
+        .add_attribute("action", "verify_proof")
+        .add_attribute("verified", "true")
+        .add_attribute("data_hash", data_hash)
+        .add_attribute("proof_id", proof_id.to_string()))
+}
+
+/// Verifies several proofs by their data hashes in one transaction.
+/// Each hash is reported in a `detrack_proof_verification` event and in the response data
+/// (`VerifyProofsResponse`); hashes without a stored proof or with a rejected proof are
+/// reported as not verified instead of failing the transaction. Revoked proofs are reported
+/// with their ID and as not verified.
+pub fn verify_proofs(
+    deps: DepsMut,
+    info: MessageInfo,
+    data_hashes: Vec<String>,
+) -> Result<Response, ContractError> {
+    validate_node(&deps, &info)?;
+    if data_hashes.is_empty() {
+        return Err(ContractError::InvalidInput("At least one data hash is required".to_string()));
+    }
+    if data_hashes.len() > MAX_PROOFS_PER_SUBMISSION {
+        return Err(ContractError::InvalidInput(format!(
+            "At most {} data hashes can be verified at once",
+            MAX_PROOFS_PER_SUBMISSION
+        )));
+    }
+
+    let mut results = Vec::with_capacity(data_hashes.len());
+    for data_hash in data_hashes {
+        let (proof_id, verified) = match PROOF_BY_HASH.may_load(deps.storage, &data_hash)? {
+            Some(id) => (Some(id), !proofs().load(deps.storage, id)?.rejected),
+            None => (REVOKED_PROOF_HASHES.may_load(deps.storage, &data_hash)?, false),
+        };
+        results.push(ProofVerification { data_hash, proof_id, verified });
+    }
+
+    let events = results.iter().map(|result| {
+        Event::new("detrack_proof_verification")
+            .add_attribute("data_hash", result.data_hash.clone())
+            .add_attribute("proof_id", result.proof_id.map_or_else(|| "none".to_string(), |id| id.to_string()))
+            .add_attribute("verified", result.verified.to_string())
+    });
+    let verified = results.iter().filter(|r| r.verified).count();
+
+    Ok(Response::new()
+        .add_attribute("action", "verify_proofs")
+        .add_attribute("verified", verified.to_string())
+        .add_attribute("unverified", (results.len() - verified).to_string())
+        .add_events(events.collect::<Vec<_>>())
+        .set_data(to_json_binary(&VerifyProofsResponse { results })?))
+}
+
+/// Decommissions a worker DID: further proofs for it are rejected and its proof chain is
+/// sealed at the latest stored proof, whose time window is recorded as the final coverage.
+/// Access Control: the node that stored the worker's latest proof, or the admin (required when
+/// the worker has no proofs).
+///
+/// Errors:
+/// - `InvalidDidFormat` if `worker_did` is not a worker DID.
+/// - `WorkerDecommissioned` if the worker is already retired.
+/// - `Unauthorized` if the sender is neither the last submitter nor the admin.
+pub fn decommission_worker(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    worker_did: String,
+) -> Result<Response, ContractError> {
+    validate_did_format(&CONFIG.load(deps.storage)?, &worker_did, "worker")?;
+    if RETIRED_WORKERS.has(deps.storage, &worker_did) {
+        return Err(ContractError::WorkerDecommissioned { worker_did });
+    }
+
+    let final_proof = proofs()
+        .idx
+        .worker
+        .prefix(worker_did.clone())
+        .range(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map(|(_, proof)| proof);
+
+    let config = CONFIG.load(deps.storage)?;
+    let is_last_submitter = final_proof.as_ref().is_some_and(|p| p.stored_by == info.sender);
+    if !is_last_submitter && info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let retirement = WorkerRetirement {
+        retired_at: env.block.time,
+        retired_by: info.sender.clone(),
+        final_proof_id: final_proof.as_ref().map(|p| p.id),
+        final_tw_start: final_proof.as_ref().map(|p| p.tw_start),
+        final_tw_end: final_proof.as_ref().map(|p| p.tw_end),
+    };
+    RETIRED_WORKERS.save(deps.storage, &worker_did, &retirement)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "decommission_worker")
+        .add_attribute("worker_did", worker_did)
+        .add_attribute("retired_by", info.sender.to_string())
+        .add_attribute(
+            "final_proof_id",
+            retirement.final_proof_id.map_or_else(|| "none".to_string(), |id| id.to_string()),
+        )
+        .add_attribute(
+            "final_tw_end",
+            retirement.final_tw_end.map_or_else(|| "none".to_string(), |t| t.to_string()),
+        ))
+}
+
+/// Binds a worker DID to the node allowed to store its proofs, so nodes cannot race each other
+/// for the worker's proofs and rewards; `None` releases the worker to any node.
+/// Access Control: the admin, or the address that controls the worker DID in the DID contract.
+///
+/// Errors:
+/// - `InvalidDidFormat` / `DidNotFound` if `worker_did` is not a registered worker DID.
+/// - `Unauthorized` if the sender is neither the admin nor the worker DID's controller.
+/// - `NodeNotRegistered` if `node_address` is not a registered node.
+pub fn assign_worker(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    worker_did: String,
+    node_address: Option<String>,
+) -> Result<Response, ContractError> {
+    let controller = verify_did(&deps.as_ref(), &worker_did, "worker")?;
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin && info.sender.as_str() != controller {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let node = node_address.map(|a| deps.api.addr_validate(&a)).transpose()?;
+    match &node {
+        Some(node) => {
+            if !WHITELISTED_NODES.has(deps.storage, node.to_string()) {
+                return Err(ContractError::NodeNotRegistered { address: node.to_string() });
+            }
+            let assignment = WorkerAssignment {
+                node: node.clone(),
+                assigned_by: info.sender.clone(),
+                assigned_at: env.block.time,
+            };
+            WORKER_ASSIGNMENTS.save(deps.storage, &worker_did, &assignment)?;
+        }
+        None => WORKER_ASSIGNMENTS.remove(deps.storage, &worker_did),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "assign_worker")
+        .add_attribute("worker_did", worker_did)
+        .add_attribute("node_address", node.map_or_else(|| "none".to_string(), |n| n.to_string()))
+        .add_attribute("assigned_by", info.sender.to_string()))
+}
+
+/// Registers a new node, verifies native stake, and locks their deposit.
+/// This function allows any address to attempt to register as a node, provided they meet
+/// the native staking requirements for a tier and send the correct corresponding deposit.
+/// Logic:
+/// 1. Checks if the node is already registered.
+/// 2. Fetches the node\'s native staked amount using `get_native_staked_amount`.
+/// 3. Determines the node\'s tier based on their native stake against configured thresholds.
+/// 4. Verifies that the `info.funds` (deposit sent with the registration message) matches
+///    the required deposit for the determined tier.
+/// 5. If all checks pass, a new `Node` entry is created and saved in `WHITELISTED_NODES`.
+///    The `WHITELISTED_NODES` map now serves as the central registry for all active nodes,
+///    regardless of the `use_whitelist` flag in `Config`.
+///
+/// Events: Emits attributes for "register_node", "node_address", "native_stake_verified",
+///         "tier_assigned", "deposit_locked".
+///
+/// Errors:
+/// - `CustomError("Node already registered")` if the node is already in `WHITELISTED_NODES`.
+/// - `InsufficientStake` if native stake is below the minimum for Tier 1.
+/// - `DepositDoesNotMatchTierRequirement` if the sent deposit doesn\'t match the tier\'s requirement.
+pub fn register_node(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    referrer: Option<String>,
+    operator_did: Option<String>,
+) -> Result<Response, ContractError> {
+    // Non-native deposit denoms count at their configured weight
+    let config = CONFIG.load(deps.storage)?;
+    let (sent_deposit_amount, deposit_coins) = deposit_value(&config, &info.funds)?;
+    let referrer = referrer.map(|r| deps.api.addr_validate(&r)).transpose()?;
+    if let Some(did) = &operator_did {
+        verify_did(&deps.as_ref(), did, "operator")?;
+    }
+    owe(deps.storage, &deposit_coins)?;
+
+    register_with_deposit(deps, env, info.sender, sent_deposit_amount, deposit_coins, referrer, operator_did)
+}
+
+/// Registration shared by `register_node` and `complete_registration`: checks the sender's
+/// native stake and locks the given deposit (uc4e-equivalent value and held coins).
+fn register_with_deposit(
+    deps: DepsMut,
+    env: Env,
+    sender_addr: Addr,
+    sent_deposit_amount: Uint128,
+    deposit_coins: Vec<Coin>,
+    referrer: Option<Addr>,
+    operator_did: Option<String>,
+) -> Result<Response, ContractError> {
+    let sender_str = sender_addr.to_string();
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_not_tombstoned(deps.storage, &sender_addr)?;
+
+    // Check if node is already registered in WHITELISTED_NODES
+    let existing_node = WHITELISTED_NODES.may_load(deps.storage, sender_str.clone())?;
+    
+    // If node exists and is already operational (tier > 0), prevent re-registration
+    if let Some(existing) = &existing_node {
+        if existing.tier > 0 {
+            return Err(ContractError::CustomError("Node already registered".to_string()));
+        }
+        // If tier is 0, this is a whitelisted node that needs to upgrade - continue with registration
+    }
+
+    // 1. Verify Native Stake and Determine Tier
+    // This step queries the chain\'s staking module to get the total amount
+    // the sender has staked in the native C4E token.
+    let native_staked_amount = get_native_staked_amount(&deps.querier, &sender_addr)?;
+
+    // Determine the tier based on the native staked amount.
+    // Tiers provide different levels of service or trust within the DeTrack network.
+    let tier = stake_tier(&config, native_staked_amount);
+    if tier == 0 {
+        return Err(ContractError::InsufficientStake {
+            required: config.min_stake_tier1, // Minimum requirement is Tier 1 stake
+            provided: native_staked_amount,
+        });
+    }
+
+    // 2. Verify Deposit Sent with this Message matches the requirement for the stake-determined Tier
+    // The node must send a specific amount of `uc4e` (the deposit token) with this registration
+    // message. The required amount depends on the tier they qualified for based on their native stake.
+    let required_deposit_for_tier = tier_deposit_requirement(&config, tier);
+
+    // Check if the sent deposit matches the required deposit for the determined tier
+    if sent_deposit_amount < required_deposit_for_tier {
+        return Err(ContractError::DepositDoesNotMatchTierRequirement {
+            required_deposit: required_deposit_for_tier,
+            provided_deposit: sent_deposit_amount,
+            tier,
+        });
+    }
+
+    let mut node = Node {
+        address: sender_addr,
+        reputation: existing_node.as_ref().map_or(0, |n| n.reputation), // Keep a reputation set while whitelisted
+        added_at: existing_node.as_ref().map_or(env.block.time, |n| n.added_at), // Preserve original timestamp for whitelisted nodes
+        deposit: sent_deposit_amount, // Store the locked deposit amount from this transaction
+        deposit_coins,
+        tier, // Tier determined by native stake
+        proof_count: 0, // Reset proof count for new registration
+        disputed_proofs: 0, // Reset disputed proofs for new registration
+        last_updated: env.block.time,
+        region: existing_node.as_ref().and_then(|n| n.region.clone()), // Keep a region set while whitelisted
+        referrer: existing_node.as_ref().and_then(|n| n.referrer.clone()),
+        referral_count: existing_node.as_ref().map_or(0, |n| n.referral_count),
+        topup_deadline_block: None,
+        paused: false,
+        accrued_rewards: Uint128::zero(),
+        escrow_earnings: Uint128::zero(),
+        onboarding_stage: existing_node.as_ref().map_or(OnboardingStage::Whitelisted, |n| n.onboarding_stage.clone()),
+        probation_until_block: None,
+        required_attestations: 0,
+        received_attestations: 0,
+        operator_did: operator_did.or_else(|| existing_node.as_ref().and_then(|n| n.operator_did.clone())),
+        jailed_until: existing_node.as_ref().and_then(|n| n.jailed_until),
+    };
+    if let Some(operator_did) = &node.operator_did {
+        let limit = OPERATOR_NODE_LIMITS.may_load(deps.storage, operator_did)?.or(config.max_nodes_per_operator);
+        if let Some(limit) = limit {
+            let others = OPERATOR_NODES
+                .prefix(operator_did)
+                .keys(deps.storage, None, None, Order::Ascending)
+                .filter(|address| address.as_ref().map_or(true, |a| *a != node.address))
+                .count();
+            if others >= limit as usize {
+                return Err(ContractError::OperatorNodeLimitReached { operator_did: operator_did.clone(), limit });
+            }
+        }
+        OPERATOR_NODES.save(deps.storage, (operator_did, &node.address), &())?;
+    }
+    if config.probation.period_blocks > 0 {
+        node.probation_until_block = Some(env.block.height + config.probation.period_blocks);
+        node.required_attestations = config.probation.required_attestations;
+    }
+    advance_onboarding(&config, &mut node);
+
+    // 3. Record the referral (only the first one counts) and pay the reward while the pool covers it
+    let mut response = Response::new();
+    let mut referral_reward = Uint128::zero();
+    if let Some(referrer) = referrer.filter(|_| node.referrer.is_none()) {
+        let mut referrer_node = WHITELISTED_NODES
+            .may_load(deps.storage, referrer.to_string())?
+            .filter(|n| n.tier > 0 && n.address != node.address)
+            .ok_or_else(|| ContractError::InvalidInput(format!("referrer {} is not a registered node", referrer)))?;
+        referrer_node.referral_count += 1;
+        referrer_node.last_updated = env.block.time;
+        WHITELISTED_NODES.save(deps.storage, referrer.to_string(), &referrer_node)?;
+        REFERRALS.save(deps.storage, (&referrer, &node.address), &())?;
+
+        if !config.referral_reward.is_zero() && config.referral_pool >= config.referral_reward {
+            referral_reward = config.referral_reward;
+            config.referral_pool -= referral_reward;
+            CONFIG.save(deps.storage, &config)?;
+            let reward = coins(referral_reward.u128(), "uc4e");
+            settle(deps.storage, &reward)?;
+            response = response.add_messages(checked_payouts(deps.as_ref(), &env, vec![(referrer.clone(), reward)])?);
+        }
+        node.referrer = Some(referrer);
+    }
+
+    WHITELISTED_NODES.save(deps.storage, sender_str.clone(), &node)?;
+
+    // TODO: Implement slashing conditions related to node registration or behavior post-registration.
+
+    Ok(response
+        .add_attribute("action", "register_node")
+        .add_attribute("node_address", sender_str)
+        .add_attribute("native_stake_verified", native_staked_amount.to_string())
+        .add_attribute("tier_assigned", tier.to_string())
+        .add_attribute("deposit_locked", sent_deposit_amount.to_string())
+        .add_attribute("referrer", node.referrer.map_or_else(|| "none".to_string(), |r| r.to_string()))
+        .add_attribute("referral_reward", referral_reward.to_string())
+        .add_attribute("operator_did", node.operator_did.unwrap_or_else(|| "none".to_string())))
+}
+
+/// Escrows a deposit for an operator whose native stake is not in place yet.
+/// The escrow can be converted with `complete_registration` until it expires, after which
+/// `refund_expired_pre_registrations` returns the coins.
+///
+/// Errors:
+/// - `PreRegistrationDisabled` if `pre_registration_period_blocks` is zero.
+/// - `CustomError("Node already registered")` if the sender is an operational node.
+/// - `PreRegistrationExists` if the sender already has an open escrow.
+/// - `UnsupportedDepositDenom` / `CustomError` for unaccepted or empty funds.
+pub fn pre_register(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.pre_registration_period_blocks == 0 {
+        return Err(ContractError::PreRegistrationDisabled {});
+    }
+    if WHITELISTED_NODES
+        .may_load(deps.storage, info.sender.to_string())?
+        .is_some_and(|n| n.tier > 0)
+    {
+        return Err(ContractError::CustomError("Node already registered".to_string()));
+    }
+    if PRE_REGISTRATIONS.has(deps.storage, &info.sender) {
+        return Err(ContractError::PreRegistrationExists { address: info.sender.to_string() });
+    }
+
+    let (value, coins) = deposit_value(&config, &info.funds)?;
+    if value.is_zero() {
+        return Err(ContractError::CustomError("No deposit amount provided or amount is zero".to_string()));
+    }
+
+    let expires_at_block = env.block.height + config.pre_registration_period_blocks;
+    owe(deps.storage, &coins)?;
+    PRE_REGISTRATIONS.save(deps.storage, &info.sender, &PreRegistration { value, coins, expires_at_block })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "pre_register")
+        .add_attribute("node_address", info.sender.to_string())
+        .add_attribute("escrowed_amount", value.to_string())
+        .add_attribute("expires_at_block", expires_at_block.to_string()))
+}
+
+/// Converts the sender's pre-registration escrow into a full registration. The stake and
+/// tier checks are those of `register_node`, with the escrow as the deposit; if they fail,
+/// the escrow stays open until it expires.
+pub fn complete_registration(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let escrow = PRE_REGISTRATIONS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or_else(|| ContractError::PreRegistrationNotFound { address: info.sender.to_string() })?;
+    if env.block.height >= escrow.expires_at_block {
+        return Err(ContractError::PreRegistrationExpired { expires_at_block: escrow.expires_at_block });
+    }
+
+    PRE_REGISTRATIONS.remove(deps.storage, &info.sender);
+    let response = register_with_deposit(deps, env, info.sender, escrow.value, escrow.coins, None, None)?;
+
+    Ok(response.add_attribute("from_pre_registration", "true"))
+}
+
+/// Number of escrows refunded per `refund_expired_pre_registrations` call
+const DEFAULT_REFUND_LIMIT: u32 = 10;
+const MAX_REFUND_LIMIT: u32 = 30;
+
+/// Refunds expired pre-registration escrows to their operators. Permissionless, so expired
+/// escrows are returned without the operator having to act. Refunds at most `limit` escrows.
+pub fn refund_expired_pre_registrations(
+    deps: DepsMut,
+    env: Env,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_REFUND_LIMIT).min(MAX_REFUND_LIMIT) as usize;
+
+    let expired: Vec<(Addr, PreRegistration)> = PRE_REGISTRATIONS
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| item.as_ref().map_or(true, |(_, e)| env.block.height >= e.expires_at_block))
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+
+    let mut payouts = Vec::with_capacity(expired.len());
+    let mut refunded = Vec::with_capacity(expired.len());
+    for (address, escrow) in expired {
+        PRE_REGISTRATIONS.remove(deps.storage, &address);
+        settle(deps.storage, &escrow.coins)?;
+        refunded.push(address.to_string());
+        payouts.push((address, escrow.coins));
+    }
+
+    Ok(Response::new()
+        .add_messages(checked_payouts(deps.as_ref(), &env, payouts)?)
+        .add_attribute("action", "refund_expired_pre_registrations")
+        .add_attribute("refunded_count", refunded.len().to_string())
+        .add_attribute("refunded", if refunded.is_empty() { "none".to_string() } else { refunded.join(",") }))
+}
+
+/// Schedules making `node_address`'s unlocking deposit claimable immediately, e.g. so a
+/// compromised node can reimburse users without waiting out the unbonding period. Takes
+/// effect through `execute_pending_action` after the admin timelock.
+pub fn emergency_release(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    node_address: String,
+) -> Result<Response, ContractError> {
+    validate_admin(&deps, &info)?;
+
+    let node_addr = deps.api.addr_validate(&node_address)?;
+    if !UNLOCKING_DEPOSITS.has(deps.storage, node_addr.to_string()) {
+        return Err(ContractError::InvalidInput(format!("node {} has no unlocking deposit", node_addr)));
+    }
+
+    schedule_action(deps, &env, &info, TimelockedAction::EmergencyRelease { node_address: node_addr.to_string() })
+}
+
+/// Schedules a change of `admin_timelock_blocks`. The change is itself timelocked, so the
+/// delay cannot be lifted before it would have expired.
+pub fn configure_admin_timelock(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    blocks: u64,
+) -> Result<Response, ContractError> {
+    validate_admin(&deps, &info)?;
+
+    schedule_action(deps, &env, &info, TimelockedAction::SetAdminTimelock { blocks })
+}
+
+/// Schedules a change of `max_reputation_delta`. Like the timelock itself, the limit can only
+/// be lifted after the timelock, so it cannot be bypassed by a compromised key.
+pub fn configure_reputation_delta_limit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    max_delta: Option<u32>,
+) -> Result<Response, ContractError> {
+    validate_admin(&deps, &info)?;
+
+    schedule_action(deps, &env, &info, TimelockedAction::SetReputationDeltaLimit { max_delta })
+}
+
+/// Runs a scheduled action whose timelock has passed.
+///
+/// Errors:
+/// - `PendingActionNotFound` if the action does not exist or was already executed or cancelled.
+/// - `PendingActionTimelocked` before `executable_at_block`.
+pub fn execute_pending_action(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    action_id: u64,
+) -> Result<Response, ContractError> {
+    validate_admin(&deps, &info)?;
+
+    let pending = PENDING_ACTIONS
+        .may_load(deps.storage, action_id)?
+        .ok_or(ContractError::PendingActionNotFound { action_id })?;
+    if env.block.height < pending.executable_at_block {
+        return Err(ContractError::PendingActionTimelocked { executable_at_block: pending.executable_at_block });
+    }
+    PENDING_ACTIONS.remove(deps.storage, action_id);
+
+    let mut response = Response::new()
+        .add_attribute("action", "execute_pending_action")
+        .add_attribute("action_id", action_id.to_string());
+    match &pending.action {
+        TimelockedAction::EmergencyRelease { node_address } => {
+            let mut unlocking = UNLOCKING_DEPOSITS
+                .may_load(deps.storage, node_address.clone())?
+                .ok_or_else(|| ContractError::InvalidInput(format!("node {} has no unlocking deposit", node_address)))?;
+            unlocking.release_at_block = unlocking.release_at_block.min(env.block.height);
+            set_unlocking_deposit(deps.storage, node_address, Some(&unlocking))?;
+            response = response
+                .add_attribute("executed", "emergency_release")
+                .add_attribute("node_address", node_address)
+                .add_attribute("release_at_block", unlocking.release_at_block.to_string());
+        }
+        TimelockedAction::SetAdminTimelock { blocks } => {
+            let mut config = CONFIG.load(deps.storage)?;
+            config.admin_timelock_blocks = *blocks;
+            CONFIG.save(deps.storage, &config)?;
+            response = response
+                .add_attribute("executed", "set_admin_timelock")
+                .add_attribute("admin_timelock_blocks", blocks.to_string());
+        }
+        TimelockedAction::SetNodeReputation { node_address, reputation } => {
+            let config = CONFIG.load(deps.storage)?;
+            set_node_reputation(deps.storage, &config, node_address, *reputation)?;
+            response = response
+                .add_attribute("executed", "set_node_reputation")
+                .add_attribute("node_address", node_address)
+                .add_attribute("reputation", reputation.to_string());
+        }
+        TimelockedAction::SetReputationDeltaLimit { max_delta } => {
+            let mut config = CONFIG.load(deps.storage)?;
+            config.max_reputation_delta = *max_delta;
+            CONFIG.save(deps.storage, &config)?;
+            response = response
+                .add_attribute("executed", "set_reputation_delta_limit")
+                .add_attribute("max_reputation_delta", max_delta.map_or_else(|| "none".to_string(), |d| d.to_string()));
+        }
+    }
+
+    record_audit(deps.storage, &env, &info.sender, action_id, pending.action, AuditEvent::Executed)?;
+    Ok(response)
+}
+
+/// Cancels a scheduled action before it is executed.
+pub fn cancel_pending_action(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    action_id: u64,
+) -> Result<Response, ContractError> {
+    validate_admin(&deps, &info)?;
+
+    let pending = PENDING_ACTIONS
+        .may_load(deps.storage, action_id)?
+        .ok_or(ContractError::PendingActionNotFound { action_id })?;
+    PENDING_ACTIONS.remove(deps.storage, action_id);
+    record_audit(deps.storage, &env, &info.sender, action_id, pending.action, AuditEvent::Cancelled)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_pending_action")
+        .add_attribute("action_id", action_id.to_string()))
+}
+
+/// Stores `action` in `PENDING_ACTIONS` under the next action ID, executable after the
+/// configured admin timelock, and records it in the audit log.
+fn schedule_action(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    action: TimelockedAction,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let action_id = config.pending_action_count;
+    config.pending_action_count += 1;
+    CONFIG.save(deps.storage, &config)?;
+
+    let executable_at_block = env.block.height + config.admin_timelock_blocks;
+    PENDING_ACTIONS.save(
+        deps.storage,
+        action_id,
+        &PendingAction {
+            action: action.clone(),
+            scheduled_by: info.sender.clone(),
+            scheduled_at_block: env.block.height,
+            executable_at_block,
+        },
+    )?;
+    record_audit(deps.storage, env, &info.sender, action_id, action, AuditEvent::Scheduled { executable_at_block })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "schedule_action")
+        .add_attribute("action_id", action_id.to_string())
+        .add_attribute("executable_at_block", executable_at_block.to_string()))
+}
+
+/// Appends an entry to `ADMIN_AUDIT_LOG`.
+fn record_audit(
+    storage: &mut dyn Storage,
+    env: &Env,
+    admin: &Addr,
+    action_id: u64,
+    action: TimelockedAction,
+    event: AuditEvent,
+) -> Result<(), ContractError> {
+    let entry_id = ADMIN_AUDIT_LOG
+        .keys(storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map_or(0, |last| last + 1);
+    ADMIN_AUDIT_LOG.save(
+        storage,
+        entry_id,
+        &AdminAuditEntry {
+            action_id,
+            action,
+            event,
+            admin: admin.clone(),
+            block_height: env.block.height,
+            time: env.block.time,
+        },
+    )?;
+    Ok(())
+}
+
+/// Sets the uc4e reward paid to a referrer for each node registering with it. Rewards are
+/// only paid while `referral_pool` covers them; zero keeps attribution without rewards.
+pub fn configure_referral_reward(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.referral_reward = amount;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_referral_reward")
+        .add_attribute("amount", amount.to_string()))
+}
+
+/// Adds the uc4e attached to the message to the referral reward pool.
+pub fn fund_referral_pool(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    validate_admin(&deps, &info)?;
+
+    let amount = info.funds.iter().find(|c| c.denom == "uc4e").map_or(Uint128::zero(), |c| c.amount);
+    if amount.is_zero() || info.funds.iter().any(|c| c.denom != "uc4e") {
+        return Err(ContractError::InvalidInput("referral pool is funded with uc4e only".to_string()));
+    }
+    let mut config = CONFIG.load(deps.storage)?;
+    config.referral_pool += amount;
+    owe(deps.storage, &coins(amount.u128(), "uc4e"))?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_referral_pool")
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("referral_pool", config.referral_pool.to_string()))
+}
+
+/// Sets the uc4e nodes accrue per stored proof. Multipliers must name tiers 1-3, at most once
+/// each. Rewards already accrued are kept.
+pub fn configure_proof_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+    rewards: ProofRewards,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut tiers: Vec<u8> = rewards.tier_multipliers.iter().map(|m| m.tier).collect();
+    tiers.sort_unstable();
+    tiers.dedup();
+    if tiers.len() != rewards.tier_multipliers.len() || tiers.iter().any(|t| !(1..=3).contains(t)) {
+        return Err(ContractError::InvalidInput("Reward multipliers must name tiers 1, 2 or 3 at most once".to_string()));
+    }
+    let mut config = CONFIG.load(deps.storage)?;
+    config.proof_rewards = rewards.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    let multipliers: Vec<String> = rewards.tier_multipliers.iter()
+        .map(|m| format!("{}:{}", m.tier, m.multiplier))
+        .collect();
+    Ok(Response::new()
+        .add_attribute("action", "configure_proof_rewards")
+        .add_attribute("reward_per_proof", rewards.reward_per_proof.to_string())
+        .add_attribute(
+            "tier_multipliers",
+            if multipliers.is_empty() { "none".to_string() } else { multipliers.join(",") },
+        ))
+}
+
+/// Adds the uc4e attached to the message to the proof reward pool.
+pub fn fund_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    validate_admin(&deps, &info)?;
+
+    let amount = info.funds.iter().find(|c| c.denom == "uc4e").map_or(Uint128::zero(), |c| c.amount);
+    if amount.is_zero() || info.funds.iter().any(|c| c.denom != "uc4e") {
+        return Err(ContractError::InvalidInput("reward pool is funded with uc4e only".to_string()));
+    }
+    let mut config = CONFIG.load(deps.storage)?;
+    config.reward_pool += amount;
+    CONFIG.save(deps.storage, &config)?;
+    owe(deps.storage, &info.funds)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_rewards")
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("reward_pool", config.reward_pool.to_string()))
+}
+
+/// Adds the uc4e attached to the message to the escrow paying for `worker_did`'s proofs. The
+/// first funder of a worker owns its escrow; other owners cannot fund it.
+pub fn fund_worker_escrow(
+    deps: DepsMut,
+    info: MessageInfo,
+    worker_did: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    validate_did_format(&config, &worker_did, "worker")?;
+    let amount = info.funds.iter().find(|c| c.denom == "uc4e").map_or(Uint128::zero(), |c| c.amount);
+    if amount.is_zero() || info.funds.iter().any(|c| c.denom != "uc4e") {
+        return Err(ContractError::InvalidInput("escrows are funded with uc4e only".to_string()));
+    }
+
+    let mut escrow = WORKER_ESCROWS
+        .may_load(deps.storage, &worker_did)?
+        .unwrap_or(WorkerEscrow { owner: info.sender.clone(), balance: Uint128::zero() });
+    if escrow.owner != info.sender {
+        return Err(ContractError::EscrowOwnedByOther { worker_did, owner: escrow.owner.to_string() });
+    }
+    escrow.balance += amount;
+    WORKER_ESCROWS.save(deps.storage, &worker_did, &escrow)?;
+    owe(deps.storage, &info.funds)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_worker_escrow")
+        .add_attribute("worker_did", worker_did)
+        .add_attribute("owner", info.sender.to_string())
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("balance", escrow.balance.to_string()))
+}
+
+/// Pays `amount` (or the whole balance) of the caller's escrow for `worker_did` back to the
+/// caller. An emptied escrow is closed, so another owner may fund the worker.
+pub fn withdraw_worker_escrow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    worker_did: String,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut escrow = WORKER_ESCROWS
+        .may_load(deps.storage, &worker_did)?
+        .ok_or_else(|| ContractError::InsufficientEscrow {
+            worker_did: worker_did.clone(),
+            balance: Uint128::zero(),
+            required: amount.unwrap_or_default(),
+        })?;
+    if escrow.owner != info.sender {
+        return Err(ContractError::EscrowOwnedByOther { worker_did, owner: escrow.owner.to_string() });
+    }
+    let amount = amount.unwrap_or(escrow.balance);
+    if amount.is_zero() || amount > escrow.balance {
+        return Err(ContractError::InsufficientEscrow { worker_did, balance: escrow.balance, required: amount });
+    }
+    escrow.balance -= amount;
+    if escrow.balance.is_zero() {
+        WORKER_ESCROWS.remove(deps.storage, &worker_did);
+    } else {
+        WORKER_ESCROWS.save(deps.storage, &worker_did, &escrow)?;
+    }
+    let refund = coins(amount.u128(), "uc4e");
+    settle(deps.storage, &refund)?;
+
+    Ok(Response::new()
+        .add_messages(checked_payouts(deps.as_ref(), &env, vec![(info.sender.clone(), refund)])?)
+        .add_attribute("action", "withdraw_worker_escrow")
+        .add_attribute("worker_did", worker_did)
+        .add_attribute("owner", info.sender.to_string())
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("balance", escrow.balance.to_string()))
+}
+
+/// Pays out the proof rewards and escrow payments accrued by the calling node. Paused nodes may claim.
+pub fn claim_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut node = WHITELISTED_NODES
+        .load(deps.storage, info.sender.to_string())
+        .map_err(|_| ContractError::NodeNotRegistered { address: info.sender.to_string() })?;
+    let amount = node.accrued_rewards + node.escrow_earnings;
+    if amount.is_zero() {
+        return Err(ContractError::NoRewardsToClaim {});
+    }
+    node.accrued_rewards = Uint128::zero();
+    node.escrow_earnings = Uint128::zero();
+    WHITELISTED_NODES.save(deps.storage, info.sender.to_string(), &node)?;
+    let reward = coins(amount.u128(), "uc4e");
+    settle(deps.storage, &reward)?;
+
+    Ok(Response::new()
+        .add_messages(checked_payouts(deps.as_ref(), &env, vec![(info.sender.clone(), reward)])?)
+        .add_attribute("action", "claim_rewards")
+        .add_attribute("node_address", info.sender.to_string())
+        .add_attribute("amount", amount.to_string()))
+}
+
+/// Sets how many blocks a pre-registration escrow stays open. Zero disables `PreRegister`;
+/// open escrows keep their expiry.
+pub fn configure_pre_registration(
+    deps: DepsMut,
+    info: MessageInfo,
+    period_blocks: u64,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.pre_registration_period_blocks = period_blocks;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_pre_registration")
+        .add_attribute("period_blocks", period_blocks.to_string()))
+}
+
+/// Initiates the unlocking period for a node\'s deposit.
+/// Access Control: Only the registered node can initiate unlocking for their own deposit.
+/// Logic:
+/// 1. Validates that the sender is a registered node.
+/// 2. Checks if the deposit isn\'t already in the process of unlocking.
+/// 3. Checks if the node has a non-zero deposit to unlock.
+/// 4. Moves the node\'s active deposit amount to a new `UnlockingDeposit` entry.
+///    The node\'s `deposit` field is set to zero, effectively making their current deposit inactive.
+/// 5. Calculates `release_at_block` based on the current block height and `deposit_unlock_period_blocks` from config.
+/// 6. Saves the `UnlockingDeposit` entry, keyed by the node\'s address.
+///
+/// State Transition:
+/// - Node\'s `deposit` in `WHITELISTED_NODES` is set to 0.
+/// - A new entry is created in `UNLOCKING_DEPOSITS` for the node, with the amount and release block.
+///
+/// Events: Emits "unlock_deposit", "node_address", "unlocking_amount", "release_at_block".
+/// Errors:
+/// - `NodeNotRegistered` if the sender is not a registered node.
+/// - `DepositAlreadyUnlocking` if an unlocking process is already active for the node.
+/// - `NoDepositToUnlock` if the node\'s current active deposit is zero.
+pub fn unlock_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let sender_addr = info.sender.clone();
+    let sender_str = sender_addr.to_string();
+    let config = CONFIG.load(deps.storage)?;
+
+    // Check if node is registered
+    let mut node = WHITELISTED_NODES.load(deps.storage, sender_str.clone())
+        .map_err(|_| ContractError::NodeNotRegistered { address: sender_str.clone() })?;
+
+    // Check if deposit is already unlocking
+    if UNLOCKING_DEPOSITS.has(deps.storage, sender_addr.to_string()) {
+        return Err(ContractError::DepositAlreadyUnlocking {});
+    }
+
+    // Check if there's a deposit to unlock
+    if node.deposit.is_zero() {
+        return Err(ContractError::NoDepositToUnlock {});
+    }
+
+    // State Change: Node\'s active deposit is moved to an unlocking state.
+    // The node.deposit field is zeroed out, and an UnlockingDeposit entry is created.
+    let unlocking_amount = node.deposit;
+    let unlocking_coins = std::mem::take(&mut node.deposit_coins);
+    node.deposit = Uint128::zero(); // Remove active deposit from node
+    WHITELISTED_NODES.save(deps.storage, sender_str.clone(), &node)?;
+
+    let release_at_block = env.block.height + config.deposit_unlock_period_blocks;
+
+    let unlocking_deposit = UnlockingDeposit {
+        owner: sender_addr.clone(),
+        amount: unlocking_amount,
+        coins: unlocking_coins,
+        release_at_block,
+    };
+
+    set_unlocking_deposit(deps.storage, sender_addr.as_str(), Some(&unlocking_deposit))?;
+
+    let mut response = Response::default();
+
+    let event = Event::new("detrack_unlock_deposit")
+        .add_attribute("node_address", sender_str)
+        .add_attribute("unlocking_amount", unlocking_amount.to_string())
+        .add_attribute("release_at_block", release_at_block.to_string());
+
+    response = response.add_event(event);
+
+    Ok(response)
+
+//     Ok(Response::new()
+//         .add_event(Event::UnlockDeposit {
+//             node_address: sender_str,
+//             unlocking_amount,
+//             release_at_block,
+//         })
+//         .add_attribute("action", "unlock_deposit")
+//         .add_attribute("node_address", sender_str)
+//         .add_attribute("unlocking_amount", unlocking_amount.to_string())
+//         .add_attribute("release_at_block", release_at_block.to_string()))
+}
+
+/// Allows a node to claim their deposit after the unlocking period has passed.
+/// Access Control: Only the node who initiated the unlock can claim their deposit.
+/// Logic:
+/// 1. Loads the `UnlockingDeposit` entry for the sender.
+/// 2. Verifies that the current block height is greater than or equal to `release_at_block`.
+/// 3. Removes the `UnlockingDeposit` entry from storage.
+/// 4. Transfers the unlocked coins back to the node in their original denoms: native coins by
+///    `BankMsg::Send`, CW20 deposits by a `transfer` on the token contract.
+///
+/// State Transition:
+/// - The `UnlockingDeposit` entry for the node is removed from `UNLOCKING_DEPOSITS`.
+/// - Funds are transferred from the contract to the node.
+///
+/// Events: Emits "claim_unlocked_deposit", "node_address", "claimed_amount".
+/// Upheld disputes and `slash_node` penalties slash the unlocking deposit like an active one, so
+/// the claim stays blocked while disputes against the node's proofs are open.
+///
+/// Errors:
+/// - `NoUnlockedDepositToClaim` if no unlocking deposit entry exists for the sender.
+/// - `DepositNotYetUnlocked` if the current block height is less than `release_at_block`.
+/// - `DepositFrozenByDisputes` while disputes against the sender's proofs are open.
+pub fn claim_unlocked_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let sender_addr = info.sender.clone();
+
+    // Check if there's an unlocking deposit entry for the sender
+    let unlocking_deposit = UNLOCKING_DEPOSITS.load(deps.storage, sender_addr.to_string())
+        .map_err(|_| ContractError::NoUnlockedDepositToClaim {})?;
+
+    // Check if the unlocking period has passed
+    if env.block.height < unlocking_deposit.release_at_block {
+        return Err(ContractError::DepositNotYetUnlocked {
+            release_at_block: unlocking_deposit.release_at_block,
+        });
+    }
+
+    // Open disputes may still slash the deposit
+    let open_disputes = NODE_OPEN_DISPUTES
+        .prefix(&sender_addr)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count() as u64;
+    if open_disputes > 0 {
+        return Err(ContractError::DepositFrozenByDisputes { open_disputes });
+    }
+
+    // State Change: Unlocking deposit entry is removed, and funds are sent to the node.
+    // Remove the unlocking deposit entry
+    set_unlocking_deposit(deps.storage, sender_addr.as_str(), None)?;
+
+    // Send the funds back to the user, in the denoms they were deposited in
+    let claimed_coins = unlocking_deposit
+        .coins
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    // Native coins go back by bank send, CW20 tokens by a transfer on their token contract
+    settle(deps.storage, &unlocking_deposit.coins)?;
+    let payout = checked_payouts(deps.as_ref(), &env, vec![(sender_addr.clone(), unlocking_deposit.coins)])?;
+
+    let mut response = Response::default();
+
+    let event = Event::new("detrack_claim_unlocked_deposit")
+        .add_attribute("node_address", sender_addr.to_string())
+        .add_attribute("claimed_amount", unlocking_deposit.amount.to_string())
+        .add_attribute("claimed_coins", claimed_coins);
+
+    response = response
+        .add_messages(payout)
+        .add_event(event);
+
+    Ok(response)
+
+    // Ok(Response::new()
+    //     .add_message(bank_msg)
+    //     .add_attribute("action", "claim_unlocked_deposit")
+    //     .add_attribute("node_address", sender_addr.to_string())
+    //     .add_attribute("claimed_amount", unlocking_deposit.amount.to_string()))
+}
+
+/// Allows a registered node to add more funds to their existing deposit.
+/// Access Control: Only a registered node can add to their own deposit.
+/// Logic:
+/// 1. Validates that the sender is a registered node.
+/// 2. Checks that the node\'s deposit is not currently in an unlocking period.
+/// 3. Verifies that only accepted deposit denominations (uc4e or `Config.deposit_denoms`) were sent.
+/// 4. Adds the sent value to the node\'s current deposit and the coins to its `deposit_coins`.
+/// 5. Updates the node\'s `last_updated` timestamp.
+///
+/// State Transition:
+/// - Node\'s `deposit` in `WHITELISTED_NODES` is increased.
+/// - Node\'s `last_updated` in `WHITELISTED_NODES` is updated.
+///
+/// Events: Emits "add_deposit", "node_address", "added_amount", "new_total_deposit".
+/// Errors:
+/// - `NodeNotRegistered` if the sender is not a registered node.
+/// - `DepositAlreadyUnlocking` if the node\'s deposit is currently being unlocked.
+/// - `CustomError("No deposit amount provided or amount is zero")` if no deposit value is sent.
+/// - `UnsupportedDepositDenom` if funds of a denomination not accepted for deposits are sent.
+pub fn add_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let sender_addr = info.sender.clone();
+    let sender_str = sender_addr.to_string();
+
+    // 1. Validate that the sender is a registered node
+    let mut node = WHITELISTED_NODES.load(deps.storage, sender_str.clone())
+        .map_err(|_| ContractError::NodeNotRegistered { address: sender_str.clone() })?;
+
+    // 2. Check that the node\'s deposit is not currently in an unlocking period
+    if UNLOCKING_DEPOSITS.has(deps.storage, sender_addr.to_string()) {
+        return Err(ContractError::DepositAlreadyUnlocking {});
+    }
+
+    // 3. Verify that funds of accepted deposit denominations were sent
+    let config = CONFIG.load(deps.storage)?;
+    let (sent_deposit_amount, sent_coins) = deposit_value(&config, &info.funds)?;
+
+    if sent_deposit_amount.is_zero() {
+        return Err(ContractError::CustomError("No deposit amount provided or amount is zero".to_string()));
+    }
+
+    // 4. Add the sent amount to the node\'s current deposit
+    owe(deps.storage, &sent_coins)?;
+    node.deposit += sent_deposit_amount;
+    add_coins(&mut node.deposit_coins, sent_coins);
+
+    // 5. Update the node\'s `last_updated` timestamp
+    node.last_updated = env.block.time;
+
+    // A top-up that still leaves a shortfall reports the remaining amount
+    let shortfall = flag_deposit_shortfall(&config, &env, &mut node);
+    advance_onboarding(&config, &mut node);
+
+    // Save the updated node data
+    WHITELISTED_NODES.save(deps.storage, sender_str.clone(), &node)?;
+
+    Ok(Response::new()
+        .add_events(shortfall)
+        .add_attribute("action", "add_deposit")
+        .add_attribute("node_address", sender_str)
+        .add_attribute("added_amount", sent_deposit_amount.to_string())
+        .add_attribute("new_total_deposit", node.deposit.to_string()))
+}
+
+/// Moves a registered node to the tier its current native stake qualifies for, upwards or
+/// downwards. Funds attached to the message are added to the deposit first; the deposit must
+/// then cover the new tier's requirement. A deposit above the new requirement stays locked.
+///
+/// Errors:
+/// - `NodeNotRegistered` if the sender is not a registered node (tier 0 nodes use `register_node`).
+/// - `DepositAlreadyUnlocking` if the node's deposit is currently being unlocked.
+/// - `InsufficientStake` if the native stake no longer qualifies for Tier 1.
+/// - `DepositDoesNotMatchTierRequirement` if the deposit does not cover the new tier.
+/// - `UnsupportedDepositDenom` if funds of a denomination not accepted for deposits are sent.
+pub fn update_tier(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let sender_str = info.sender.to_string();
+    let mut node = WHITELISTED_NODES
+        .may_load(deps.storage, sender_str.clone())?
+        .filter(|n| n.tier > 0)
+        .ok_or_else(|| ContractError::NodeNotRegistered { address: sender_str.clone() })?;
+    if UNLOCKING_DEPOSITS.has(deps.storage, sender_str.clone()) {
+        return Err(ContractError::DepositAlreadyUnlocking {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let native_staked_amount = get_native_staked_amount(&deps.querier, &info.sender)?;
+    let tier = stake_tier(&config, native_staked_amount);
+    if tier == 0 {
+        return Err(ContractError::InsufficientStake {
+            required: config.min_stake_tier1,
+            provided: native_staked_amount,
+        });
+    }
+
+    let (added_amount, added_coins) = deposit_value(&config, &info.funds)?;
+    owe(deps.storage, &added_coins)?;
+    node.deposit += added_amount;
+    add_coins(&mut node.deposit_coins, added_coins);
+
+    let required_deposit = tier_deposit_requirement(&config, tier);
+    if node.deposit < required_deposit {
+        return Err(ContractError::DepositDoesNotMatchTierRequirement {
+            required_deposit,
+            provided_deposit: node.deposit,
+            tier,
+        });
+    }
+
+    let previous_tier = node.tier;
+    node.tier = tier;
+    node.last_updated = env.block.time;
+    flag_deposit_shortfall(&config, &env, &mut node);
+    advance_onboarding(&config, &mut node);
+    WHITELISTED_NODES.save(deps.storage, sender_str.clone(), &node)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_tier")
+        .add_attribute("node_address", sender_str)
+        .add_attribute("native_stake_verified", native_staked_amount.to_string())
+        .add_attribute("previous_tier", previous_tier.to_string())
+        .add_attribute("tier", tier.to_string())
+        .add_attribute("added_amount", added_amount.to_string())
+        .add_attribute("deposit", node.deposit.to_string()))
+}
+
+/// Refuses to write proofs while the stored contract version differs from the code's, i.e.
+/// while `migrate` has not brought existing proofs to `PROOF_SCHEMA_VERSION`.
+fn ensure_migrated(storage: &dyn Storage) -> Result<(), ContractError> {
+    let stored = cw2::get_contract_version(storage)?;
+    if stored.version != CONTRACT_VERSION {
+        return Err(ContractError::MigrationIncomplete {
+            reason: format!("state is at version {}, code is {}", stored.version, CONTRACT_VERSION),
+        });
+    }
+    Ok(())
+}
+
+/// Tier a native stake qualifies for, 0 below the Tier 1 minimum.
+pub(crate) fn stake_tier(config: &Config, native_staked_amount: Uint128) -> u8 {
+    if native_staked_amount >= config.min_stake_tier3 {
+        3
+    } else if native_staked_amount >= config.min_stake_tier2 {
+        2
+    } else if native_staked_amount >= config.min_stake_tier1 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Onboarding stage a node's record currently meets. Tier-0 nodes count as `Staked` once
+/// `native_staked_amount` qualifies for a tier; callers without a stake at hand pass zero.
+pub(crate) fn onboarding_stage(config: &Config, node: &Node, native_staked_amount: Uint128) -> OnboardingStage {
+    if node.tier == 0 {
+        if stake_tier(config, native_staked_amount) == 0 {
+            OnboardingStage::Whitelisted
+        } else {
+            OnboardingStage::Staked
+        }
+    } else if node.deposit < tier_deposit_requirement(config, node.tier) {
+        OnboardingStage::Staked
+    } else if node.reputation < tier_reputation_requirement(config, node.tier) {
+        OnboardingStage::Deposited
+    } else {
+        OnboardingStage::Operational
+    }
+}
+
+/// Moves a node's recorded onboarding stage forward to the stage its record meets; it never
+/// moves back. Callers save the node.
+pub(crate) fn advance_onboarding(config: &Config, node: &mut Node) {
+    let stage = onboarding_stage(config, node, Uint128::zero());
+    if stage > node.onboarding_stage {
+        node.onboarding_stage = stage;
+    }
+}
+
+/// Deposit required for a node tier; non-operational tiers fall back to the Tier 1 requirement.
+pub(crate) fn tier_deposit_requirement(config: &Config, tier: u8) -> Uint128 {
+    match tier {
+        3 => config.deposit_tier3,
+        2 => config.deposit_tier2,
+        _ => config.deposit_tier1,
+    }
+}
+
+/// Minimum reputation for a node tier; non-operational tiers fall back to the Tier 1 minimum.
+pub(crate) fn tier_reputation_requirement(config: &Config, tier: u8) -> i32 {
+    match tier {
+        3 => config.min_reputation_tier3,
+        2 => config.min_reputation_tier2,
+        _ => config.min_reputation_tier1,
+    }
+}
+
+/// Compares a node's deposit with its tier requirement. On a shortfall, sets the top-up
+/// deadline (keeping one already running) and returns a `deposit_shortfall` event with the
+/// missing amount; otherwise clears the deadline. Callers save the node.
+pub(crate) fn flag_deposit_shortfall(config: &Config, env: &Env, node: &mut Node) -> Option<Event> {
+    let required = tier_deposit_requirement(config, node.tier);
+    if node.tier == 0 || node.deposit >= required {
+        node.topup_deadline_block = None;
+        return None;
+    }
+
+    let deadline = *node
+        .topup_deadline_block
+        .get_or_insert(env.block.height + config.deposit_topup_grace_blocks);
+    Some(
+        Event::new("deposit_shortfall")
+            .add_attribute("node_address", node.address.to_string())
+            .add_attribute("tier", node.tier.to_string())
+            .add_attribute("required_deposit", required.to_string())
+            .add_attribute("current_deposit", node.deposit.to_string())
+            .add_attribute("missing_amount", (required - node.deposit).to_string())
+            .add_attribute("deadline_block", deadline.to_string()),
+    )
+}
+
+/// Number of nodes checked per `check_deposit_shortfalls` call
+const DEFAULT_SHORTFALL_CHECK_LIMIT: u32 = 10;
+const MAX_SHORTFALL_CHECK_LIMIT: u32 = 30;
+
+/// Checks registered nodes against the current tier deposit requirements, e.g. after the
+/// requirements changed, emitting a `deposit_shortfall` event for each node below its
+/// requirement and starting its top-up deadline. Nodes with an unlocking deposit are
+/// leaving and are skipped. Permissionless, so operator tooling can run it.
+pub fn check_deposit_shortfalls(
+    deps: DepsMut,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_SHORTFALL_CHECK_LIMIT).min(MAX_SHORTFALL_CHECK_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let nodes: Vec<(String, Node)> = WHITELISTED_NODES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+
+    let mut response = Response::new().add_attribute("action", "check_deposit_shortfalls");
+    let mut shortfalls = 0u32;
+    let last_checked = nodes.last().map(|(address, _)| address.clone());
+    for (address, mut node) in nodes {
+        if UNLOCKING_DEPOSITS.has(deps.storage, address.clone()) {
+            continue;
+        }
+        let previous_deadline = node.topup_deadline_block;
+        let shortfall = flag_deposit_shortfall(&config, &env, &mut node);
+        if node.topup_deadline_block != previous_deadline {
+            WHITELISTED_NODES.save(deps.storage, address, &node)?;
+        }
+        if let Some(event) = shortfall {
+            shortfalls += 1;
+            response = response.add_event(event);
+        }
+    }
+
+    Ok(response
+        .add_attribute("shortfalls", shortfalls.to_string())
+        .add_attribute("last_checked", last_checked.unwrap_or_else(|| "none".to_string())))
+}
+
+/// Sets how many blocks a node with a deposit shortfall may keep storing proofs. Applies to
+/// shortfalls detected from now on.
+pub fn configure_deposit_topup_grace(
+    deps: DepsMut,
+    info: MessageInfo,
+    blocks: u64,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.deposit_topup_grace_blocks = blocks;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_deposit_topup_grace")
+        .add_attribute("blocks", blocks.to_string()))
+}
+
+/// Sets the range node reputations are kept within. The bounds must include 0, the reputation
+/// nodes start with. Stored reputations are not rewritten; ones outside new bounds are clamped
+/// on their next relative change.
+pub fn configure_reputation_bounds(
+    deps: DepsMut,
+    info: MessageInfo,
+    bounds: ReputationBounds,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+    if !bounds.contains(0) {
+        return Err(ContractError::InvalidInput("Reputation bounds must satisfy min <= 0 <= max".to_string()));
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.reputation_bounds = bounds.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_reputation_bounds")
+        .add_attribute("min", bounds.min.to_string())
+        .add_attribute("max", bounds.max.to_string()))
+}
+
+/// Sets the byte cap on variable-length `store_proof` event attributes (`gateway_dids`,
+/// `owner_shares`). `None` restores `DEFAULT_MAX_EVENT_ATTRIBUTE_BYTES`.
+pub fn configure_event_attribute_cap(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_bytes: Option<u32>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.max_event_attribute_bytes = max_bytes;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_event_attribute_cap")
+        .add_attribute("max_bytes", max_bytes.map_or_else(|| "none".to_string(), |v| v.to_string())))
+}
+
+/// Sets the URI schemes accepted for `original_data_reference`. `None` restores
+/// `DEFAULT_DATA_REFERENCE_SCHEMES`. Already stored references are not re-validated.
+pub fn configure_data_reference_schemes(
+    deps: DepsMut,
+    info: MessageInfo,
+    schemes: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+    if let Some(schemes) = &schemes {
+        if schemes.is_empty() {
+            return Err(ContractError::InvalidInput("At least one scheme is required".to_string()));
+        }
+        for (i, scheme) in schemes.iter().enumerate() {
+            let valid = !scheme.is_empty()
+                && scheme.len() <= MAX_SCHEME_LENGTH
+                && scheme.starts_with(|c: char| c.is_ascii_lowercase())
+                && scheme.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "+-.".contains(c));
+            if !valid {
+                return Err(ContractError::InvalidInput(format!("Invalid URI scheme: {}", scheme)));
+            }
+            if schemes[..i].contains(scheme) {
+                return Err(ContractError::InvalidInput(format!("Duplicate URI scheme: {}", scheme)));
+            }
+        }
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.data_reference_schemes = schemes.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_data_reference_schemes")
+        .add_attribute("schemes", schemes.map_or_else(|| "default".to_string(), |s| s.join(","))))
+}
+
+/// Accepts DIDs starting with `prefix`, e.g. `did:web:` for a partner network's DID method.
+/// The first change replaces the unset default with `DEFAULT_DID_PREFIXES` plus `prefix`.
+pub fn add_did_prefix(
+    deps: DepsMut,
+    info: MessageInfo,
+    prefix: String,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+    let valid = prefix.len() <= MAX_DID_PREFIX_LENGTH
+        && prefix.strip_prefix("did:").and_then(|p| p.strip_suffix(':')).is_some_and(|method| {
+            !method.is_empty() && method.split(':').all(|part| {
+                !part.is_empty() && part.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '-' | '_'))
+            })
+        });
+    if !valid {
+        return Err(ContractError::InvalidInput(format!("Invalid DID prefix: {}", prefix)));
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut prefixes = config.did_prefixes.take()
+        .unwrap_or_else(|| DEFAULT_DID_PREFIXES.iter().map(|p| p.to_string()).collect());
+    if prefixes.contains(&prefix) {
+        return Err(ContractError::InvalidInput(format!("DID prefix already accepted: {}", prefix)));
+    }
+    prefixes.push(prefix.clone());
+    config.did_prefixes = Some(prefixes);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_did_prefix")
+        .add_attribute("prefix", prefix))
+}
+
+/// Stops accepting DIDs starting with `prefix` for new proofs, registrations and mappings.
+/// Records already stored with such DIDs are kept. The last prefix cannot be removed.
+pub fn remove_did_prefix(
+    deps: DepsMut,
+    info: MessageInfo,
+    prefix: String,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut prefixes = config.did_prefixes.take()
+        .unwrap_or_else(|| DEFAULT_DID_PREFIXES.iter().map(|p| p.to_string()).collect());
+    if !prefixes.contains(&prefix) {
+        return Err(ContractError::InvalidInput(format!("DID prefix not accepted: {}", prefix)));
+    }
+    if prefixes.len() == 1 {
+        return Err(ContractError::InvalidInput("At least one DID prefix is required".to_string()));
+    }
+    prefixes.retain(|p| p != &prefix);
+    config.did_prefixes = Some(prefixes);
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_did_prefix")
+        .add_attribute("prefix", prefix))
+}
+
+/// Maximum number of hook subscribers
+const MAX_HOOK_SUBSCRIBERS: usize = 10;
+
+/// Reply ID of hook notifications. They are sent with `reply_on_error` and a failure only emits
+/// `detrack_hook_failed` (see `contract::reply`), so a broken subscriber cannot block the
+/// operation it is notified of.
+pub(crate) const HOOK_REPLY_ID: u64 = 1;
+
+/// Subscribes `contract_addr` to `events`, replacing its previous subscription.
+pub fn add_hook_subscriber(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract_addr: String,
+    mut events: Vec<HookEvent>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+    let subscriber = deps.api.addr_validate(&contract_addr)?;
+    if events.is_empty() {
+        return Err(ContractError::InvalidInput("At least one hook event is required".to_string()));
+    }
+    events.sort();
+    events.dedup();
+    if !HOOK_SUBSCRIBERS.has(deps.storage, &subscriber)
+        && HOOK_SUBSCRIBERS.keys(deps.storage, None, None, Order::Ascending).count() >= MAX_HOOK_SUBSCRIBERS
+    {
+        return Err(ContractError::InvalidInput(format!("At most {} hook subscribers are allowed", MAX_HOOK_SUBSCRIBERS)));
+    }
+    HOOK_SUBSCRIBERS.save(deps.storage, &subscriber, &events)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_hook_subscriber")
+        .add_attribute("contract_addr", subscriber.to_string())
+        .add_attribute("events", events.iter().map(HookEvent::key).collect::<Vec<_>>().join(",")))
+}
+
+/// Stops sending hook notifications to `contract_addr`.
+pub fn remove_hook_subscriber(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract_addr: String,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+    let subscriber = deps.api.addr_validate(&contract_addr)?;
+    if !HOOK_SUBSCRIBERS.has(deps.storage, &subscriber) {
+        return Err(ContractError::InvalidInput(format!("Not a hook subscriber: {}", subscriber)));
+    }
+    HOOK_SUBSCRIBERS.remove(deps.storage, &subscriber);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_hook_subscriber")
+        .add_attribute("contract_addr", subscriber.to_string()))
+}
+
+/// Delivers `msg` to every subscriber of its event.
+fn hook_messages(storage: &dyn Storage, msg: DetrackHookMsg) -> StdResult<Vec<SubMsg>> {
+    let event = msg.event();
+    let mut messages = vec![];
+    for item in HOOK_SUBSCRIBERS.range(storage, None, None, Order::Ascending) {
+        let (subscriber, events) = item?;
+        if events.contains(&event) {
+            messages.push(SubMsg::reply_on_error(msg.clone().into_cosmos_msg(subscriber)?, HOOK_REPLY_ID));
+        }
+    }
+    Ok(messages)
+}
+
+/// Sets how long full proofs are kept before compaction when their data owner's plan sets no
+/// retention. Applies to proofs stored from now on.
+pub fn configure_proof_retention(
+    deps: DepsMut,
+    info: MessageInfo,
+    retention_seconds: Option<u64>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.proof_retention_seconds = retention_seconds;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_proof_retention")
+        .add_attribute("retention_seconds", retention_seconds.map_or_else(|| "none".to_string(), |v| v.to_string())))
+}
+
+/// Sets the uc4e moved from a worker's escrow to the storing node per batch. Zero stops
+/// payments; escrows keep their balances.
+pub fn configure_escrow_price(
+    deps: DepsMut,
+    info: MessageInfo,
+    price_per_batch: Uint128,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.escrow_price_per_batch = price_per_batch;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_escrow_price")
+        .add_attribute("price_per_batch", price_per_batch.to_string()))
+}
+
+const DEFAULT_COMPACTION_LIMIT: u32 = 10;
+const MAX_COMPACTION_LIMIT: u32 = 30;
+
+/// Strips `metadata_json` and `original_data_reference` (of the proof and its batches) from
+/// proofs whose retention ended, scanning at most `limit` proofs after `start_after`. Hashes,
+/// merkle roots, time windows and energy values are kept, so compacted proofs still verify.
+/// Proofs under review or with open disputes keep their metadata as evidence.
+pub fn compact_proofs(
+    deps: DepsMut,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_COMPACTION_LIMIT).min(MAX_COMPACTION_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let scanned: Vec<(u64, Proof)> = proofs()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+
+    let mut compacted = 0u32;
+    let last_checked = scanned.last().map(|(id, _)| *id);
+    for (id, mut proof) in scanned {
+        let expired = proof.retain_until.is_some_and(|until| until <= env.block.time);
+        if !expired || proof.compacted || proof.under_review {
+            continue;
+        }
+        if OPEN_DISPUTES.prefix(id).range(deps.storage, None, None, Order::Ascending).next().is_some() {
+            continue;
+        }
+        proof.metadata_json = None;
+        proof.original_data_reference = None;
+        for batch in &mut proof.batch_metadata {
+            batch.metadata_json = None;
+            batch.original_data_reference = None;
+        }
+        proof.compacted = true;
+        proofs().save(deps.storage, id, &proof)?;
+        compacted += 1;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "compact_proofs")
+        .add_attribute("compacted", compacted.to_string())
+        .add_attribute("last_checked", last_checked.map_or_else(|| "none".to_string(), |id| id.to_string())))
+}
+
+/// Sets the largest run of skipped batch sequence numbers `store_proof` accepts per gateway.
+/// `None` accepts any gap; gaps are recorded either way.
+pub fn configure_sequence_gap_tolerance(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_gap: Option<u64>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.max_sequence_gap = max_gap;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_sequence_gap_tolerance")
+        .add_attribute("max_gap", max_gap.map_or_else(|| "none".to_string(), |v| v.to_string())))
+}
+
+/// Sets the automatic reputation scoring. A `proof_reward_cap` must lie within
+/// `Config.reputation_bounds`. `UpdateNodeReputation` still overrides any node's reputation.
+pub fn configure_reputation_scoring(
+    deps: DepsMut,
+    info: MessageInfo,
+    scoring: ReputationScoring,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    if let Some(cap) = scoring.proof_reward_cap.filter(|cap| !config.reputation_bounds.contains(*cap)) {
+        return Err(ContractError::ReputationOutOfBounds {
+            reputation: cap,
+            min: config.reputation_bounds.min,
+            max: config.reputation_bounds.max,
+        });
+    }
+    config.reputation_scoring = scoring.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_reputation_scoring")
+        .add_attribute("proof_reward", scoring.proof_reward.to_string())
+        .add_attribute(
+            "proof_reward_cap",
+            scoring.proof_reward_cap.map_or_else(|| "none".to_string(), |v| v.to_string()),
+        )
+        .add_attribute("upheld_dispute_penalty", scoring.upheld_dispute_penalty.to_string()))
+}
+
+/// Splits sent funds into deposit coins and their uc4e-equivalent value.
+/// uc4e counts at face value; other denoms must be listed in `Config.deposit_denoms`.
+fn deposit_value(config: &Config, funds: &[Coin]) -> Result<(Uint128, Vec<Coin>), ContractError> {
+    let mut value = Uint128::zero();
+    let mut coins = Vec::with_capacity(funds.len());
+    for coin in funds.iter().filter(|c| !c.amount.is_zero()) {
+        let weight = if coin.denom == "uc4e" {
+            Decimal::one()
+        } else {
+            config
+                .deposit_denoms
+                .iter()
+                .find(|d| d.denom == coin.denom)
+                .map(|d| d.weight)
+                .ok_or_else(|| ContractError::UnsupportedDepositDenom { denom: coin.denom.clone() })?
+        };
+        value += coin.amount.mul_floor(weight);
+        coins.push(coin.clone());
+    }
+    Ok((value, coins))
+}
+
+/// Merges `added` into `held`, summing amounts of the same denom.
+pub(crate) fn add_coins(held: &mut Vec<Coin>, added: Vec<Coin>) {
+    for coin in added {
+        match held.iter_mut().find(|c| c.denom == coin.denom) {
+            Some(existing) => existing.amount += coin.amount,
+            None => held.push(coin),
+        }
+    }
+}
+
+/// Records funds received into the contract's custody in the `LIABILITIES` ledger.
+pub(crate) fn owe(storage: &mut dyn Storage, coins: &[Coin]) -> StdResult<()> {
+    for coin in coins.iter().filter(|c| !c.amount.is_zero()) {
+        let owed = LIABILITIES.may_load(storage, &coin.denom)?.unwrap_or_default();
+        LIABILITIES.save(storage, &coin.denom, &(owed + coin.amount))?;
+    }
+    Ok(())
+}
+
+/// Removes funds that are paid out, or forfeited to the contract, from the `LIABILITIES` ledger.
+pub(crate) fn settle(storage: &mut dyn Storage, coins: &[Coin]) -> StdResult<()> {
+    for coin in coins.iter().filter(|c| !c.amount.is_zero()) {
+        let owed = LIABILITIES.may_load(storage, &coin.denom)?.unwrap_or_default().saturating_sub(coin.amount);
+        if owed.is_zero() {
+            LIABILITIES.remove(storage, &coin.denom);
+        } else {
+            LIABILITIES.save(storage, &coin.denom, &owed)?;
+        }
+    }
+    Ok(())
+}
+
+/// Recomputes the liabilities from the records holding funds; `LIABILITIES` must always
+/// equal it. Used to rebuild the ledger on migration and by the invariant checks.
+pub(crate) fn tracked_liabilities(storage: &dyn Storage) -> StdResult<BTreeMap<String, Uint128>> {
+    let mut liabilities: BTreeMap<String, Uint128> = BTreeMap::new();
+    let mut add = |coins: &[Coin]| {
+        for coin in coins.iter().filter(|c| !c.amount.is_zero()) {
+            *liabilities.entry(coin.denom.clone()).or_default() += coin.amount;
+        }
+    };
+    let uc4e = |amount: Uint128| [Coin { denom: "uc4e".to_string(), amount }];
+    for item in WHITELISTED_NODES.range(storage, None, None, Order::Ascending) {
+        let node = item?.1;
+        add(&node.deposit_coins);
+        add(&uc4e(node.accrued_rewards + node.escrow_earnings));
+    }
+    for item in UNLOCKING_DEPOSITS.range(storage, None, None, Order::Ascending) {
+        add(&item?.1.coins);
+    }
+    for item in PRE_REGISTRATIONS.range(storage, None, None, Order::Ascending) {
+        add(&item?.1.coins);
+    }
+    for item in HELD_DEPOSITS.range(storage, None, None, Order::Ascending) {
+        add(&item?.1.coins);
+    }
+    for item in PROOF_BONDS.range(storage, None, None, Order::Ascending) {
+        add(&uc4e(item?.1.amount));
+    }
+    for item in WORKER_ESCROWS.range(storage, None, None, Order::Ascending) {
+        add(&uc4e(item?.1.balance));
+    }
+    for item in DISPUTES.range(storage, None, None, Order::Ascending) {
+        let dispute = item?.1;
+        if dispute.status == DisputeStatus::Open {
+            add(&uc4e(dispute.bond));
+        }
+    }
+    let config = CONFIG.load(storage)?;
+    add(&uc4e(config.referral_pool));
+    add(&uc4e(config.reward_pool));
+    add(&uc4e(config.collected_fees));
+    Ok(liabilities)
+}
+
+/// Builds the transfers for `payouts`, refusing them if they would dip the contract's balance
+/// of a denom below its tracked liabilities. The paid-out funds must already be settled.
+///
+/// Errors:
+/// - `InsufficientFreeBalance` if the balance does not cover the liabilities plus the payouts
+fn checked_payouts(deps: Deps, env: &Env, payouts: Vec<(Addr, Vec<Coin>)>) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut requested: Vec<Coin> = vec![];
+    for (_, coins) in &payouts {
+        add_coins(&mut requested, coins.iter().filter(|c| !c.amount.is_zero()).cloned().collect());
+    }
+    for coin in requested {
+        let balance = query_denom_balance(&deps.querier, &env.contract.address, &coin.denom)?;
+        let liabilities = LIABILITIES.may_load(deps.storage, &coin.denom)?.unwrap_or_default();
+        if balance < liabilities + coin.amount {
+            return Err(ContractError::InsufficientFreeBalance {
+                denom: coin.denom,
+                balance,
+                liabilities,
+                requested: coin.amount,
+            });
+        }
+    }
+    let mut messages = vec![];
+    for (recipient, coins) in payouts {
+        messages.extend(send_coins(&recipient, coins)?);
+    }
+    Ok(messages)
+}
+
+/// Sets or clears the calling node's region/zone code.
+/// Access Control: Only a registered (or whitelisted) node can set its own region.
+/// Logic:
+/// 1. Validates the region against the admin-managed `REGIONS` list.
+/// 2. Moves the node's entry in the `REGION_NODES` index from the old region to the new one.
+/// 3. Updates the node's `region` and `last_updated` fields.
+///
+/// Errors:
+/// - `NodeNotRegistered` if the sender is not in `WHITELISTED_NODES`.
+/// - `RegionNotAllowed` if the region is not in the accepted list.
+pub fn set_region(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    region: Option<String>,
+) -> Result<Response, ContractError> {
+    let sender_str = info.sender.to_string();
+
+    let mut node = WHITELISTED_NODES.load(deps.storage, sender_str.clone())
+        .map_err(|_| ContractError::NodeNotRegistered { address: sender_str.clone() })?;
+
+    ensure_region_allowed(deps.storage, &region)?;
+
+    if let Some(old_region) = &node.region {
+        REGION_NODES.remove(deps.storage, (old_region, &node.address));
+    }
+    if let Some(new_region) = &region {
+        REGION_NODES.save(deps.storage, (new_region, &node.address), &())?;
+    }
+
+    node.region = region.clone();
+    node.last_updated = env.block.time;
+    WHITELISTED_NODES.save(deps.storage, sender_str.clone(), &node)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_region")
+        .add_attribute("node_address", sender_str)
+        .add_attribute("region", region.unwrap_or_else(|| "none".to_string())))
+}
+
+/// Pauses or resumes the calling node. A paused node stays registered with its deposit and
+/// reputation intact but fails `validate_node`, so it cannot store or verify proofs.
+pub fn set_node_paused(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let sender_str = info.sender.to_string();
+
+    let mut node = WHITELISTED_NODES.load(deps.storage, sender_str.clone())
+        .map_err(|_| ContractError::NodeNotRegistered { address: sender_str.clone() })?;
+    match (node.paused, paused) {
+        (true, true) => return Err(ContractError::NodePaused { address: sender_str }),
+        (false, false) => return Err(ContractError::NodeNotPaused { address: sender_str }),
+        _ => {}
+    }
+
+    node.paused = paused;
+    node.last_updated = env.block.time;
+    WHITELISTED_NODES.save(deps.storage, sender_str.clone(), &node)?;
+
+    Ok(Response::new()
+        .add_attribute("action", if paused { "pause_self" } else { "resume_self" })
+        .add_attribute("node_address", sender_str))
+}
+
+/// Declares (or, with `None`, withdraws) the reporting cadence of a gateway controlled by the
+/// calling node. Declaring requires the node to control the gateway's DID, and replaces a
+/// declaration made by a previous controller.
+pub fn declare_gateway(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    gateway_did: String,
+    reporting_interval_seconds: Option<u64>,
+) -> Result<Response, ContractError> {
+    let sender_str = info.sender.to_string();
+    let node = WHITELISTED_NODES.load(deps.storage, sender_str.clone())
+        .map_err(|_| ContractError::NodeNotRegistered { address: sender_str.clone() })?;
+
+    match reporting_interval_seconds {
+        Some(0) => return Err(ContractError::InvalidInput("Reporting interval must be positive".to_string())),
+        Some(interval) => {
+            verify_gateway_controller(&deps.as_ref(), &gateway_did, &node)?;
+            let cadence = GatewayCadence {
+                node: info.sender,
+                reporting_interval_seconds: interval,
+                declared_at: env.block.time,
+            };
+            GATEWAY_CADENCES.save(deps.storage, &gateway_did, &cadence)?;
+        }
+        None => {
+            if GATEWAY_CADENCES.may_load(deps.storage, &gateway_did)?.map(|c| c.node) != Some(info.sender) {
+                return Err(ContractError::GatewayNotDeclared { gateway_did, node: sender_str });
+            }
+            GATEWAY_CADENCES.remove(deps.storage, &gateway_did);
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "declare_gateway")
+        .add_attribute("node_address", sender_str)
+        .add_attribute("gateway_did", gateway_did)
+        .add_attribute(
+            "reporting_interval_seconds",
+            reporting_interval_seconds.map_or_else(|| "none".to_string(), |v| v.to_string()),
+        ))
+}
+
+/// Sets the probation of nodes registering from now on. Nodes already on probation keep the
+/// period end and attestation count set at their registration; the probation proof cap is
+/// read from the current policy.
+pub fn configure_probation(
+    deps: DepsMut,
+    info: MessageInfo,
+    policy: ProbationPolicy,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+    if policy.period_blocks == 0 && (policy.max_proofs_per_period.is_some() || policy.required_attestations > 0) {
+        return Err(ContractError::InvalidInput("Probation limits require a non-zero period".to_string()));
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.probation = policy.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_probation")
+        .add_attribute("period_blocks", policy.period_blocks.to_string())
+        .add_attribute("max_proofs_per_period", policy.max_proofs_per_period.map_or_else(|| "none".to_string(), |v| v.to_string()))
+        .add_attribute("required_attestations", policy.required_attestations.to_string()))
+}
+
+/// Sets the default limit on registered nodes per operator DID. Operators already above a
+/// lowered limit keep their nodes but cannot register more.
+pub fn configure_operator_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_nodes_per_operator: Option<u32>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.max_nodes_per_operator = max_nodes_per_operator;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_operator_limit")
+        .add_attribute("max_nodes_per_operator", max_nodes_per_operator.map_or_else(|| "none".to_string(), |v| v.to_string())))
+}
+
+/// Overrides the node limit of a single operator DID, e.g. for a vetted operator running a
+/// larger fleet. `None` returns the operator to `max_nodes_per_operator`.
+pub fn set_operator_node_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator_did: String,
+    max_nodes: Option<u32>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+    validate_did_format(&CONFIG.load(deps.storage)?, &operator_did, "operator")?;
+
+    match max_nodes {
+        Some(limit) => OPERATOR_NODE_LIMITS.save(deps.storage, &operator_did, &limit)?,
+        None => OPERATOR_NODE_LIMITS.remove(deps.storage, &operator_did),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_operator_node_limit")
+        .add_attribute("operator_did", operator_did)
+        .add_attribute("max_nodes", max_nodes.map_or_else(|| "none".to_string(), |v| v.to_string())))
+}
+
+/// Attests one of the first proofs of a node on probation. The attester must be an operational
+/// node that is not on probation itself and did not store the proof. Each attested proof counts
+/// towards the storing node's `required_attestations`.
+///
+/// Errors:
+/// - `AttestationNotRequired` if the proof does not await an attestation
+/// - `NodeOnProbation` if the attester is on probation
+/// - `Unauthorized` if the attester stored the proof
+/// - `ProofRejected` / `ProofRevoked` if the proof no longer stands
+pub fn attest_proof(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proof_id: u64,
+) -> Result<Response, ContractError> {
+    validate_node(&deps, &info)?;
+    let attester = WHITELISTED_NODES.load(deps.storage, info.sender.to_string())?;
+    if attester.on_probation(env.block.height) {
+        return Err(ContractError::NodeOnProbation { address: info.sender.to_string() });
+    }
+
+    let stored_by = PROBATION_PROOFS.may_load(deps.storage, proof_id)?
+        .ok_or(ContractError::AttestationNotRequired { proof_id })?;
+    if stored_by == info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let proof = proofs().load(deps.storage, proof_id)?;
+    if proof.rejected {
+        return Err(ContractError::ProofRejected { proof_id });
+    }
+    if proof.status != ProofStatus::Active {
+        return Err(ContractError::ProofRevoked { proof_id });
+    }
+    PROBATION_PROOFS.remove(deps.storage, proof_id);
+
+    let mut response = Response::new()
+        .add_attribute("action", "attest_proof")
+        .add_attribute("proof_id", proof_id.to_string())
+        .add_attribute("attester", info.sender.to_string())
+        .add_attribute("stored_by", stored_by.to_string());
+    // The storing node may have been removed since; the attestation is then only recorded
+    if let Some(mut node) = WHITELISTED_NODES.may_load(deps.storage, stored_by.to_string())? {
+        node.received_attestations += 1;
+        node.last_updated = env.block.time;
+        WHITELISTED_NODES.save(deps.storage, stored_by.to_string(), &node)?;
+        response = response
+            .add_attribute("pending_attestations", node.pending_attestations().to_string())
+            .add_attribute("on_probation", node.on_probation(env.block.height).to_string());
+    }
+
+    Ok(response)
+}
+
+/// Pauses or unpauses the contract. While paused, proof submissions, registrations and
+/// deposit operations are rejected (see `NodeExecuteMsg::is_pausable`); admin messages, queries
+/// and the remaining node messages keep working.
+pub fn set_contract_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    validate_admin(&deps, &info)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    match (config.paused, paused) {
+        (true, true) => return Err(ContractError::ContractPaused {}),
+        (false, false) => return Err(ContractError::ContractNotPaused {}),
+        _ => {}
+    }
+    config.paused = paused;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", if paused { "pause" } else { "unpause" }))
+}
+
+// ============================================================================
+// DATA OWNER PLANS
+// ============================================================================
+
+/// Creates or updates a data-owner subscription plan.
+/// Owners already on the plan pick up the new limits immediately.
+pub fn set_plan(
+    deps: DepsMut,
+    info: MessageInfo,
+    plan_id: String,
+    proofs_per_period: u64,
+    max_metadata_bytes: u32,
+    price: Uint128,
+    retention_seconds: Option<u64>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    if plan_id.is_empty() || plan_id.len() > 64 {
+        return Err(ContractError::InvalidInput("Plan ID must be 1-64 characters".to_string()));
+    }
+
+    let plan = Plan { proofs_per_period, max_metadata_bytes, price, retention_seconds };
+    PLANS.save(deps.storage, &plan_id, &plan)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_plan")
+        .add_attribute("plan_id", plan_id)
+        .add_attribute("proofs_per_period", proofs_per_period.to_string())
+        .add_attribute("max_metadata_bytes", max_metadata_bytes.to_string())
+        .add_attribute("price", price.to_string())
+        .add_attribute("retention_seconds", retention_seconds.map_or_else(|| "default".to_string(), |v| v.to_string())))
+}
+
+/// Removes a subscription plan. Owners still assigned to it have no active plan
+/// until they are assigned or subscribe to another one.
+pub fn remove_plan(
+    deps: DepsMut,
+    info: MessageInfo,
+    plan_id: String,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    if !PLANS.has(deps.storage, &plan_id) {
+        return Err(ContractError::PlanNotFound { plan_id });
+    }
+    PLANS.remove(deps.storage, &plan_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_plan")
+        .add_attribute("plan_id", plan_id))
+}
+
+/// Assigns a plan to a data owner, replacing any current plan.
+/// `expires_at: None` assigns the plan without expiry.
+pub fn assign_owner_plan(
+    deps: DepsMut,
+    info: MessageInfo,
+    data_owner: String,
+    plan_id: String,
+    expires_at: Option<Timestamp>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::Operator)?;
+
+    let owner = deps.api.addr_validate(&data_owner)
+        .map_err(|_| ContractError::InvalidDataOwner(data_owner))?;
+    if !PLANS.has(deps.storage, &plan_id) {
+        return Err(ContractError::PlanNotFound { plan_id });
+    }
+
+    OWNER_PLANS.save(deps.storage, &owner, &OwnerPlan { plan_id: plan_id.clone(), expires_at })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "assign_owner_plan")
+        .add_attribute("data_owner", owner.to_string())
+        .add_attribute("plan_id", plan_id)
+        .add_attribute("expires_at", expires_at.map_or_else(|| "never".to_string(), |t| t.to_string())))
+}
+
+/// Purchases a subscription plan for the sender, or extends it by one quota period.
+/// Access Control: Any address can subscribe for itself.
+/// Logic:
+/// 1. The plan must exist and have a non-zero price.
+/// 2. Exactly the plan price must be sent in uc4e.
+/// 3. Renewing the same plan extends from the current expiry; switching plans starts from now.
+/// 4. The payment is forwarded to the treasury when one is configured.
+///
+/// Errors:
+/// - `PlanNotFound`, `PlanNotPurchasable`, `InvalidPayment`
+pub fn subscribe_plan(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    plan_id: String,
+) -> Result<Response, ContractError> {
+    let plan = PLANS.may_load(deps.storage, &plan_id)?
+        .ok_or_else(|| ContractError::PlanNotFound { plan_id: plan_id.clone() })?;
+    if plan.price.is_zero() {
+        return Err(ContractError::PlanNotPurchasable { plan_id });
+    }
+
+    let paid = ensure_exact_payment(&info, plan.price)?;
+
+    let current = OWNER_PLANS.may_load(deps.storage, &info.sender)?;
+    let base = match &current {
+        Some(OwnerPlan { plan_id: current_id, expires_at: None }) if *current_id == plan_id => {
+            return Err(ContractError::CustomError("Plan already assigned without expiry".to_string()));
+        }
+        Some(OwnerPlan { plan_id: current_id, expires_at: Some(expiry) })
+            if *current_id == plan_id && *expiry > env.block.time => *expiry,
+        _ => env.block.time,
+    };
+    let expires_at = base.plus_seconds(QUOTA_PERIOD_SECONDS);
+
+    OWNER_PLANS.save(deps.storage, &info.sender, &OwnerPlan { plan_id: plan_id.clone(), expires_at: Some(expires_at) })?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "subscribe_plan")
+        .add_attribute("data_owner", info.sender.to_string())
+        .add_attribute("plan_id", plan_id)
+        .add_attribute("expires_at", expires_at.to_string());
+
+    if let Some(treasury) = CONFIG.load(deps.storage)?.treasury {
+        let payment = vec![Coin { denom: "uc4e".to_string(), amount: paid }];
+        response = response.add_messages(checked_payouts(deps.as_ref(), &env, vec![(treasury, payment)])?);
+    }
+
+    Ok(response)
+}
+
+/// Maximum length of the reason given when flagging a proof
+const MAX_FLAG_REASON_LENGTH: usize = 256;
+
+/// Flags a proof as incorrect on behalf of one of its owners (see `Proof::ownership`).
+///
+/// Each owner may flag a proof once. The proof is marked `under_review` until the admin
+/// clears its flags. Funds are optional: attaching exactly `Config.dispute_bond` also opens a
+/// dispute with the reason as its evidence.
+///
+/// Errors:
+/// - `NotProofOwner` if the proof is not attributed to the sender
+/// - `ProofAlreadyFlagged` if the sender already flagged it
+/// - `InvalidInput` if the reason is empty or longer than 256 characters
+/// - `InvalidPayment` if funds other than the dispute bond are attached
+pub fn flag_proof(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proof_id: u64,
+    reason: String,
+    owner: Option<String>,
+) -> Result<Response, ContractError> {
+    let owner = acting_owner(&deps, &info, owner)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    let bonded = !info.funds.is_empty();
+    if bonded {
+        ensure_exact_payment(&info, config.dispute_bond)?;
+    }
+    if reason.trim().is_empty() || reason.len() > MAX_FLAG_REASON_LENGTH {
+        return Err(ContractError::InvalidInput(format!("Reason must be 1-{} characters", MAX_FLAG_REASON_LENGTH)));
+    }
+    validate_text("reason", &reason, MAX_FLAG_REASON_LENGTH, false)?;
+
+    let mut proof = proofs().may_load(deps.storage, proof_id)?
+        .ok_or(ContractError::ProofNotFound(proof_id.to_string()))?;
+    if !proof.ownership().iter().any(|(o, _)| *o == owner) {
+        return Err(ContractError::NotProofOwner { proof_id, owner: owner.to_string() });
+    }
+
+    let mut flags = PROOF_FLAGS.may_load(deps.storage, proof_id)?.unwrap_or_default();
+    if flags.iter().any(|f| f.flagged_by == owner) {
+        return Err(ContractError::ProofAlreadyFlagged { proof_id, owner: owner.to_string() });
+    }
+    flags.push(ProofFlag { flagged_by: owner.clone(), reason: reason.clone(), flagged_at: env.block.time });
+    PROOF_FLAGS.save(deps.storage, proof_id, &flags)?;
+
+    proof.under_review = true;
+    proofs().save(deps.storage, proof_id, &proof)?;
+
+    let mut response = Response::new().add_event(
+        Event::new("flag_proof")
+            .add_attribute("action", "flag_proof")
+            .add_attribute("proof_id", proof_id.to_string())
+            .add_attribute("flagged_by", owner.to_string())
+            .add_attribute("delegate", if owner == info.sender { "none".to_string() } else { info.sender.to_string() })
+            .add_attribute("reason", escape_attribute_value(&reason)),
+    );
+    if bonded {
+        let (event, hooks) = open_dispute(deps.storage, &env, &mut config, &proof, &owner, reason)?;
+        response = response.add_event(event).add_submessages(hooks);
+    }
+    Ok(response)
+}
+
+/// Maximum number of delegates per data owner
+const MAX_OWNER_DELEGATES: usize = 10;
+
+/// Resolves the owner a data-owner message acts for: the sender itself, or the given `owner`
+/// when the sender is one of its delegates.
+fn acting_owner(deps: &DepsMut, info: &MessageInfo, owner: Option<String>) -> Result<Addr, ContractError> {
+    let Some(owner) = owner else {
+        return Ok(info.sender.clone());
+    };
+    let owner = deps.api.addr_validate(&owner)?;
+    if owner != info.sender && !OWNER_DELEGATES.has(deps.storage, (&owner, &info.sender)) {
+        return Err(ContractError::NotOwnerDelegate { owner: owner.to_string(), delegate: info.sender.to_string() });
+    }
+    Ok(owner)
+}
+
+/// Authorizes `delegate` to approve and flag the sender's proofs, e.g. an operations company
+/// running the sender's facilities. An owner can have at most 10 delegates.
+pub fn add_owner_delegate(
+    deps: DepsMut,
+    info: MessageInfo,
+    delegate: String,
+) -> Result<Response, ContractError> {
+    let delegate = deps.api.addr_validate(&delegate)?;
+    if delegate == info.sender {
+        return Err(ContractError::InvalidInput("An owner cannot delegate to itself".to_string()));
+    }
+    let count = OWNER_DELEGATES
+        .prefix(&info.sender)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count();
+    if !OWNER_DELEGATES.has(deps.storage, (&info.sender, &delegate)) && count >= MAX_OWNER_DELEGATES {
+        return Err(ContractError::InvalidInput(format!("An owner can have at most {} delegates", MAX_OWNER_DELEGATES)));
+    }
+    OWNER_DELEGATES.save(deps.storage, (&info.sender, &delegate), &())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_delegate")
+        .add_attribute("owner", info.sender.to_string())
+        .add_attribute("delegate", delegate.to_string()))
+}
+
+/// Withdraws a delegate's authorization. Approvals and flags it made stay in place.
+pub fn remove_owner_delegate(
+    deps: DepsMut,
+    info: MessageInfo,
+    delegate: String,
+) -> Result<Response, ContractError> {
+    let delegate = deps.api.addr_validate(&delegate)?;
+    if !OWNER_DELEGATES.has(deps.storage, (&info.sender, &delegate)) {
+        return Err(ContractError::NotOwnerDelegate { owner: info.sender.to_string(), delegate: delegate.to_string() });
+    }
+    OWNER_DELEGATES.remove(deps.storage, (&info.sender, &delegate));
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_delegate")
+        .add_attribute("owner", info.sender.to_string())
+        .add_attribute("delegate", delegate.to_string()))
+}
+
+/// Maximum number of proofs in one `approve_proofs` call
+const MAX_PROOFS_PER_APPROVAL: usize = 50;
+
+/// Approves proofs on behalf of one of their owners (see `Proof::ownership`), e.g. after
+/// reviewing the `UnapprovedProofs` list. Approvals are per owner; a proof counts as
+/// owner-approved in `VerifyHash` once all its owners approved it. Proofs the sender already
+/// approved are skipped.
+///
+/// Errors:
+/// - `InvalidInput` if no or more than 50 proof IDs are given
+/// - `ProofNotFound` if a proof does not exist
+/// - `NotProofOwner` if a proof is not attributed to the sender
+/// - `ProofAlreadyFlagged` if the sender flagged a proof
+/// - `ProofRejected` / `ProofRevoked` if a proof no longer stands
+pub fn approve_proofs(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proof_ids: Vec<u64>,
+    owner: Option<String>,
+) -> Result<Response, ContractError> {
+    let owner = acting_owner(&deps, &info, owner)?;
+    if proof_ids.is_empty() || proof_ids.len() > MAX_PROOFS_PER_APPROVAL {
+        return Err(ContractError::InvalidInput(format!("Between 1 and {} proof IDs are required", MAX_PROOFS_PER_APPROVAL)));
+    }
+
+    let mut approved = vec![];
+    let mut skipped = 0u64;
+    for proof_id in proof_ids {
+        let proof = proofs().may_load(deps.storage, proof_id)?
+            .ok_or(ContractError::ProofNotFound(proof_id.to_string()))?;
+        if !proof.ownership().iter().any(|(o, _)| *o == owner) {
+            return Err(ContractError::NotProofOwner { proof_id, owner: owner.to_string() });
+        }
+        if proof.status != ProofStatus::Active {
+            return Err(ContractError::ProofRevoked { proof_id });
+        }
+        if proof.rejected {
+            return Err(ContractError::ProofRejected { proof_id });
+        }
+        if PROOF_FLAGS.may_load(deps.storage, proof_id)?.is_some_and(|flags| flags.iter().any(|f| f.flagged_by == owner)) {
+            return Err(ContractError::ProofAlreadyFlagged { proof_id, owner: owner.to_string() });
+        }
+        if PROOF_APPROVALS.has(deps.storage, (&owner, proof_id)) {
+            skipped += 1;
+            continue;
+        }
+        PROOF_APPROVALS.save(deps.storage, (&owner, proof_id), &env.block.time)?;
+        approved.push(proof_id.to_string());
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "approve_proofs")
+        .add_attribute("owner", owner.to_string())
+        .add_attribute("delegate", if owner == info.sender { "none".to_string() } else { info.sender.to_string() })
+        .add_attribute("approved", if approved.is_empty() { "none".to_string() } else { approved.join(",") })
+        .add_attribute("already_approved", skipped.to_string()))
+}
+
+/// Maximum length of a dispute's evidence URI
+const MAX_EVIDENCE_URI_LENGTH: usize = 256;
+
+/// Disputes a proof, escrowing exactly `Config.dispute_bond` uc4e until the admin rules.
+/// Access Control: any registered node other than the proof's submitter, or one of the proof's
+/// owners (see `Proof::ownership`). Each may have one open dispute per proof.
+///
+/// Errors:
+/// - `Unauthorized` if the sender is neither a registered node nor an owner, or submitted the proof
+/// - `ProofRejected` if the proof was already ruled wrong
+/// - `ProofRevoked` if the admin revoked the proof
+/// - `DisputeAlreadyOpen` if the sender has an open dispute on the proof
+/// - `InvalidInput` if the evidence URI is empty or longer than 256 characters
+/// - `InvalidPayment` if the attached funds are not exactly the dispute bond
+pub fn raise_dispute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proof_id: u64,
+    evidence_uri: String,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_exact_payment(&info, config.dispute_bond)?;
+    if evidence_uri.trim().is_empty() || evidence_uri.len() > MAX_EVIDENCE_URI_LENGTH {
+        return Err(ContractError::InvalidInput(format!("Evidence URI must be 1-{} characters", MAX_EVIDENCE_URI_LENGTH)));
+    }
+    validate_text("evidence_uri", &evidence_uri, MAX_EVIDENCE_URI_LENGTH, false)?;
+
+    let proof = proofs().may_load(deps.storage, proof_id)?
+        .ok_or(ContractError::ProofNotFound(proof_id.to_string()))?;
+    let is_owner = proof.ownership().iter().any(|(owner, _)| *owner == info.sender);
+    let is_other_node = proof.stored_by != info.sender && WHITELISTED_NODES.has(deps.storage, info.sender.to_string());
+    if !is_owner && !is_other_node {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let (event, hooks) = open_dispute(deps.storage, &env, &mut config, &proof, &info.sender, evidence_uri)?;
+    Ok(Response::new().add_attribute("action", "raise_dispute").add_event(event).add_submessages(hooks))
+}
+
+/// Records an open dispute on `proof` with `config.dispute_bond` escrowed and returns its
+/// `detrack_dispute_raised` event and hook notifications. Saves the config with the advanced
+/// dispute counter.
+fn open_dispute(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &mut Config,
+    proof: &Proof,
+    raised_by: &Addr,
+    evidence_uri: String,
+) -> Result<(Event, Vec<SubMsg>), ContractError> {
+    if proof.rejected {
+        return Err(ContractError::ProofRejected { proof_id: proof.id });
+    }
+    if proof.status != ProofStatus::Active {
+        return Err(ContractError::ProofRevoked { proof_id: proof.id });
+    }
+    if OPEN_DISPUTES.has(storage, (proof.id, raised_by)) {
+        return Err(ContractError::DisputeAlreadyOpen { proof_id: proof.id, raised_by: raised_by.to_string() });
+    }
+
+    let dispute_id = config.dispute_count;
+    config.dispute_count += 1;
+    CONFIG.save(storage, config)?;
+    DISPUTES.save(storage, dispute_id, &Dispute {
+        proof_id: proof.id,
+        raised_by: raised_by.clone(),
+        evidence_uri: evidence_uri.clone(),
+        bond: config.dispute_bond,
+        status: DisputeStatus::Open,
+        raised_at: env.block.time,
+        resolved_at: None,
+    })?;
+    OPEN_DISPUTES.save(storage, (proof.id, raised_by), &dispute_id)?;
+    let open = OPEN_DISPUTE_COUNT.may_load(storage)?.unwrap_or_default();
+    OPEN_DISPUTE_COUNT.save(storage, &(open + 1))?;
+    NODE_OPEN_DISPUTES.save(storage, (&proof.stored_by, dispute_id), &())?;
+    owe(storage, &coins(config.dispute_bond.u128(), "uc4e"))?;
+
+    let event = Event::new("detrack_dispute_raised")
+        .add_attribute("dispute_id", dispute_id.to_string())
+        .add_attribute("proof_id", proof.id.to_string())
+        .add_attribute("raised_by", raised_by.to_string())
+        .add_attribute("evidence_uri", escape_attribute_value(&evidence_uri))
+        .add_attribute("bond", config.dispute_bond.to_string());
+    let hooks = hook_messages(storage, DetrackHookMsg::DisputeOpened {
+        dispute_id,
+        proof_id: proof.id,
+        node: proof.stored_by.to_string(),
+        raised_by: raised_by.to_string(),
+    })?;
+    Ok((event, hooks))
+}
+
+/// Rules on an open dispute.
+///
+/// `Upheld` refunds the bond to the raiser and marks the proof rejected. The submitter, if still
+/// registered, loses `reputation_penalty` (clamped to `Config.reputation_bounds`), has its
+/// `disputed_proofs` incremented and, with `slash_bps`, loses that share of its deposit; a
+/// resulting deposit shortfall starts its top-up deadline. A removed submitter is slashed from
+/// its held deposit instead. Any deposit the submitter is unlocking is slashed by the same share.
+/// The proof's bond, if still held, is forfeited with the slashed funds, which are split per
+/// `Config.slash_distribution`: the raiser receives the challenger share, the burn share is
+/// burned and the rest goes to the treasury. `Rejected`
+/// forfeits the bond to the treasury. Without a treasury, forfeited funds and the treasury's
+/// share of slashed funds stay in the contract. Resolving the last open dispute against a removed
+/// node's proofs refunds its held deposit.
+///
+/// With `Config.auto_jail` set, an upheld ruling that brings the submitter's `disputed_proofs`
+/// to the policy's threshold or above also jails it for the policy's term.
+///
+/// `TombstoneNode` is an upheld ruling that slashes the submitter's whole deposit (registered,
+/// unlocking or held) and records it in `TOMBSTONED_NODES`, barring it from registering again. A
+/// registered submitter is removed, its unclaimed rewards returning to the reward pool. Its other
+/// proofs still awaiting finality (bond not yet releasable, or unattested probation proofs) are
+/// flagged for review by the contract itself.
+pub fn resolve_dispute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    dispute_id: u64,
+    verdict: DisputeVerdict,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::Slasher)?;
+
+    let mut dispute = DISPUTES
+        .may_load(deps.storage, dispute_id)?
+        .ok_or(ContractError::DisputeNotFound { dispute_id })?;
+    if dispute.status != DisputeStatus::Open {
+        return Err(ContractError::DisputeNotOpen { dispute_id });
+    }
+    let mut config = CONFIG.load(deps.storage)?;
+    let bond = coins(dispute.bond.u128(), "uc4e");
+    // The bond is paid out or forfeited to the contract either way
+    settle(deps.storage, &bond)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "resolve_dispute")
+        .add_attribute("dispute_id", dispute_id.to_string())
+        .add_attribute("proof_id", dispute.proof_id.to_string());
+    let mut payouts: Vec<(Addr, Vec<Coin>)> = vec![];
+    let mut proof = proofs().load(deps.storage, dispute.proof_id)?;
+    let mut held = HELD_DEPOSITS.may_load(deps.storage, &proof.stored_by)?;
+    // Tombstoning is an upheld ruling that takes the whole deposit
+    let (verdict, tombstone) = match verdict {
+        DisputeVerdict::TombstoneNode => (DisputeVerdict::Upheld { reputation_penalty: 0, slash_bps: Some(10_000) }, true),
+        verdict => (verdict, false),
+    };
+    match verdict {
+        DisputeVerdict::Upheld { reputation_penalty, slash_bps } => {
+            if slash_bps.is_some_and(|bps| bps == 0 || bps > 10_000) {
+                return Err(ContractError::InvalidInput("slash_bps must be between 1 and 10000".to_string()));
+            }
+            dispute.status = DisputeStatus::Upheld;
+            payouts.push((dispute.raised_by.clone(), bond));
+
+            if !proof.rejected && proof.status == ProofStatus::Active {
+                record_worker_energy(deps.storage, &proof, true)?;
+            }
+            proof.rejected = true;
+            proofs().save(deps.storage, dispute.proof_id, &proof)?;
+
+            let mut slashed = vec![];
+            let mut slashed_value = None;
+            if let Some(mut node) = WHITELISTED_NODES.may_load(deps.storage, proof.stored_by.to_string())? {
+                let penalty = i64::from(reputation_penalty) + i64::from(config.reputation_scoring.upheld_dispute_penalty);
+                node.reputation = config.reputation_bounds.apply(node.reputation, -penalty);
+                node.disputed_proofs += 1;
+                node.last_updated = env.block.time;
+                if let Some(policy) = config.auto_jail.as_ref().filter(|p| !tombstone && node.disputed_proofs >= p.disputed_proofs) {
+                    response = response.add_event(jail(&mut node, env.block.height + policy.jail_blocks, "disputed_proofs"));
+                }
+                if let Some(bps) = slash_bps {
+                    let deposit_before = node.deposit;
+                    slashed = slash_deposit(&mut node, bps);
+                    slashed_value = Some(deposit_before - node.deposit);
+                    // A tombstoned node has nothing left to top up
+                    if !tombstone {
+                        if let Some(event) = flag_deposit_shortfall(&config, &env, &mut node) {
+                            response = response.add_event(event);
+                        }
+                    }
+                }
+                if tombstone {
+                    let escrow_earnings = deregister_node(deps.storage, &mut config, &node)?;
+                    if !escrow_earnings.is_empty() {
+                        response = response.add_attribute("escrow_earnings_paid", escrow_earnings[0].to_string());
+                        payouts.push((node.address.clone(), escrow_earnings));
+                    }
+                } else {
+                    WHITELISTED_NODES.save(deps.storage, proof.stored_by.to_string(), &node)?;
+                }
+            } else if let (Some(held), Some(bps)) = (held.as_mut(), slash_bps) {
+                // The node was removed; slash the deposit held for its disputes instead
+                let value = held.amount.multiply_ratio(u128::from(bps), 10_000u128);
+                held.amount -= value;
+                slashed = slash_coins(&mut held.coins, bps);
+                slashed_value = Some(value);
+            }
+            if let Some(bps) = slash_bps {
+                // A deposit the node started unlocking before the ruling is slashed as well
+                if let Some((value, coins)) = slash_unlocking_deposit(deps.storage, &proof.stored_by, bps)? {
+                    slashed_value = Some(slashed_value.unwrap_or_default() + value);
+                    add_coins(&mut slashed, coins);
+                }
+            }
+            if let Some(value) = slashed_value {
+                record_slash(deps.storage, &env, value)?;
+            }
+            settle(deps.storage, &slashed)?;
+            // The proof's bond is forfeited and split like the slashed deposit
+            let mut forfeited = slashed.clone();
+            let forfeited_bond = match PROOF_BONDS.may_load(deps.storage, dispute.proof_id)? {
+                Some(proof_bond) => {
+                    PROOF_BONDS.remove(deps.storage, dispute.proof_id);
+                    proof_bond.amount
+                }
+                None => Uint128::zero(),
+            };
+            if !forfeited_bond.is_zero() {
+                let bond_coins = coins(forfeited_bond.u128(), "uc4e");
+                settle(deps.storage, &bond_coins)?;
+                add_coins(&mut forfeited, bond_coins);
+            }
+            if !slashed.is_empty() {
+                response = response.add_submessages(hook_messages(deps.storage, DetrackHookMsg::NodeSlashed {
+                    node: proof.stored_by.to_string(),
+                    slashed: slashed.clone(),
+                    dispute_id: Some(dispute_id),
+                })?);
+            }
+            response = response
+                .add_attribute("verdict", if tombstone { "tombstone_node" } else { "upheld" })
+                .add_attribute("node_address", proof.stored_by.to_string())
+                .add_attribute("reputation_penalty", reputation_penalty.to_string())
+                .add_attribute(
+                    "slashed",
+                    if slashed.is_empty() {
+                        "none".to_string()
+                    } else {
+                        slashed.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(",")
+                    },
+                )
+                .add_attribute("forfeited_bond", forfeited_bond.to_string());
+            // The challenger's reward and the burned share come out of the slashed deposit and bond
+            let (reward, burned, remainder) = split_slashed(&forfeited, &config.slash_distribution);
+            let listed = |coins: &[Coin]| {
+                if coins.is_empty() {
+                    "none".to_string()
+                } else {
+                    coins.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(",")
+                }
+            };
+            response = response
+                .add_attribute("challenger_reward", listed(&reward))
+                .add_attribute("burned", listed(&burned))
+                .add_messages(burn_coins(burned)?);
+            payouts.push((dispute.raised_by.clone(), reward));
+            if let Some(treasury) = &config.treasury {
+                payouts.push((treasury.clone(), remainder));
+            }
+            if tombstone {
+                response = response.add_event(tombstone_node(deps.storage, &env, &proof.stored_by, dispute_id)?);
+            }
+        }
+        DisputeVerdict::TombstoneNode => unreachable!("converted to an upheld ruling above"),
+        DisputeVerdict::Rejected => {
+            dispute.status = DisputeStatus::Rejected;
+            response = response.add_attribute("verdict", "rejected");
+            if let Some(treasury) = &config.treasury {
+                payouts.push((treasury.clone(), bond));
+            }
+        }
+    }
+
+    dispute.resolved_at = Some(env.block.time);
+    DISPUTES.save(deps.storage, dispute_id, &dispute)?;
+    response = response.add_submessages(hook_messages(deps.storage, DetrackHookMsg::DisputeResolved {
+        dispute_id,
+        proof_id: dispute.proof_id,
+        node: proof.stored_by.to_string(),
+        upheld: dispute.status == DisputeStatus::Upheld,
+    })?);
+    OPEN_DISPUTES.remove(deps.storage, (dispute.proof_id, &dispute.raised_by));
+    let open = OPEN_DISPUTE_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    OPEN_DISPUTE_COUNT.save(deps.storage, &open.saturating_sub(1))?;
+    NODE_OPEN_DISPUTES.remove(deps.storage, (&proof.stored_by, dispute_id));
+
+    // Release a removed node's held deposit once no dispute against its proofs remains open
+    if let Some(held) = held {
+        let still_open = NODE_OPEN_DISPUTES
+            .prefix(&proof.stored_by)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_some();
+        if still_open {
+            HELD_DEPOSITS.save(deps.storage, &proof.stored_by, &held)?;
+        } else {
+            HELD_DEPOSITS.remove(deps.storage, &proof.stored_by);
+            response = response.add_event(
+                Event::new("detrack_held_deposit_released")
+                    .add_attribute("node_address", proof.stored_by.to_string())
+                    .add_attribute(
+                        "refunded",
+                        if held.coins.is_empty() {
+                            "none".to_string()
+                        } else {
+                            held.coins.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(",")
+                        },
+                    ),
+            );
+            settle(deps.storage, &held.coins)?;
+            payouts.push((proof.stored_by.clone(), held.coins));
+        }
+    }
+
+    Ok(response.add_messages(checked_payouts(deps.as_ref(), &env, payouts)?))
+}
+
+/// Records `node` as tombstoned by `dispute_id` and flags its proofs that are not yet final for
+/// review: proofs whose bond is not yet releasable and probation proofs awaiting attestation.
+/// Returns the `detrack_node_tombstoned` event.
+fn tombstone_node(storage: &mut dyn Storage, env: &Env, node: &Addr, dispute_id: u64) -> StdResult<Event> {
+    TOMBSTONED_NODES.save(storage, node, &dispute_id)?;
+
+    let mut pending = BTreeSet::new();
+    for entry in PROOF_BONDS.range(storage, None, None, Order::Ascending) {
+        let (proof_id, bond) = entry?;
+        if bond.node == *node && env.block.height < bond.release_at_block {
+            pending.insert(proof_id);
+        }
+    }
+    for entry in PROBATION_PROOFS.range(storage, None, None, Order::Ascending) {
+        let (proof_id, stored_by) = entry?;
+        if stored_by == *node {
+            pending.insert(proof_id);
+        }
+    }
+
+    let reason = format!("node tombstoned by dispute {}", dispute_id);
+    let mut flagged = vec![];
+    for proof_id in pending {
+        let mut proof = proofs().load(storage, proof_id)?;
+        if proof.rejected || proof.status != ProofStatus::Active {
+            continue;
+        }
+        let mut flags = PROOF_FLAGS.may_load(storage, proof_id)?.unwrap_or_default();
+        flags.push(ProofFlag { flagged_by: env.contract.address.clone(), reason: reason.clone(), flagged_at: env.block.time });
+        PROOF_FLAGS.save(storage, proof_id, &flags)?;
+        proof.under_review = true;
+        proofs().save(storage, proof_id, &proof)?;
+        flagged.push(proof_id.to_string());
+    }
+
+    Ok(Event::new("detrack_node_tombstoned")
+        .add_attribute("node_address", node.to_string())
+        .add_attribute("dispute_id", dispute_id.to_string())
+        .add_attribute("flagged_proofs", if flagged.is_empty() { "none".to_string() } else { flagged.join(",") }))
+}
+
+/// Applies `update` to the stats of the current epoch.
+fn record_epoch_stats(
+    storage: &mut dyn Storage,
+    env: &Env,
+    update: impl FnOnce(&mut EpochStats),
+) -> StdResult<()> {
+    let epoch = env.block.time.seconds() / QUOTA_PERIOD_SECONDS;
+    let mut stats = EPOCH_STATS.may_load(storage, epoch)?.unwrap_or_default();
+    update(&mut stats);
+    EPOCH_STATS.save(storage, epoch, &stats)
+}
+
+/// Adds a proof's energy values to its worker's `WORKER_ENERGY` bucket, or with `remove` takes
+/// them back out when the proof is rejected or revoked. Proofs without a unit are not tracked.
+pub(crate) fn record_worker_energy(storage: &mut dyn Storage, proof: &Proof, remove: bool) -> StdResult<()> {
+    let Some(unit) = &proof.unit else {
+        return Ok(());
+    };
+    let key = (proof.worker_did.as_str(), proof.tw_start.seconds() / ENERGY_BUCKET_SECONDS, unit.as_str());
+    let mut bucket = WORKER_ENERGY.may_load(storage, key)?.unwrap_or_default();
+    let (value_in, value_out) = (proof.value_in.unwrap_or_default(), proof.value_out.unwrap_or_default());
+    if remove {
+        bucket.value_in = bucket.value_in.saturating_sub(value_in);
+        bucket.value_out = bucket.value_out.saturating_sub(value_out);
+        bucket.proofs = bucket.proofs.saturating_sub(1);
+    } else {
+        bucket.value_in += value_in;
+        bucket.value_out += value_out;
+        bucket.proofs += 1;
+    }
+    if bucket.proofs == 0 {
+        WORKER_ENERGY.remove(storage, key);
+        Ok(())
+    } else {
+        WORKER_ENERGY.save(storage, key, &bucket)
+    }
+}
+
+fn record_slash(storage: &mut dyn Storage, env: &Env, value: Uint128) -> StdResult<()> {
+    record_epoch_stats(storage, env, |stats| {
+        stats.slashes += 1;
+        stats.slashed_value += value;
+    })
+}
+
+/// Sets what `close_epoch` carries into the next epoch. Applies to epochs closed from now on;
+/// the quota carry-over limit applies when an owner's quota next rolls over.
+pub fn configure_carry_over(
+    deps: DepsMut,
+    info: MessageInfo,
+    rules: CarryOverRules,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.carry_over = rules.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_carry_over")
+        .add_attribute("expire_unclaimed_rewards", rules.expire_unclaimed_rewards.to_string())
+        .add_attribute("max_quota_carry_over", rules.max_quota_carry_over.to_string())
+        .add_attribute("expire_unfinished_reviews", rules.expire_unfinished_reviews.to_string()))
+}
+
+/// Nodes and flagged proofs handled by one `close_epoch` call
+pub const CLOSE_EPOCH_PAGE_SIZE: usize = 100;
+
+/// Emits the `detrack_epoch_closed` summary of an ended epoch and marks it closed. Callable by
+/// anyone, once per epoch; epochs without activity are closed with zero totals. The active
+/// node count is taken when the epoch is closed.
+///
+/// Applies `Config.carry_over`: unclaimed rewards of nodes that accrued none after the epoch
+/// return to the reward pool, and reviews last flagged before its end without an open dispute
+/// are closed, when the rules say so. The summary reports what was carried and what expired.
+///
+/// Each call handles at most `CLOSE_EPOCH_PAGE_SIZE` nodes and flagged proofs, so the crank
+/// stays within gas however large the registry grows. A call that does not reach the end saves
+/// its cursor and totals in the epoch's `EpochStats.closing` and reports `complete: false`; the
+/// next call continues from there, and the one that finishes emits the summary.
+///
+/// The alert thresholds are evaluated against the state after closing, adding a `detrack_alert`
+/// event for each breach as `check_alerts` does.
+///
+/// Errors:
+/// - `EpochNotEnded` if the epoch is the current one or lies in the future
+/// - `EpochAlreadyClosed` if the summary was already emitted
+pub fn close_epoch(deps: DepsMut, env: Env, epoch: u64) -> Result<Response, ContractError> {
+    if epoch >= env.block.time.seconds() / QUOTA_PERIOD_SECONDS {
+        return Err(ContractError::EpochNotEnded { epoch });
     }
-    
-    // Phase 1b: Verify all Gateway DIDs in batch_metadata
-    for batch in &batch_metadata {
-        verify_did(&deps.as_ref(), &batch.gateway_did, "gateway")?;
+    let mut stats = EPOCH_STATS.may_load(deps.storage, epoch)?.unwrap_or_default();
+    if stats.closed {
+        return Err(ContractError::EpochAlreadyClosed { epoch });
     }
-    
-    // Validate data_hash
-    if data_hash.is_empty() {
-        return Err(ContractError::InvalidInput("Data hash cannot be empty".to_string()));
+    let mut closing = stats.closing.take().unwrap_or_default();
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let rules = config.carry_over.clone();
+    let mut budget = CLOSE_EPOCH_PAGE_SIZE;
+    if !closing.nodes_done {
+        let start = closing.nodes_after.clone().map(Bound::exclusive);
+        let nodes: Vec<Node> = WHITELISTED_NODES
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(budget)
+            .map(|item| item.map(|(_, node)| node))
+            .collect::<StdResult<_>>()?;
+        closing.nodes_done = nodes.len() < budget;
+        budget -= nodes.len();
+        let mut expired = Uint128::zero();
+        for mut node in nodes {
+            closing.nodes_after = Some(node.address.to_string());
+            if node.tier > 0 && !node.paused && node.jailed_until.is_none() && node.reputation >= tier_reputation_requirement(&config, node.tier) {
+                closing.active_nodes += 1;
+            }
+            // Only pool-funded rewards expire; `escrow_earnings` was paid by data owners and stays
+            if node.accrued_rewards.is_zero() {
+                continue;
+            }
+            let last_accrual = REWARD_EPOCHS.may_load(deps.storage, &node.address)?.unwrap_or_default();
+            if rules.expire_unclaimed_rewards && last_accrual <= epoch {
+                expired += node.accrued_rewards;
+                node.accrued_rewards = Uint128::zero();
+                WHITELISTED_NODES.save(deps.storage, node.address.to_string(), &node)?;
+            } else {
+                closing.rewards_carried += node.accrued_rewards;
+            }
+        }
+        if !expired.is_zero() {
+            closing.rewards_expired += expired;
+            config.reward_pool += expired;
+            CONFIG.save(deps.storage, &config)?;
+        }
     }
-    
-    if data_hash.len() != 64 || !data_hash.chars().all(|c| c.is_ascii_hexdigit()) {
-        return Err(ContractError::InvalidInput("Data hash must be 64 hex characters".to_string()));
+
+    // Reviews whose last flag predates the end of the epoch, and that no open dispute backs
+    let epoch_end = Timestamp::from_seconds((epoch + 1) * QUOTA_PERIOD_SECONDS);
+    let mut reviews_done = false;
+    if closing.nodes_done {
+        let start = closing.reviews_after.map(Bound::exclusive);
+        let reviews: Vec<(u64, Vec<ProofFlag>)> = PROOF_FLAGS
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(budget)
+            .collect::<StdResult<_>>()?;
+        reviews_done = reviews.len() < budget;
+        for (proof_id, flags) in reviews {
+            closing.reviews_after = Some(proof_id);
+            let stale = flags.iter().all(|flag| flag.flagged_at < epoch_end);
+            let disputed = OPEN_DISPUTES.prefix(proof_id).keys(deps.storage, None, None, Order::Ascending).next().is_some();
+            if rules.expire_unfinished_reviews && stale && !disputed {
+                PROOF_FLAGS.remove(deps.storage, proof_id);
+                let mut proof = proofs().load(deps.storage, proof_id)?;
+                proof.under_review = false;
+                proofs().save(deps.storage, proof_id, &proof)?;
+                closing.reviews_closed += 1;
+            } else {
+                closing.reviews_carried += 1;
+            }
+        }
     }
-    
-    // Check if proof already exists
-    if PROOF_BY_HASH.has(deps.storage, &data_hash) {
-        return Err(ContractError::ProofAlreadyExists(data_hash));
+    if !reviews_done {
+        stats.closing = Some(closing);
+        EPOCH_STATS.save(deps.storage, epoch, &stats)?;
+        return Ok(Response::new()
+            .add_attribute("action", "close_epoch")
+            .add_attribute("epoch", epoch.to_string())
+            .add_attribute("complete", "false"));
     }
-    
-    // Increment proof count
-    let proof_id = config.proof_count;
-    config.proof_count += 1;
-    CONFIG.save(deps.storage, &config)?;
-    
-    // Create new proof (Phase 1b structure)
-    let proof = Proof {
-        id: proof_id,
-        worker_did: worker_did.clone(),
-        data_hash: data_hash.clone(),
-        tw_start,
-        tw_end,
-        batch_metadata: batch_metadata.clone(),
-        original_data_reference,
-        metadata_json,
-        stored_at: env.block.time,
-        stored_by: info.sender.clone(),
+    stats.closed = true;
+    EPOCH_STATS.save(deps.storage, epoch, &stats)?;
+
+    let energy = |value: fn(&EnergyTotal) -> Uint128| {
+        if stats.energy.is_empty() {
+            "none".to_string()
+        } else {
+            stats.energy.iter().map(|t| format!("{}{}", value(t), t.unit)).collect::<Vec<_>>().join(",")
+        }
     };
-    
-    // Save proof with IndexedMap (auto-indexes by worker_did)
-    proofs().save(deps.storage, proof_id, &proof)?;
-    
-    // Index proof by hash
-    PROOF_BY_HASH.save(deps.storage, &data_hash, &proof_id)?;
-    
-    // Phase 1b: Index by gateway DIDs (manual index)
-    for batch in &batch_metadata {
-        GATEWAY_PROOFS.save(
-            deps.storage,
-            (&batch.gateway_did, proof_id),
-            &(),
-        )?;
+    let event = Event::new("detrack_epoch_closed")
+        .add_attribute("epoch", epoch.to_string())
+        .add_attribute("start_time", (epoch * QUOTA_PERIOD_SECONDS).to_string())
+        .add_attribute("end_time", ((epoch + 1) * QUOTA_PERIOD_SECONDS).to_string())
+        .add_attribute("proofs_stored", stats.proofs_stored.to_string())
+        .add_attribute("energy_in", energy(|t| t.value_in))
+        .add_attribute("energy_out", energy(|t| t.value_out))
+        .add_attribute("rewards_distributed", stats.rewards_distributed.to_string())
+        .add_attribute("slashes", stats.slashes.to_string())
+        .add_attribute("slashed_value", stats.slashed_value.to_string())
+        .add_attribute("active_nodes", closing.active_nodes.to_string())
+        .add_attribute("rewards_carried", closing.rewards_carried.to_string())
+        .add_attribute("rewards_expired", closing.rewards_expired.to_string())
+        .add_attribute("max_quota_carry_over", rules.max_quota_carry_over.to_string())
+        .add_attribute("reviews_carried", closing.reviews_carried.to_string())
+        .add_attribute("reviews_closed", closing.reviews_closed.to_string());
+    let alerts = alert_events(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "close_epoch")
+        .add_attribute("epoch", epoch.to_string())
+        .add_attribute("complete", "true")
+        .add_attribute("alerts", alerts.len().to_string())
+        .add_event(event)
+        .add_events(alerts))
+}
+
+/// Removes `bps` basis points from a node's deposit value and from each of its deposit coins,
+/// returning the removed coins. Callers save the node.
+pub(crate) fn slash_deposit(node: &mut Node, bps: u16) -> Vec<Coin> {
+    node.deposit -= node.deposit.multiply_ratio(u128::from(bps), 10_000u128);
+    slash_coins(&mut node.deposit_coins, bps)
+}
+
+/// Removes `bps` basis points from an unlocking deposit's value and coins, returning the removed
+/// value and coins. Callers save the entry.
+pub(crate) fn slash_unlocking(unlocking: &mut UnlockingDeposit, bps: u16) -> (Uint128, Vec<Coin>) {
+    let value = unlocking.amount.multiply_ratio(u128::from(bps), 10_000u128);
+    unlocking.amount -= value;
+    (value, slash_coins(&mut unlocking.coins, bps))
+}
+
+/// Slashes the deposit `node` is unlocking, if any, removing the entry once nothing is left.
+fn slash_unlocking_deposit(storage: &mut dyn Storage, node: &Addr, bps: u16) -> StdResult<Option<(Uint128, Vec<Coin>)>> {
+    let Some(mut unlocking) = UNLOCKING_DEPOSITS.may_load(storage, node.to_string())? else {
+        return Ok(None);
+    };
+    let slashed = slash_unlocking(&mut unlocking, bps);
+    set_unlocking_deposit(storage, node.as_str(), Some(&unlocking).filter(|u| !u.coins.is_empty()))?;
+    Ok(Some(slashed))
+}
+
+/// Saves the unlocking deposit of `address`, or removes it with `None`, adjusting
+/// `UNLOCKING_TOTAL` by the change in its amount.
+pub(crate) fn set_unlocking_deposit(storage: &mut dyn Storage, address: &str, entry: Option<&UnlockingDeposit>) -> StdResult<()> {
+    let previous = UNLOCKING_DEPOSITS.may_load(storage, address.to_string())?.map(|u| u.amount).unwrap_or_default();
+    let total = UNLOCKING_TOTAL.may_load(storage)?.unwrap_or_default() - previous;
+    match entry {
+        Some(entry) => {
+            UNLOCKING_DEPOSITS.save(storage, address.to_string(), entry)?;
+            UNLOCKING_TOTAL.save(storage, &(total + entry.amount))
+        }
+        None => {
+            UNLOCKING_DEPOSITS.remove(storage, address.to_string());
+            UNLOCKING_TOTAL.save(storage, &total)
+        }
     }
-    
-    // Build event attributes
-    let mut event = Event::new("store_proof")
-        .add_attribute("action", "store_proof")
-        .add_attribute("proof_id", proof_id.to_string())
-        .add_attribute("worker_did", worker_did)
-        .add_attribute("data_hash", data_hash)
-        .add_attribute("stored_by", info.sender.to_string())
-        .add_attribute("batch_count", batch_metadata.len().to_string())
-        .add_attribute("tw_start", tw_start.to_string())
-        .add_attribute("tw_end", tw_end.to_string());
-    
-    // Add gateway DIDs to event (comma-separated)
-    let gateway_dids: Vec<String> = batch_metadata.iter()
-        .map(|b| b.gateway_did.clone())
-        .collect();
-    event = event.add_attribute("gateway_dids", gateway_dids.join(","));
-    
+}
+
+/// Maximum length of a `slash_node` reason
+const MAX_SLASH_REASON_LENGTH: usize = 256;
+
+/// Jails `node` until block `until`, keeping a longer term already running, and returns the
+/// `detrack_node_jailed` event. Callers save the node.
+fn jail(node: &mut Node, until: u64, reason: &str) -> Event {
+    let until = node.jailed_until.map_or(until, |current| current.max(until));
+    node.jailed_until = Some(until);
+    Event::new("detrack_node_jailed")
+        .add_attribute("node_address", node.address.to_string())
+        .add_attribute("jailed_until", until.to_string())
+        .add_attribute("reason", reason)
+}
+
+/// Jails a registered node for `blocks` blocks. A jailed node fails `validate_node` and stays
+/// jailed until it calls `Unjail` after its term; jailing a jailed node keeps the longer term.
+///
+/// Errors:
+/// - `InvalidInput` if `blocks` is zero
+/// - `NodeNotRegistered` if the address is not a registered node
+pub fn jail_node(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    node_address: String,
+    blocks: u64,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::Slasher)?;
+    if blocks == 0 {
+        return Err(ContractError::InvalidInput("blocks must be positive".to_string()));
+    }
+    let node_str = deps.api.addr_validate(&node_address)?.to_string();
+    let mut node = WHITELISTED_NODES
+        .may_load(deps.storage, node_str.clone())?
+        .ok_or(ContractError::NodeNotRegistered { address: node_str.clone() })?;
+
+    let event = jail(&mut node, env.block.height + blocks, "admin");
+    node.last_updated = env.block.time;
+    WHITELISTED_NODES.save(deps.storage, node_str.clone(), &node)?;
+
     Ok(Response::new()
+        .add_attribute("action", "jail_node")
+        .add_attribute("node_address", node_str)
         .add_event(event))
 }
 
+/// Releases the calling node from jail once its term has ended.
+///
+/// Errors:
+/// - `NodeNotRegistered` if the sender is not a registered node
+/// - `NodeNotJailed` if the node is not jailed
+/// - `NodeJailed` if the jail term has not ended yet
+pub fn unjail(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let sender_str = info.sender.to_string();
+    let mut node = WHITELISTED_NODES.load(deps.storage, sender_str.clone())
+        .map_err(|_| ContractError::NodeNotRegistered { address: sender_str.clone() })?;
+    match node.jailed_until {
+        None => return Err(ContractError::NodeNotJailed { address: sender_str }),
+        Some(until_block) if env.block.height < until_block => {
+            return Err(ContractError::NodeJailed { address: sender_str, until_block });
+        }
+        Some(_) => {}
+    }
+
+    node.jailed_until = None;
+    node.last_updated = env.block.time;
+    WHITELISTED_NODES.save(deps.storage, sender_str.clone(), &node)?;
 
-/// Verifies a proof's existence by its data hash.
-/// 
-pub fn verify_proof(
+    Ok(Response::new()
+        .add_attribute("action", "unjail")
+        .add_attribute("node_address", sender_str))
+}
+
+/// Sets the number of upheld disputes from which a node is jailed, and for how long, or with
+/// `None` stops jailing automatically. Jailed nodes keep their terms.
+pub fn configure_auto_jail(
     deps: DepsMut,
-    _env: Env,
     info: MessageInfo,
-    data_hash: String,
+    policy: Option<AutoJailPolicy>,
 ) -> Result<Response, ContractError> {
-    // Check that sender is a whitelisted node
-    validate_node(&deps, &info)?;
-    
-    // Check if proof exists
-    if !PROOF_BY_HASH.has(deps.storage, &data_hash) {
-        return Err(ContractError::ProofNotFound(data_hash));
+    validate_role(&deps, &info, Role::ConfigManager)?;
+    if policy.as_ref().is_some_and(|p| p.disputed_proofs == 0 || p.jail_blocks == 0) {
+        return Err(ContractError::InvalidInput("disputed_proofs and jail_blocks must be positive".to_string()));
     }
 
-    // Get proof ID
-    let proof_id = PROOF_BY_HASH.load(deps.storage, &data_hash)?;
-    
+    let mut config = CONFIG.load(deps.storage)?;
+    config.auto_jail = policy.clone();
+    CONFIG.save(deps.storage, &config)?;
+
     Ok(Response::new()
-        .add_attribute("action", "verify_proof")
-        .add_attribute("verified", "true")
-        .add_attribute("data_hash", data_hash)
-        .add_attribute("proof_id", proof_id.to_string()))
+        .add_attribute("action", "configure_auto_jail")
+        .add_attribute("disputed_proofs", policy.as_ref().map_or("none".to_string(), |p| p.disputed_proofs.to_string()))
+        .add_attribute("jail_blocks", policy.as_ref().map_or("none".to_string(), |p| p.jail_blocks.to_string())))
 }
 
-/// Registers a new node, verifies native stake, and locks their deposit.
-/// This function allows any address to attempt to register as a node, provided they meet
-/// the native staking requirements for a tier and send the correct corresponding deposit.
-/// Logic:
-/// 1. Checks if the node is already registered.
-/// 2. Fetches the node\'s native staked amount using `get_native_staked_amount`.
-/// 3. Determines the node\'s tier based on their native stake against configured thresholds.
-/// 4. Verifies that the `info.funds` (deposit sent with the registration message) matches
-///    the required deposit for the determined tier.
-/// 5. If all checks pass, a new `Node` entry is created and saved in `WHITELISTED_NODES`.
-///    The `WHITELISTED_NODES` map now serves as the central registry for all active nodes,
-///    regardless of the `use_whitelist` flag in `Config`.
-/// Events: Emits attributes for "register_node", "node_address", "native_stake_verified",
-///         "tier_assigned", "deposit_locked".
+/// Penalizes a node outside of a dispute by slashing `bps` basis points of its deposit and of
+/// any deposit it is unlocking, so misbehaving nodes cannot escape by unlocking first. Slashed
+/// coins go to the treasury (or stay in the contract without one); a resulting deposit
+/// shortfall starts the node's top-up deadline.
+///
 /// Errors:
-/// - `CustomError("Node already registered")` if the node is already in `WHITELISTED_NODES`.
-/// - `InsufficientStake` if native stake is below the minimum for Tier 1.
-/// - `DepositDoesNotMatchTierRequirement` if the sent deposit doesn\'t match the tier\'s requirement.
-pub fn register_node(
+/// - `InvalidInput` for `bps` outside 1-10000 or a reason outside 1-256 characters
+/// - `NodeNotRegistered` if the address has neither a registration nor an unlocking deposit
+pub fn slash_node(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    node_address: String,
+    bps: u16,
+    reason: String,
 ) -> Result<Response, ContractError> {
-    let sender_addr = info.sender.clone();
-    let sender_str = sender_addr.to_string();
+    validate_role(&deps, &info, Role::Slasher)?;
+    if bps == 0 || bps > 10_000 {
+        return Err(ContractError::InvalidInput("bps must be between 1 and 10000".to_string()));
+    }
+    if reason.trim().is_empty() || reason.len() > MAX_SLASH_REASON_LENGTH {
+        return Err(ContractError::InvalidInput(format!("Reason must be 1-{} characters", MAX_SLASH_REASON_LENGTH)));
+    }
+    validate_text("reason", &reason, MAX_SLASH_REASON_LENGTH, false)?;
+    let node_addr = deps.api.addr_validate(&node_address)?;
     let config = CONFIG.load(deps.storage)?;
 
-    // Check if node is already registered in WHITELISTED_NODES
-    let existing_node = WHITELISTED_NODES.may_load(deps.storage, sender_str.clone())?;
-    
-    // If node exists and is already operational (tier > 0), prevent re-registration
-    if let Some(existing) = &existing_node {
-        if existing.tier > 0 {
-            return Err(ContractError::CustomError("Node already registered".to_string()));
+    let mut response = Response::new();
+    let mut slashed = vec![];
+    let mut slashed_value = Uint128::zero();
+    let node = WHITELISTED_NODES.may_load(deps.storage, node_addr.to_string())?;
+    let registered = node.is_some();
+    if let Some(mut node) = node {
+        let deposit_before = node.deposit;
+        slashed = slash_deposit(&mut node, bps);
+        slashed_value = deposit_before - node.deposit;
+        if let Some(event) = flag_deposit_shortfall(&config, &env, &mut node) {
+            response = response.add_event(event);
         }
-        // If tier is 0, this is a whitelisted node that needs to upgrade - continue with registration
+        node.last_updated = env.block.time;
+        WHITELISTED_NODES.save(deps.storage, node_addr.to_string(), &node)?;
+    }
+    match slash_unlocking_deposit(deps.storage, &node_addr, bps)? {
+        Some((value, coins)) => {
+            slashed_value += value;
+            add_coins(&mut slashed, coins);
+        }
+        None if !registered => return Err(ContractError::NodeNotRegistered { address: node_addr.to_string() }),
+        None => {}
+    }
+    record_slash(deps.storage, &env, slashed_value)?;
+    settle(deps.storage, &slashed)?;
+    if !slashed.is_empty() {
+        response = response.add_submessages(hook_messages(deps.storage, DetrackHookMsg::NodeSlashed {
+            node: node_addr.to_string(),
+            slashed: slashed.clone(),
+            dispute_id: None,
+        })?);
     }
 
-    // 1. Verify Native Stake and Determine Tier
-    // This step queries the chain\'s staking module to get the total amount
-    // the sender has staked in the native C4E token.
-    let native_staked_amount = get_native_staked_amount(&deps.querier, &sender_addr)?;
+    response = response
+        .add_attribute("action", "slash_node")
+        .add_attribute("node_address", node_addr.to_string())
+        .add_attribute("bps", bps.to_string())
+        .add_attribute("reason", escape_attribute_value(&reason))
+        .add_attribute(
+            "slashed",
+            if slashed.is_empty() {
+                "none".to_string()
+            } else {
+                slashed.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(",")
+            },
+        );
+    if let Some(treasury) = &config.treasury {
+        response = response.add_messages(checked_payouts(deps.as_ref(), &env, vec![(treasury.clone(), slashed)])?);
+    }
+    Ok(response)
+}
 
-    // Determine the tier based on the native staked amount.
-    // Tiers provide different levels of service or trust within the DeTrack network.
-    let tier = if native_staked_amount >= config.min_stake_tier3 {
-        3u8
-    } else if native_staked_amount >= config.min_stake_tier2 {
-        2u8
-    } else if native_staked_amount >= config.min_stake_tier1 {
-        1u8
-    } else {
-        return Err(ContractError::InsufficientStake {
-            required: config.min_stake_tier1, // Minimum requirement is Tier 1 stake
-            provided: native_staked_amount,
-        });
-    };
+/// Removes `bps` basis points from each coin, dropping emptied coins and returning the removed ones.
+fn slash_coins(coins: &mut Vec<Coin>, bps: u16) -> Vec<Coin> {
+    let mut slashed = vec![];
+    for coin in coins.iter_mut() {
+        let amount = coin.amount.multiply_ratio(u128::from(bps), 10_000u128);
+        if !amount.is_zero() {
+            coin.amount -= amount;
+            slashed.push(Coin { denom: coin.denom.clone(), amount });
+        }
+    }
+    coins.retain(|c| !c.amount.is_zero());
+    slashed
+}
 
-    // 2. Verify Deposit Sent with this Message matches the requirement for the stake-determined Tier
-    // The node must send a specific amount of `uc4e` (the deposit token) with this registration
-    // message. The required amount depends on the tier they qualified for based on their native stake.
-    let required_deposit_for_tier = match tier {
-        3 => config.deposit_tier3,
-        2 => config.deposit_tier2,
-        _ => config.deposit_tier1, // Default to Tier 1 deposit requirement
-    };
+/// Sets the uc4e bond escrowed by each dispute. Open disputes keep the bond they were raised with.
+pub fn configure_dispute_bond(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.dispute_bond = amount;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_dispute_bond")
+        .add_attribute("amount", amount.to_string()))
+}
+
+/// Sets the share of a removed node's deposit held per open dispute against its proofs. `None`
+/// holds the whole deposit. Deposits already held keep their amount.
+pub fn configure_dispute_hold(
+    deps: DepsMut,
+    info: MessageInfo,
+    bps: Option<u16>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+    if bps.is_some_and(|bps| bps == 0 || bps > 10_000) {
+        return Err(ContractError::InvalidInput("bps must be between 1 and 10000".to_string()));
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.dispute_hold_bps = bps;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_dispute_hold")
+        .add_attribute("bps", bps.map_or("none".to_string(), |bps| bps.to_string())))
+}
+
+/// Sets the shares of deposit slashed by upheld disputes paid to the dispute's raiser and burned;
+/// the rest goes to the treasury. Applies to disputes resolved from now on.
+pub fn configure_slash_distribution(
+    deps: DepsMut,
+    info: MessageInfo,
+    distribution: SlashDistribution,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+    if u32::from(distribution.challenger_bps) + u32::from(distribution.burn_bps) > 10_000 {
+        return Err(ContractError::InvalidInput("challenger_bps and burn_bps must not exceed 10000 together".to_string()));
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.slash_distribution = distribution.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_slash_distribution")
+        .add_attribute("challenger_bps", distribution.challenger_bps.to_string())
+        .add_attribute("burn_bps", distribution.burn_bps.to_string()))
+}
+
+/// Splits slashed funds into the challenger's reward, the burned share and the remainder, in
+/// that order. Shares round down, leaving the dust in the remainder.
+fn split_slashed(slashed: &[Coin], distribution: &SlashDistribution) -> (Vec<Coin>, Vec<Coin>, Vec<Coin>) {
+    let mut reward = vec![];
+    let mut burned = vec![];
+    let mut remainder = vec![];
+    for coin in slashed {
+        let reward_amount = coin.amount.multiply_ratio(u128::from(distribution.challenger_bps), 10_000u128);
+        let burn_amount = coin.amount.multiply_ratio(u128::from(distribution.burn_bps), 10_000u128);
+        for (coins, amount) in [
+            (&mut reward, reward_amount),
+            (&mut burned, burn_amount),
+            (&mut remainder, coin.amount - reward_amount - burn_amount),
+        ] {
+            if !amount.is_zero() {
+                coins.push(Coin { denom: coin.denom.clone(), amount });
+            }
+        }
+    }
+    (reward, burned, remainder)
+}
 
-    let sent_deposit_amount = info
+/// Checks that exactly `required` uc4e (and no other denomination) was sent, returning the amount.
+fn ensure_exact_payment(info: &MessageInfo, required: Uint128) -> Result<Uint128, ContractError> {
+    let paid = info
         .funds
         .iter()
-        .find(|c| c.denom == "uc4e") // Assuming "uc4e" is the deposit/staking denom
+        .find(|c| c.denom == "uc4e")
         .map_or(Uint128::zero(), |c| c.amount);
-    
-    // Check if the sent deposit matches the required deposit for the determined tier
-    if sent_deposit_amount < required_deposit_for_tier {
-        return Err(ContractError::DepositDoesNotMatchTierRequirement {
-            required_deposit: required_deposit_for_tier,
-            provided_deposit: sent_deposit_amount,
-            tier,
+    if paid != required || info.funds.iter().any(|c| c.denom != "uc4e") {
+        return Err(ContractError::InvalidPayment { required, provided: paid });
+    }
+    Ok(paid)
+}
+
+/// Checks a data owner's plan and counts one proof against the current quota period,
+/// returning the plan.
+fn consume_owner_quota(
+    storage: &mut dyn Storage,
+    env: &Env,
+    data_owner: &cosmwasm_std::Addr,
+    metadata_bytes: usize,
+) -> Result<Plan, ContractError> {
+    let no_plan = || ContractError::NoActivePlan { data_owner: data_owner.to_string() };
+
+    let owner_plan = OWNER_PLANS.may_load(storage, data_owner)?.ok_or_else(no_plan)?;
+    if owner_plan.expires_at.is_some_and(|expiry| expiry <= env.block.time) {
+        return Err(no_plan());
+    }
+    let plan = PLANS.may_load(storage, &owner_plan.plan_id)?.ok_or_else(no_plan)?;
+
+    if metadata_bytes > plan.max_metadata_bytes as usize {
+        return Err(ContractError::MetadataAllowanceExceeded { size: metadata_bytes, limit: plan.max_metadata_bytes });
+    }
+
+    let period = env.block.time.seconds() / QUOTA_PERIOD_SECONDS;
+    let mut usage = match QUOTA_USAGE.may_load(storage, data_owner)? {
+        Some(usage) if usage.period == period => usage,
+        previous => {
+            let max_carry_over = CONFIG.load(storage)?.carry_over.max_quota_carry_over;
+            QuotaUsage::start(period, previous.as_ref(), plan.proofs_per_period, max_carry_over)
+        }
+    };
+
+    let limit = plan.proofs_per_period + usage.carried_over;
+    if usage.proofs_used >= limit {
+        return Err(ContractError::ProofQuotaExceeded {
+            data_owner: data_owner.to_string(),
+            limit,
         });
     }
 
-    let node = Node {
-        address: sender_addr,
-        reputation: 0, // Reset reputation for new registration
-        added_at: existing_node.as_ref().map_or(env.block.time, |n| n.added_at), // Preserve original timestamp for whitelisted nodes
-        deposit: sent_deposit_amount, // Store the locked deposit amount from this transaction
-        tier, // Tier determined by native stake
-        proof_count: 0, // Reset proof count for new registration
-        disputed_proofs: 0, // Reset disputed proofs for new registration
-        last_updated: env.block.time,
-    };
+    usage.proofs_used += 1;
+    QUOTA_USAGE.save(storage, data_owner, &usage)?;
+
+    Ok(plan)
+}
+
+/// Counts one proof against a node's per-period cap (its tier's, or the probation cap when
+/// lower), returning `limit_reached` once the cap is used up.
+fn consume_node_quota(
+    storage: &mut dyn Storage,
+    env: &Env,
+    node: &Node,
+    limit: u64,
+    limit_reached: ContractError,
+) -> Result<(), ContractError> {
+    let period = env.block.time.seconds() / QUOTA_PERIOD_SECONDS;
+    let mut usage = NODE_QUOTA_USAGE
+        .may_load(storage, &node.address)?
+        .filter(|u| u.period == period)
+        .unwrap_or(QuotaUsage { period, proofs_used: 0, carried_over: 0 });
+
+    if usage.proofs_used >= limit {
+        return Err(limit_reached);
+    }
+
+    usage.proofs_used += 1;
+    NODE_QUOTA_USAGE.save(storage, &node.address, &usage)?;
+
+    Ok(())
+}
+
+// ============================================================================
+// PROOF BONDS
+// ============================================================================
 
-    WHITELISTED_NODES.save(deps.storage, sender_str.clone(), &node)?;
+/// Maximum number of proof bonds claimable in one transaction
+const MAX_BOND_CLAIMS: usize = 30;
 
-    // TODO: Consider adding a mechanism for nodes to upgrade/downgrade tiers if their native stake changes.
-    // TODO: Implement slashing conditions related to node registration or behavior post-registration.
+/// Configures the per-proof bond. A zero `amount` disables the bond for new proofs;
+/// bonds already escrowed keep their amount and release height.
+pub fn configure_proof_bond(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Uint128,
+    finalization_period_blocks: u64,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.proof_bond = amount;
+    config.proof_finalization_period_blocks = finalization_period_blocks;
+    CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new()
-        .add_attribute("action", "register_node")
-        .add_attribute("node_address", sender_str)
-        .add_attribute("native_stake_verified", native_staked_amount.to_string())
-        .add_attribute("tier_assigned", tier.to_string())
-        .add_attribute("deposit_locked", sent_deposit_amount.to_string()))
+        .add_attribute("action", "configure_proof_bond")
+        .add_attribute("proof_bond", amount.to_string())
+        .add_attribute("finalization_period_blocks", finalization_period_blocks.to_string()))
 }
 
-/// Initiates the unlocking period for a node\'s deposit.
-/// Access Control: Only the registered node can initiate unlocking for their own deposit.
-/// Logic:
-/// 1. Validates that the sender is a registered node.
-/// 2. Checks if the deposit isn\'t already in the process of unlocking.
-/// 3. Checks if the node has a non-zero deposit to unlock.
-/// 4. Moves the node\'s active deposit amount to a new `UnlockingDeposit` entry.
-///    The node\'s `deposit` field is set to zero, effectively making their current deposit inactive.
-/// 5. Calculates `release_at_block` based on the current block height and `deposit_unlock_period_blocks` from config.
-/// 6. Saves the `UnlockingDeposit` entry, keyed by the node\'s address.
-/// State Transition:
-/// - Node\'s `deposit` in `WHITELISTED_NODES` is set to 0.
-/// - A new entry is created in `UNLOCKING_DEPOSITS` for the node, with the amount and release block.
-/// Events: Emits "unlock_deposit", "node_address", "unlocking_amount", "release_at_block".
+/// Sets the protocol fee nodes pay with every `StoreProof`, or removes it with `None`.
+/// Fees already collected stay until swept.
+pub fn configure_proof_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    fee: Option<ProofFee>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.proof_fee = fee.clone();
+    CONFIG.save(deps.storage, &config)?;
+
+    let (kind, amount) = match &fee {
+        Some(ProofFee::Flat { amount }) => ("flat", amount.to_string()),
+        Some(ProofFee::PerBatch { amount }) => ("per_batch", amount.to_string()),
+        None => ("none", "0".to_string()),
+    };
+    Ok(Response::new()
+        .add_attribute("action", "configure_proof_fee")
+        .add_attribute("fee_kind", kind)
+        .add_attribute("amount", amount))
+}
+
+/// Sends the collected protocol fees to the treasury.
+///
 /// Errors:
-/// - `NodeNotRegistered` if the sender is not a registered node.
-/// - `DepositAlreadyUnlocking` if an unlocking process is already active for the node.
-/// - `NoDepositToUnlock` if the node\'s current active deposit is zero.
-pub fn unlock_deposit(
+/// - `TreasuryNotConfigured` if no treasury is set
+/// - `NoFeesToSweep` if no fees were collected since the last sweep
+pub fn sweep_fees(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
-    let sender_addr = info.sender.clone();
-    let sender_str = sender_addr.to_string();
-    let config = CONFIG.load(deps.storage)?;
-
-    // Check if node is registered
-    let mut node = WHITELISTED_NODES.load(deps.storage, sender_str.clone())
-        .map_err(|_| ContractError::NodeNotRegistered { address: sender_str.clone() })?;
+    validate_admin(&deps, &info)?;
 
-    // Check if deposit is already unlocking
-    if UNLOCKING_DEPOSITS.has(deps.storage, sender_addr.to_string()) {
-        return Err(ContractError::DepositAlreadyUnlocking {});
+    let mut config = CONFIG.load(deps.storage)?;
+    let treasury = config.treasury.clone().ok_or(ContractError::TreasuryNotConfigured {})?;
+    if config.collected_fees.is_zero() {
+        return Err(ContractError::NoFeesToSweep {});
     }
+    let amount = config.collected_fees;
+    config.collected_fees = Uint128::zero();
+    CONFIG.save(deps.storage, &config)?;
+    let fees = coins(amount.u128(), "uc4e");
+    settle(deps.storage, &fees)?;
 
-    // Check if there's a deposit to unlock
-    if node.deposit.is_zero() {
-        return Err(ContractError::NoDepositToUnlock {});
-    }
+    Ok(Response::new()
+        .add_messages(checked_payouts(deps.as_ref(), &env, vec![(treasury.clone(), fees)])?)
+        .add_attribute("action", "sweep_fees")
+        .add_attribute("treasury", treasury.to_string())
+        .add_attribute("amount", amount.to_string()))
+}
 
-    // State Change: Node\'s active deposit is moved to an unlocking state.
-    // The node.deposit field is zeroed out, and an UnlockingDeposit entry is created.
-    let unlocking_amount = node.deposit;
-    node.deposit = Uint128::zero(); // Remove active deposit from node
-    WHITELISTED_NODES.save(deps.storage, sender_str.clone(), &node)?;
+/// Sets the thresholds evaluated by `check_alerts`. Replaces the previous thresholds entirely.
+pub fn configure_alert_thresholds(
+    deps: DepsMut,
+    info: MessageInfo,
+    thresholds: AlertThresholds,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
 
-    let release_at_block = env.block.height + config.deposit_unlock_period_blocks;
+    let mut config = CONFIG.load(deps.storage)?;
+    config.alert_thresholds = thresholds.clone();
+    CONFIG.save(deps.storage, &config)?;
 
-    let unlocking_deposit = UnlockingDeposit {
-        owner: sender_addr.clone(),
-        amount: unlocking_amount,
-        release_at_block,
-    };
+    Ok(Response::new()
+        .add_attribute("action", "configure_alert_thresholds")
+        .add_attribute("max_open_disputes", thresholds.max_open_disputes.map_or_else(|| "none".to_string(), |v| v.to_string()))
+        .add_attribute("min_reward_pool", thresholds.min_reward_pool.map_or_else(|| "none".to_string(), |v| v.to_string()))
+        .add_attribute("max_unlocking_volume", thresholds.max_unlocking_volume.map_or_else(|| "none".to_string(), |v| v.to_string())))
+}
 
-    UNLOCKING_DEPOSITS.save(deps.storage, sender_addr.to_string(), &unlocking_deposit)?;
+/// Emits a `detrack_alert` event for every configured threshold the current state breaches.
+/// Callable by anyone so monitoring can subscribe to the events without evaluating state itself;
+/// `close_epoch` evaluates the thresholds as well.
+pub fn check_alerts(deps: DepsMut) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let alerts = alert_events(deps.storage, &config)?;
 
-    let mut response = Response::default();
+    Ok(Response::new()
+        .add_attribute("action", "check_alerts")
+        .add_attribute("alerts", alerts.len().to_string())
+        .add_events(alerts))
+}
 
-    let event = Event::new("detrack_unlock_deposit")
-        .add_attribute("node_address", sender_str)
-        .add_attribute("unlocking_amount", unlocking_amount.to_string())
-        .add_attribute("release_at_block", release_at_block.to_string());
+/// Returns a `detrack_alert` event for every threshold of `config.alert_thresholds` the
+/// current state breaches.
+fn alert_events(storage: &dyn Storage, config: &Config) -> StdResult<Vec<Event>> {
+    let thresholds = &config.alert_thresholds;
+    let mut alerts = Vec::new();
 
-    response = response.add_event(event);
+    if let Some(max) = thresholds.max_open_disputes {
+        let open = OPEN_DISPUTE_COUNT.may_load(storage)?.unwrap_or_default();
+        if open > max {
+            alerts.push(alert_event("open_disputes", open.to_string(), max.to_string()));
+        }
+    }
+    if let Some(min) = thresholds.min_reward_pool {
+        if config.reward_pool < min {
+            alerts.push(alert_event("reward_pool", config.reward_pool.to_string(), min.to_string()));
+        }
+    }
+    if let Some(max) = thresholds.max_unlocking_volume {
+        let unlocking = UNLOCKING_TOTAL.may_load(storage)?.unwrap_or_default();
+        if unlocking > max {
+            alerts.push(alert_event("unlocking_volume", unlocking.to_string(), max.to_string()));
+        }
+    }
 
-    Ok(response)
+    Ok(alerts)
+}
 
-//     Ok(Response::new()
-//         .add_event(Event::UnlockDeposit {
-//             node_address: sender_str,
-//             unlocking_amount,
-//             release_at_block,
-//         })
-//         .add_attribute("action", "unlock_deposit")
-//         .add_attribute("node_address", sender_str)
-//         .add_attribute("unlocking_amount", unlocking_amount.to_string())
-//         .add_attribute("release_at_block", release_at_block.to_string()))
+fn alert_event(alert: &str, value: String, threshold: String) -> Event {
+    Event::new("detrack_alert")
+        .add_attribute("alert", alert)
+        .add_attribute("value", value)
+        .add_attribute("threshold", threshold)
 }
 
-/// Allows a node to claim their deposit after the unlocking period has passed.
-/// Access Control: Only the node who initiated the unlock can claim their deposit.
-/// Logic:
-/// 1. Loads the `UnlockingDeposit` entry for the sender.
-/// 2. Verifies that the current block height is greater than or equal to `release_at_block`.
-/// 3. Removes the `UnlockingDeposit` entry from storage.
-/// 4. Creates a `BankMsg::Send` to transfer the unlocked amount back to the node.
-/// State Transition:
-/// - The `UnlockingDeposit` entry for the node is removed from `UNLOCKING_DEPOSITS`.
-/// - Funds are transferred from the contract to the node.
-/// Events: Emits "claim_unlocked_deposit", "node_address", "claimed_amount".
+/// Maximum length of a proof revocation reason
+const MAX_REVOCATION_REASON_LENGTH: usize = 256;
+
+/// Revokes a proof. The record stays in `proofs()` as a tombstone with `ProofStatus::Revoked`,
+/// and its hash moves from `PROOF_BY_HASH` to `REVOKED_PROOF_HASHES`: the proof no longer
+/// verifies and the hash cannot be stored again.
+///
 /// Errors:
-/// - `NoUnlockedDepositToClaim` if no unlocking deposit entry exists for the sender.
-/// - `DepositNotYetUnlocked` if the current block height is less than `release_at_block`.
-/// TODO: Consider if any slashing conditions should prevent claiming (e.g., if node was slashed during unlock period).
-///       Currently, slashing is not implemented, but this would be a point of integration.
-pub fn claim_unlocked_deposit(
+/// - `ProofNotFound` if no proof has the ID
+/// - `ProofRevoked` if the proof is already revoked
+/// - `InvalidInput` if the reason is empty or longer than 256 characters
+pub fn revoke_proof(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    proof_id: u64,
+    reason: String,
 ) -> Result<Response, ContractError> {
-    let sender_addr = info.sender.clone();
-
-    // Check if there's an unlocking deposit entry for the sender
-    let unlocking_deposit = UNLOCKING_DEPOSITS.load(deps.storage, sender_addr.to_string())
-        .map_err(|_| ContractError::NoUnlockedDepositToClaim {})?;
+    validate_role(&deps, &info, Role::Slasher)?;
+    if reason.trim().is_empty() || reason.len() > MAX_REVOCATION_REASON_LENGTH {
+        return Err(ContractError::InvalidInput(format!("Revocation reason must be 1-{} characters", MAX_REVOCATION_REASON_LENGTH)));
+    }
+    validate_text("reason", &reason, MAX_REVOCATION_REASON_LENGTH, false)?;
 
-    // Check if the unlocking period has passed
-    if env.block.height < unlocking_deposit.release_at_block {
-        return Err(ContractError::DepositNotYetUnlocked {
-            release_at_block: unlocking_deposit.release_at_block,
-        });
+    let mut proof = proofs().may_load(deps.storage, proof_id)?
+        .ok_or(ContractError::ProofNotFound(proof_id.to_string()))?;
+    if proof.status != ProofStatus::Active {
+        return Err(ContractError::ProofRevoked { proof_id });
     }
+    if !proof.rejected {
+        record_worker_energy(deps.storage, &proof, true)?;
+    }
+    proof.status = ProofStatus::Revoked { reason: reason.clone(), revoked_at: env.block.time };
+    proofs().save(deps.storage, proof_id, &proof)?;
+    PROOF_BY_HASH.remove(deps.storage, &proof.data_hash);
+    REVOKED_PROOF_HASHES.save(deps.storage, &proof.data_hash, &proof_id)?;
 
-    // State Change: Unlocking deposit entry is removed, and funds are sent to the node.
-    // Remove the unlocking deposit entry
-    UNLOCKING_DEPOSITS.remove(deps.storage, sender_addr.to_string());
-
-    // Send the funds back to the user
-    let bank_msg = BankMsg::Send {
-        to_address: sender_addr.to_string(),
-        amount: vec![Coin {
-            denom: "uc4e".to_string(), // Ensure this is your chain's native token denom
-            amount: unlocking_deposit.amount,
-        }],
-    };
+    Ok(Response::new()
+        .add_attribute("action", "revoke_proof")
+        .add_event(
+            Event::new("detrack_proof_revoked")
+                .add_attribute("proof_id", proof_id.to_string())
+                .add_attribute("data_hash", proof.data_hash)
+                .add_attribute("stored_by", proof.stored_by.to_string())
+                .add_attribute("reason", escape_attribute_value(&reason)),
+        ))
+}
 
-    let mut response = Response::default();
+/// Forfeits the bond of a proof that has not reached finality, or of a rejected proof at any time
+/// (its bond can no longer be claimed).
+/// The bond is sent to the treasury when one is configured, otherwise it stays in the contract.
+///
+/// Errors:
+/// - `ProofBondNotFound` if no bond is held for the proof
+/// - `CustomError` if the proof is already final and not rejected
+pub fn forfeit_proof_bond(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proof_id: u64,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::Slasher)?;
 
-    let event = Event::new("detrack_claim_unlocked_deposit")
-        .add_attribute("node_address", sender_addr.to_string())
-        .add_attribute("claimed_amount", unlocking_deposit.amount.to_string());
+    let bond = PROOF_BONDS.may_load(deps.storage, proof_id)?
+        .ok_or(ContractError::ProofBondNotFound { proof_id })?;
+    if env.block.height >= bond.release_at_block && !proofs().load(deps.storage, proof_id)?.rejected {
+        return Err(ContractError::CustomError(format!("Proof {} is already final", proof_id)));
+    }
+    PROOF_BONDS.remove(deps.storage, proof_id);
+    let forfeited = vec![Coin { denom: "uc4e".to_string(), amount: bond.amount }];
+    settle(deps.storage, &forfeited)?;
 
-    response = response
-        .add_message(bank_msg)
-        .add_event(event);
+    let mut response = Response::new().add_event(
+        Event::new("detrack_proof_bond_forfeited")
+            .add_attribute("proof_id", proof_id.to_string())
+            .add_attribute("node_address", bond.node.to_string())
+            .add_attribute("amount", bond.amount.to_string()),
+    );
 
-    Ok(response)
+    if let Some(treasury) = CONFIG.load(deps.storage)?.treasury {
+        response = response.add_messages(checked_payouts(deps.as_ref(), &env, vec![(treasury, forfeited)])?);
+    }
 
-    // Ok(Response::new()
-    //     .add_message(bank_msg)
-    //     .add_attribute("action", "claim_unlocked_deposit")
-    //     .add_attribute("node_address", sender_addr.to_string())
-    //     .add_attribute("claimed_amount", unlocking_deposit.amount.to_string()))
+    Ok(response)
 }
 
-/// Allows a registered node to add more funds to their existing deposit.
-/// Access Control: Only a registered node can add to their own deposit.
-/// Logic:
-/// 1. Validates that the sender is a registered node.
-/// 2. Checks that the node\'s deposit is not currently in an unlocking period.
-/// 3. Verifies that funds of the correct denomination ("uc4e") were sent with the message.
-/// 4. Adds the sent amount to the node\'s current deposit.
-/// 5. Updates the node\'s `last_updated` timestamp.
-/// State Transition:
-/// - Node\'s `deposit` in `WHITELISTED_NODES` is increased.
-/// - Node\'s `last_updated` in `WHITELISTED_NODES` is updated.
-/// Events: Emits "add_deposit", "node_address", "added_amount", "new_total_deposit".
+/// Returns the bonds of the caller's proofs that have reached finality.
+/// Access Control: Only the node that escrowed a bond can claim it.
+///
 /// Errors:
-/// - `NodeNotRegistered` if the sender is not a registered node.
-/// - `DepositAlreadyUnlocking` if the node\'s deposit is currently being unlocked.
-/// - `CustomError("No deposit amount provided or amount is zero")` if no "uc4e" funds are sent.
-/// - `CustomError("Invalid deposit denomination")` if funds other than "uc4e" are sent.
-pub fn add_deposit(
+/// - `InvalidInput` if no IDs or more than `MAX_BOND_CLAIMS` are given
+/// - `ProofBondNotFound` if a proof has no bond held
+/// - `Unauthorized` if a bond was escrowed by another node
+/// - `ProofBondLocked` if a proof is not yet final
+/// - `ProofRejected` if a proof was rejected
+/// - `ProofBondContested` if a proof is under review or has an open dispute
+pub fn claim_proof_bonds(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    proof_ids: Vec<u64>,
 ) -> Result<Response, ContractError> {
-    let sender_addr = info.sender.clone();
-    let sender_str = sender_addr.to_string();
-
-    // 1. Validate that the sender is a registered node
-    let mut node = WHITELISTED_NODES.load(deps.storage, sender_str.clone())
-        .map_err(|_| ContractError::NodeNotRegistered { address: sender_str.clone() })?;
+    if proof_ids.is_empty() || proof_ids.len() > MAX_BOND_CLAIMS {
+        return Err(ContractError::InvalidInput(format!(
+            "Between 1 and {} proof IDs must be given",
+            MAX_BOND_CLAIMS
+        )));
+    }
 
-    // 2. Check that the node\'s deposit is not currently in an unlocking period
-    if UNLOCKING_DEPOSITS.has(deps.storage, sender_addr.to_string()) {
-        return Err(ContractError::DepositAlreadyUnlocking {});
+    let mut total = Uint128::zero();
+    for proof_id in &proof_ids {
+        let bond = PROOF_BONDS.may_load(deps.storage, *proof_id)?
+            .ok_or(ContractError::ProofBondNotFound { proof_id: *proof_id })?;
+        if bond.node != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+        if env.block.height < bond.release_at_block {
+            return Err(ContractError::ProofBondLocked {
+                proof_id: *proof_id,
+                release_at_block: bond.release_at_block,
+            });
+        }
+        let proof = proofs().load(deps.storage, *proof_id)?;
+        if proof.rejected {
+            return Err(ContractError::ProofRejected { proof_id: *proof_id });
+        }
+        let disputed = OPEN_DISPUTES
+            .prefix(*proof_id)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_some();
+        if proof.under_review || disputed {
+            return Err(ContractError::ProofBondContested { proof_id: *proof_id });
+        }
+        PROOF_BONDS.remove(deps.storage, *proof_id);
+        total += bond.amount;
     }
 
-    // 3. Verify that funds of the correct denomination ("uc4e") were sent
-    let sent_deposit_amount = info
-        .funds
-        .iter()
-        .find(|c| c.denom == "uc4e") // Assuming "uc4e" is the deposit denom
-        .map_or(Uint128::zero(), |c| c.amount);
+    let claimed = vec![Coin { denom: "uc4e".to_string(), amount: total }];
+    settle(deps.storage, &claimed)?;
 
-    if sent_deposit_amount.is_zero() {
-        return Err(ContractError::CustomError("No deposit amount provided or amount is zero".to_string()));
-    }
+    let proof_ids_attr: Vec<String> = proof_ids.iter().map(|id| id.to_string()).collect();
+    Ok(Response::new()
+        .add_messages(checked_payouts(deps.as_ref(), &env, vec![(info.sender.clone(), claimed)])?)
+        .add_event(
+            Event::new("detrack_claim_proof_bonds")
+                .add_attribute("node_address", info.sender.to_string())
+                .add_attribute("proof_ids", proof_ids_attr.join(","))
+                .add_attribute("claimed_amount", total.to_string()),
+        ))
+}
+
+// ============================================================================
+// LEGACY STOREPROOF COMPATIBILITY
+// ============================================================================
+
+/// Enables or disables `StoreProofLegacy` submissions.
+pub fn configure_legacy_store_proof(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::ConfigManager)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.legacy_store_proof_enabled = enabled;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "configure_legacy_store_proof")
+        .add_attribute("enabled", enabled.to_string()))
+}
 
-    // Optional: Check if other denominations were sent and reject if so, or ignore.
-    // For simplicity, we only care about "uc4e". If other denoms are sent, they are ignored by the sum above.
-    // If strictness is required:
-    if info.funds.len() > 1 && info.funds.iter().any(|c| c.denom != "uc4e") {
-         // Or if only one coin is sent but it's not uc4e
-         if info.funds.len() == 1 && info.funds[0].denom != "uc4e" {
-            return Err(ContractError::CustomError("Invalid deposit denomination. Only uc4e is accepted.".to_string()));
-         }
+/// Sets (or clears) the DIDs a node's legacy proofs are recorded with.
+/// The DIDs must have the regular worker/gateway format; their registration is checked
+/// against the DID contract on every legacy submission, like any other proof.
+pub fn set_legacy_mapping(
+    deps: DepsMut,
+    info: MessageInfo,
+    node_address: String,
+    mapping: Option<LegacyMapping>,
+) -> Result<Response, ContractError> {
+    validate_role(&deps, &info, Role::Operator)?;
+
+    let node_addr = deps.api.addr_validate(&node_address)?;
+    let mut response = Response::new()
+        .add_attribute("action", "set_legacy_mapping")
+        .add_attribute("node_address", node_addr.to_string());
+
+    match mapping {
+        Some(mapping) => {
+            let config = CONFIG.load(deps.storage)?;
+            validate_did_format(&config, &mapping.worker_did, "worker")?;
+            validate_did_format(&config, &mapping.gateway_did, "gateway")?;
+            response = response
+                .add_attribute("worker_did", mapping.worker_did.clone())
+                .add_attribute("gateway_did", mapping.gateway_did.clone());
+            LEGACY_MAPPINGS.save(deps.storage, &node_addr, &mapping)?;
+        }
+        None => {
+            LEGACY_MAPPINGS.remove(deps.storage, &node_addr);
+            response = response.add_attribute("worker_did", "none");
+        }
     }
 
+    Ok(response)
+}
 
-    // 4. Add the sent amount to the node\'s current deposit
-    node.deposit += sent_deposit_amount;
+/// Stores a proof submitted in the pre-DID message shape.
+/// Access Control: Same as `store_proof`; the node must also have a legacy mapping.
+/// Logic:
+/// 1. Legacy submissions must be enabled in config.
+/// 2. The sender's `LEGACY_MAPPINGS` entry provides the worker DID and the gateway DID.
+/// 3. A single batch is synthesized from the gateway DID, with `data_hash` as its Merkle root.
+/// 4. `unit` is kept only when a value is present, then the proof goes through `store_proof`.
+///
+/// Errors:
+/// - `LegacyStoreProofDisabled` if legacy submissions are disabled
+/// - `LegacyMappingNotFound` if the sender has no mapping
+/// - Any `store_proof` error
+#[allow(clippy::too_many_arguments)]
+pub fn store_proof_legacy(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    data_hash: String,
+    original_data_reference: Option<String>,
+    data_owner: Option<String>,
+    metadata_json: Option<String>,
+    tw_start: Timestamp,
+    tw_end: Timestamp,
+    value_in: Option<Uint128>,
+    value_out: Option<Uint128>,
+    unit: String,
+) -> Result<Response, ContractError> {
+    if !CONFIG.load(deps.storage)?.legacy_store_proof_enabled {
+        return Err(ContractError::LegacyStoreProofDisabled {});
+    }
 
-    // 5. Update the node\'s `last_updated` timestamp
-    node.last_updated = env.block.time;
+    let mapping = LEGACY_MAPPINGS.may_load(deps.storage, &info.sender)?
+        .ok_or_else(|| ContractError::LegacyMappingNotFound { node_address: info.sender.to_string() })?;
 
-    // Save the updated node data
-    WHITELISTED_NODES.save(deps.storage, sender_str.clone(), &node)?;
+    let batch = BatchInfo {
+        batch_id: "legacy".to_string(),
+        gateway_did: mapping.gateway_did,
+        snapshot_count: 1,
+        batch_merkle_root: data_hash.clone(),
+        original_data_reference: None,
+        metadata_json: None,
+        sequence: None,
+    };
+    let unit = (value_in.is_some() || value_out.is_some()).then_some(unit);
 
-    Ok(Response::new()
-        .add_attribute("action", "add_deposit")
-        .add_attribute("node_address", sender_str)
-        .add_attribute("added_amount", sent_deposit_amount.to_string())
-        .add_attribute("new_total_deposit", node.deposit.to_string()))
-}
\ No newline at end of file
+    let response = store_proof(
+        deps,
+        env,
+        info,
+        mapping.worker_did,
+        data_hash,
+        tw_start,
+        tw_end,
+        vec![batch],
+        original_data_reference,
+        metadata_json,
+        None,
+        None,
+        data_owner,
+        vec![],
+        value_in,
+        value_out,
+        unit,
+    )?;
+
+    Ok(response.add_attribute("action", "store_proof_legacy"))
+}
@@ -1,6 +1,9 @@
 use crate::error::ContractError;
-use cosmwasm_std::{Addr, AllDelegationsResponse, BondedDenomResponse, QuerierWrapper, QueryRequest, StakingQuery, Uint128};
+use crate::msg::{Cw20BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, QueryMsg, VerifyHashResponse};
+use crate::state::{Node, Proof, AUDIT_COMMITTEE_SIZE, WHITELISTED_NODES};
+use cosmwasm_std::{to_json_binary, Addr, AllDelegationsResponse, BankMsg, BondedDenomResponse, Coin, CosmosMsg, Order, QuerierWrapper, QueryRequest, Record, StakingQuery, StdResult, Storage, Uint128, WasmMsg};
 use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
 /// Deserialize a string to a number
@@ -25,24 +28,13 @@ where
 
 /// Queries the native staking module to get the total staked amount for a given address.
 /// This function is crucial for determining a node's tier during registration.
-/// 
-/// In test environments where the staking module is not available, this function
-/// returns a default stake amount sufficient for tier 1 registration.
+///
+/// Any failure of the staking queries is returned as `StakingQueryError`; there is no
+/// fallback stake. Tests provide a staking module through cw-multi-test instead.
 pub fn get_native_staked_amount(querier: &QuerierWrapper, address: &Addr) -> Result<Uint128, ContractError> {
-    // Try to query the bonded denom. If it fails (e.g., in test environment), return default stake.
-    let bonded_denom_response: BondedDenomResponse =
-        match querier.query(&QueryRequest::Staking(StakingQuery::BondedDenom {})) {
-            Ok(response) => response,
-            Err(e) => {
-                // In test environments, staking module might not be available
-                if e.to_string().contains("Unexpected custom query") || 
-                   e.to_string().contains("BondedDenom") {
-                    // Return a default stake amount that qualifies for tier 1 (1000 in most test configs)
-                    return Ok(Uint128::new(1000));
-                }
-                return Err(ContractError::StakingQueryError { error: e.to_string() });
-            }
-        };
+    let bonded_denom_response: BondedDenomResponse = querier
+        .query(&QueryRequest::Staking(StakingQuery::BondedDenom {}))
+        .map_err(|e| ContractError::StakingQueryError { error: e.to_string() })?;
     let bonded_denom = bonded_denom_response.denom;
 
     // Then, get all delegations for the address.
@@ -63,3 +55,189 @@ pub fn get_native_staked_amount(querier: &QuerierWrapper, address: &Addr) -> Res
     }
     Ok(total_staked)
 }
+
+/// Prefix of the deposit denoms under which CW20 tokens are tracked, as `cw20:<token address>`.
+pub const CW20_DENOM_PREFIX: &str = "cw20:";
+
+/// Returns the token contract address of a `cw20:` deposit denom.
+pub fn cw20_token(denom: &str) -> Option<&str> {
+    denom.strip_prefix(CW20_DENOM_PREFIX)
+}
+
+/// Builds the messages paying `coins` to `recipient`: a bank send for the native coins and a
+/// CW20 transfer for each `cw20:` denom. Zero amounts are skipped.
+pub fn send_coins(recipient: &Addr, coins: Vec<Coin>) -> StdResult<Vec<CosmosMsg>> {
+    let mut messages = vec![];
+    let mut native = vec![];
+    for coin in coins.into_iter().filter(|c| !c.amount.is_zero()) {
+        match cw20_token(&coin.denom) {
+            Some(token) => messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer { recipient: recipient.to_string(), amount: coin.amount })?,
+                funds: vec![],
+            })),
+            None => native.push(coin),
+        }
+    }
+    if !native.is_empty() {
+        messages.insert(0, BankMsg::Send { to_address: recipient.to_string(), amount: native }.into());
+    }
+    Ok(messages)
+}
+
+/// Builds the messages burning `coins`: a bank burn for the native coins and a CW20 burn for each
+/// `cw20:` denom. Zero amounts are skipped.
+pub fn burn_coins(coins: Vec<Coin>) -> StdResult<Vec<CosmosMsg>> {
+    let mut messages = vec![];
+    let mut native = vec![];
+    for coin in coins.into_iter().filter(|c| !c.amount.is_zero()) {
+        match cw20_token(&coin.denom) {
+            Some(token) => messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Burn { amount: coin.amount })?,
+                funds: vec![],
+            })),
+            None => native.push(coin),
+        }
+    }
+    if !native.is_empty() {
+        messages.insert(0, BankMsg::Burn { amount: native }.into());
+    }
+    Ok(messages)
+}
+
+/// Returns the balance `account` holds of `denom`, querying the token contract for `cw20:` denoms.
+pub fn query_denom_balance(querier: &QuerierWrapper, account: &Addr, denom: &str) -> StdResult<Uint128> {
+    match cw20_token(denom) {
+        Some(token) => {
+            let query = Cw20QueryMsg::Balance { address: account.to_string() };
+            Ok(querier.query_wasm_smart::<Cw20BalanceResponse>(token, &query)?.balance)
+        }
+        None => Ok(querier.query_balance(account, denom)?.amount),
+    }
+}
+
+/// Unicode bidirectional marks and embedding, override and isolate characters
+pub(crate) fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Escapes control and bidirectional formatting characters in an event attribute value as
+/// `\u{...}`, so free text recorded before input validation cannot break indexers.
+pub fn escape_attribute_value(value: &str) -> String {
+    let unsafe_char = |c: char| c.is_control() || is_bidi_control(c);
+    if !value.chars().any(unsafe_char) {
+        return value.to_string();
+    }
+    value
+        .chars()
+        .map(|c| if unsafe_char(c) { c.escape_unicode().to_string() } else { c.to_string() })
+        .collect()
+}
+
+/// Returns the lowercase hex SHA-256 digest of `data`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Returns the seed a proof's audit committee is ranked by: the hex SHA-256 of its data hash,
+/// ID and storage height. Block hashes are not available to contracts.
+pub fn audit_committee_seed(proof: &Proof) -> String {
+    sha256_hex(format!("{}:{}:{}", proof.data_hash, proof.id, proof.stored_height).as_bytes())
+}
+
+/// Selects the audit committee of a proof from the node registry. Candidates are the registered
+/// nodes added no later than the proof was stored, other than its storer. Each is ranked by the
+/// SHA-256 of the seed and its address, and only the best-ranked node of each operator DID is
+/// kept, so one operator cannot fill the committee.
+///
+/// Reads every registered node; `store_proof` runs it once and saves the result in
+/// `AUDIT_COMMITTEES`.
+pub(crate) fn select_audit_committee(storage: &dyn Storage, proof: &Proof) -> StdResult<Vec<Addr>> {
+    let seed = audit_committee_seed(proof);
+    let mut ranked = WHITELISTED_NODES
+        .range(storage, None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((_, node)) if node.added_at <= proof.stored_at && node.address != proof.stored_by => {
+                let rank = sha256_hex(format!("{}:{}", seed, node.address).as_bytes());
+                Some(Ok((rank, node)))
+            }
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .collect::<StdResult<Vec<(String, Node)>>>()?;
+    ranked.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut operators = std::collections::BTreeSet::new();
+    Ok(ranked
+        .into_iter()
+        .filter(|(_, node)| node.operator_did.as_ref().is_none_or(|did| operators.insert(did.clone())))
+        .take(AUDIT_COMMITTEE_SIZE)
+        .map(|(_, node)| node.address)
+        .collect())
+}
+
+/// Asks a DeTrack contract whether `data_hash` is stored and final.
+///
+/// Meant for contracts that depend on this crate with the `library` feature (certificate
+/// issuers, marketplaces) instead of hand-writing the `VerifyHash` query.
+pub fn query_verify_hash(
+    querier: &QuerierWrapper,
+    detrack_contract: impl Into<String>,
+    data_hash: impl Into<String>,
+) -> StdResult<VerifyHashResponse> {
+    querier.query_wasm_smart(detrack_contract, &QueryMsg::VerifyHash { data_hash: data_hash.into() })
+}
+
+/// Storage layered over another storage that keeps every write in memory, so a handler can run
+/// without persisting anything (see `preview` on admin configuration messages), or persist its
+/// writes only once it succeeded (see `into_writes`).
+pub(crate) struct PreviewStorage<'a> {
+    base: &'a dyn Storage,
+    /// Written values, or `None` for removed keys
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<'a> PreviewStorage<'a> {
+    pub(crate) fn new(base: &'a dyn Storage) -> Self {
+        PreviewStorage { base, writes: BTreeMap::new() }
+    }
+
+    /// The kept writes, as values or `None` for removed keys, for applying to the base storage
+    pub(crate) fn into_writes(self) -> BTreeMap<Vec<u8>, Option<Vec<u8>>> {
+        self.writes
+    }
+}
+
+impl Storage for PreviewStorage<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(value) => value.clone(),
+            None => self.base.get(key),
+        }
+    }
+
+    fn range<'b>(&'b self, start: Option<&[u8]>, end: Option<&[u8]>, order: Order) -> Box<dyn Iterator<Item = Record> + 'b> {
+        let mut merged: BTreeMap<Vec<u8>, Vec<u8>> = self.base.range(start, end, Order::Ascending).collect();
+        let in_range = |key: &[u8]| start.is_none_or(|s| key >= s) && end.is_none_or(|e| key < e);
+        for (key, value) in self.writes.iter().filter(|(key, _)| in_range(key)) {
+            match value {
+                Some(value) => merged.insert(key.clone(), value.clone()),
+                None => merged.remove(key),
+            };
+        }
+        match order {
+            Order::Ascending => Box::new(merged.into_iter()),
+            Order::Descending => Box::new(merged.into_iter().rev()),
+        }
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes.insert(key.to_vec(), None);
+    }
+}
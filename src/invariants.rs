@@ -0,0 +1,375 @@
+//! State invariant checks, compiled only with the `debug-invariants` feature.
+//!
+//! `contract::execute` runs [`check`] after every successful handler and aborts the
+//! transaction on violation. The checks scan whole maps, so the feature is meant for
+//! testnets and tests, never for production builds.
+
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Deps, Env, Order, StdResult, Uint128};
+
+use crate::error::ContractError;
+use crate::execute::tracked_liabilities;
+use crate::helpers::query_denom_balance;
+use crate::msg::{DisputeStatus, ProofStatus};
+use crate::state::{
+    proofs, CONFIG, DISPUTES, LIABILITIES, OPEN_DISPUTES, NODE_OPEN_DISPUTES, GatewayProofEntry, GATEWAY_PROOFS, PENDING_ACTIONS, GATEWAY_TIME_PROOFS, GRID_POINT_PROOFS, HEIGHT_PROOFS, OWNER_PROOFS, PROOF_BONDS,
+    PROBATION_PROOFS, PROOF_APPROVALS, PROOF_BY_HASH, REVOKED_PROOF_HASHES, PROOF_CONFLICTS, PROOF_FLAGS, OPERATOR_NODES, REGION_NODES, REGION_PROOFS, WHITELISTED_NODES,
+    WORKER_WINDOWS, TOMBSTONED_NODES, WORKER_ENERGY, ENERGY_BUCKET_SECONDS, EnergyBucket, OPEN_DISPUTE_COUNT, WORKER_PROOF_COUNTS,
+    UNLOCKING_DEPOSITS, UNLOCKING_TOTAL,
+};
+
+fn violation(invariant: impl Into<String>) -> ContractError {
+    ContractError::InvariantViolation { invariant: invariant.into() }
+}
+
+/// Runs all invariant checks against the current state.
+pub fn check(deps: Deps, env: &Env) -> Result<(), ContractError> {
+    check_deposit_accounting(deps, env)?;
+    check_counters(deps)?;
+    check_indexes(deps, env)?;
+    check_energy_totals(deps)?;
+    Ok(())
+}
+
+/// The `LIABILITIES` ledger must equal the liabilities recomputed from the records holding
+/// funds (deposits, escrows, bonds, pools, accrued rewards and collected fees), and the
+/// contract must hold, per denom (or CW20 token), at least that much.
+/// Outgoing transfers of the current transaction are still in the balance at this point,
+/// which is fine because their ledger entries are already removed.
+fn check_deposit_accounting(deps: Deps, env: &Env) -> Result<(), ContractError> {
+    let liabilities = tracked_liabilities(deps.storage)?;
+    let ledger: BTreeMap<String, Uint128> = LIABILITIES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    if ledger != liabilities {
+        return Err(violation(format!("liability ledger {:?} differs from tracked liabilities {:?}", ledger, liabilities)));
+    }
+
+    for (denom, owed) in liabilities {
+        let balance = query_denom_balance(&deps.querier, &env.contract.address, &denom)?;
+        if balance < owed {
+            return Err(violation(format!(
+                "contract balance {}{} below tracked liabilities {}{}",
+                balance, denom, owed, denom
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// `WORKER_ENERGY` must equal the energy values of the active, non-rejected proofs summed per
+/// worker, day and unit.
+fn check_energy_totals(deps: Deps) -> Result<(), ContractError> {
+    let mut expected: BTreeMap<(String, u64, String), EnergyBucket> = BTreeMap::new();
+    for item in proofs().range(deps.storage, None, None, Order::Ascending) {
+        let (_, proof) = item?;
+        let Some(unit) = proof.unit.filter(|_| proof.status == ProofStatus::Active && !proof.rejected) else {
+            continue;
+        };
+        let bucket = expected
+            .entry((proof.worker_did, proof.tw_start.seconds() / ENERGY_BUCKET_SECONDS, unit))
+            .or_default();
+        bucket.value_in += proof.value_in.unwrap_or_default();
+        bucket.value_out += proof.value_out.unwrap_or_default();
+        bucket.proofs += 1;
+    }
+    let stored: BTreeMap<(String, u64, String), EnergyBucket> = WORKER_ENERGY
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    if stored != expected {
+        return Err(violation(format!("energy totals {:?} differ from the proofs' {:?}", stored, expected)));
+    }
+    Ok(())
+}
+
+/// `Config.proof_count` must match the stored proofs, which use IDs `0..proof_count`, and
+/// pending actions and disputes must use IDs below `Config.pending_action_count` and
+/// `Config.dispute_count`. `OPEN_DISPUTE_COUNT` and `UNLOCKING_TOTAL` must match their maps.
+fn check_counters(deps: Deps) -> Result<(), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let mut stored = 0u64;
+    for id in proofs().keys(deps.storage, None, None, Order::Ascending) {
+        let id = id?;
+        if id >= config.proof_count {
+            return Err(violation(format!("proof {} at or above proof_count {}", id, config.proof_count)));
+        }
+        stored += 1;
+    }
+    if stored != config.proof_count {
+        return Err(violation(format!("proof_count {} but {} proofs stored", config.proof_count, stored)));
+    }
+
+    if let Some(last) = PENDING_ACTIONS.keys(deps.storage, None, None, Order::Descending).next().transpose()? {
+        if last >= config.pending_action_count {
+            return Err(violation(format!("pending action {} at or above pending_action_count {}", last, config.pending_action_count)));
+        }
+    }
+
+    if let Some(last) = DISPUTES.keys(deps.storage, None, None, Order::Descending).next().transpose()? {
+        if last >= config.dispute_count {
+            return Err(violation(format!("dispute {} at or above dispute_count {}", last, config.dispute_count)));
+        }
+    }
+
+    let node_proofs = WHITELISTED_NODES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, node)| node.proof_count))
+        .sum::<StdResult<u64>>()?;
+    if node_proofs > config.proof_count {
+        return Err(violation(format!("nodes account for {} proofs, more than proof_count {}", node_proofs, config.proof_count)));
+    }
+
+    let open_disputes = OPEN_DISPUTES.keys(deps.storage, None, None, Order::Ascending).count() as u64;
+    let counted = OPEN_DISPUTE_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    if counted != open_disputes {
+        return Err(violation(format!("open dispute count {} but {} disputes open", counted, open_disputes)));
+    }
+    let unlocking = UNLOCKING_DEPOSITS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, entry)| entry.amount))
+        .sum::<StdResult<Uint128>>()?;
+    let counted = UNLOCKING_TOTAL.may_load(deps.storage)?.unwrap_or_default();
+    if counted != unlocking {
+        return Err(violation(format!("unlocking total {} but {} unlocking", counted, unlocking)));
+    }
+    Ok(())
+}
+
+/// Every secondary index entry must point at a record with the indexed value, and every
+/// proof must be reachable through its hash index (the revoked one for revoked proofs). Tags are not checked against `REGIONS`
+/// because removing a region keeps the existing tags. Proof flags come from the proof's owners,
+/// or from the contract itself when it tombstoned the submitter, and tombstoned nodes stay
+/// out of the registry.
+fn check_indexes(deps: Deps, env: &Env) -> Result<(), ContractError> {
+    for item in proofs().range(deps.storage, None, None, Order::Ascending) {
+        let (id, proof) = item?;
+        let hash_index = match proof.status {
+            ProofStatus::Active => PROOF_BY_HASH,
+            ProofStatus::Revoked { .. } => REVOKED_PROOF_HASHES,
+        };
+        if hash_index.may_load(deps.storage, &proof.data_hash)? != Some(id) {
+            return Err(violation(format!("proof {} missing from hash index", id)));
+        }
+        if proof.under_review && !PROOF_FLAGS.has(deps.storage, id) {
+            return Err(violation(format!("proof {} under review without flags", id)));
+        }
+        for (owner, share) in proof.ownership() {
+            if OWNER_PROOFS.may_load(deps.storage, (&owner, id))? != Some(share) {
+                return Err(violation(format!("proof {} missing from owner index {}", id, owner)));
+            }
+        }
+    }
+
+    for item in PROOF_BY_HASH.range(deps.storage, None, None, Order::Ascending) {
+        let (hash, id) = item?;
+        match proofs().may_load(deps.storage, id)? {
+            Some(proof) if proof.data_hash == hash => {}
+            _ => return Err(violation(format!("hash index entry {} points at proof {} without that hash", hash, id))),
+        }
+    }
+    for item in PROBATION_PROOFS.range(deps.storage, None, None, Order::Ascending) {
+        let (id, stored_by) = item?;
+        if proofs().may_load(deps.storage, id)?.map(|proof| proof.stored_by) != Some(stored_by) {
+            return Err(violation(format!("probation proof entry {} points at a proof of another node", id)));
+        }
+    }
+    for item in REVOKED_PROOF_HASHES.range(deps.storage, None, None, Order::Ascending) {
+        let (hash, id) = item?;
+        match proofs().may_load(deps.storage, id)? {
+            Some(proof) if proof.data_hash == hash && proof.status != ProofStatus::Active => {}
+            _ => return Err(violation(format!("revoked hash entry {} points at proof {} that is not a revoked proof with that hash", hash, id))),
+        }
+    }
+
+    for item in GATEWAY_PROOFS.range(deps.storage, None, None, Order::Ascending) {
+        let ((gateway_did, id), entry) = item?;
+        let indexed = proofs().may_load(deps.storage, id)?.is_some_and(|p| {
+            p.batch_metadata.iter().any(|b| b.gateway_did == gateway_did)
+                && GatewayProofEntry::from_proof(&p) == entry
+        });
+        if !indexed {
+            return Err(violation(format!("gateway index entry ({}, {}) is stale", gateway_did, id)));
+        }
+    }
+
+    for item in GATEWAY_TIME_PROOFS.keys(deps.storage, None, None, Order::Ascending) {
+        let (gateway_did, tw_start, id) = item?;
+        if !GATEWAY_PROOFS.has(deps.storage, (&gateway_did, id))
+            || proofs().may_load(deps.storage, id)?.map(|p| p.tw_start.nanos()) != Some(tw_start)
+        {
+            return Err(violation(format!("gateway time index entry ({}, {}, {}) is stale", gateway_did, tw_start, id)));
+        }
+    }
+
+    for item in REGION_PROOFS.keys(deps.storage, None, None, Order::Ascending) {
+        let (region, id) = item?;
+        let indexed = proofs()
+            .may_load(deps.storage, id)?
+            .is_some_and(|p| p.region.as_deref() == Some(region.as_str()));
+        if !indexed {
+            return Err(violation(format!("region index entry ({}, {}) is stale", region, id)));
+        }
+    }
+
+    for item in HEIGHT_PROOFS.keys(deps.storage, None, None, Order::Ascending) {
+        let (height, id) = item?;
+        if proofs().may_load(deps.storage, id)?.map(|p| p.stored_height) != Some(height) {
+            return Err(violation(format!("height index entry ({}, {}) is stale", height, id)));
+        }
+    }
+
+    for item in OWNER_PROOFS.range(deps.storage, None, None, Order::Ascending) {
+        let ((owner, id), share) = item?;
+        let indexed = proofs().may_load(deps.storage, id)?.is_some_and(|p| p.ownership().contains(&(owner.clone(), share)));
+        if !indexed {
+            return Err(violation(format!("owner index entry ({}, {}) is stale", owner, id)));
+        }
+    }
+    for item in PROOF_APPROVALS.keys(deps.storage, None, None, Order::Ascending) {
+        let (owner, id) = item?;
+        if !OWNER_PROOFS.has(deps.storage, (&owner, id)) {
+            return Err(violation(format!("approval ({}, {}) is for a proof not attributed to the owner", owner, id)));
+        }
+    }
+
+    for item in GRID_POINT_PROOFS.keys(deps.storage, None, None, Order::Ascending) {
+        let (point, id) = item?;
+        let indexed = proofs().may_load(deps.storage, id)?.is_some_and(|p| {
+            p.site.and_then(|s| s.grid_connection_point).as_deref() == Some(point.as_str())
+        });
+        if !indexed {
+            return Err(violation(format!("grid connection point index entry ({}, {}) is stale", point, id)));
+        }
+    }
+
+    for item in REGION_NODES.keys(deps.storage, None, None, Order::Ascending) {
+        let (region, address) = item?;
+        let indexed = WHITELISTED_NODES
+            .may_load(deps.storage, address.to_string())?
+            .is_some_and(|n| n.region.as_deref() == Some(region.as_str()));
+        if !indexed {
+            return Err(violation(format!("region node index entry ({}, {}) is stale", region, address)));
+        }
+    }
+
+    for item in OPERATOR_NODES.keys(deps.storage, None, None, Order::Ascending) {
+        let (operator_did, address) = item?;
+        let indexed = WHITELISTED_NODES
+            .may_load(deps.storage, address.to_string())?
+            .is_some_and(|n| n.operator_did.as_deref() == Some(operator_did.as_str()));
+        if !indexed {
+            return Err(violation(format!("operator node index entry ({}, {}) is stale", operator_did, address)));
+        }
+    }
+
+    for item in WHITELISTED_NODES.range(deps.storage, None, None, Order::Ascending) {
+        let (address, node) = item?;
+        if let Some(region) = &node.region {
+            if !REGION_NODES.has(deps.storage, (region, &node.address)) {
+                return Err(violation(format!("node {} missing from region index {}", address, region)));
+            }
+        }
+        if let Some(operator_did) = &node.operator_did {
+            if !OPERATOR_NODES.has(deps.storage, (operator_did, &node.address)) {
+                return Err(violation(format!("node {} missing from operator index {}", address, operator_did)));
+            }
+        }
+    }
+
+    let mut worker_proofs: BTreeMap<String, u64> = BTreeMap::new();
+    for item in proofs().range(deps.storage, None, None, Order::Ascending) {
+        *worker_proofs.entry(item?.1.worker_did).or_default() += 1;
+    }
+    let counted: BTreeMap<String, u64> = WORKER_PROOF_COUNTS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    if counted != worker_proofs {
+        return Err(violation("per-worker proof counts differ from the proofs stored".to_string()));
+    }
+
+    for item in WORKER_WINDOWS.range(deps.storage, None, None, Order::Ascending) {
+        let ((worker_did, tw_start, tw_end), id) = item?;
+        let indexed = proofs().may_load(deps.storage, id)?.is_some_and(|p| {
+            p.worker_did == worker_did && p.tw_start.nanos() == tw_start && p.tw_end.nanos() == tw_end
+        });
+        if !indexed {
+            return Err(violation(format!("worker window entry ({}, {}, {}) points at proof {} outside it", worker_did, tw_start, tw_end, id)));
+        }
+    }
+
+    for item in PROOF_CONFLICTS.range(deps.storage, None, None, Order::Ascending) {
+        let (id, conflict) = item?;
+        if conflict.original_proof_id >= id || !proofs().has(deps.storage, id) || !proofs().has(deps.storage, conflict.original_proof_id) {
+            return Err(violation(format!("conflict for proof {} references unknown or later proof {}", id, conflict.original_proof_id)));
+        }
+        if let Some(resolution) = &conflict.resolution {
+            let rejected_id = if resolution.canonical_proof_id == id { conflict.original_proof_id } else { id };
+            if (resolution.canonical_proof_id != id && resolution.canonical_proof_id != conflict.original_proof_id)
+                || !proofs().load(deps.storage, rejected_id)?.rejected
+            {
+                return Err(violation(format!("resolution of conflict for proof {} is inconsistent", id)));
+            }
+        }
+    }
+
+    for item in PROOF_FLAGS.range(deps.storage, None, None, Order::Ascending) {
+        let (id, flags) = item?;
+        let consistent = proofs().may_load(deps.storage, id)?.is_some_and(|p| {
+            p.under_review
+                && !flags.is_empty()
+                && flags.iter().all(|f| f.flagged_by == env.contract.address || p.ownership().iter().any(|(owner, _)| *owner == f.flagged_by))
+        });
+        if !consistent {
+            return Err(violation(format!("flags of proof {} do not match an owner-flagged proof under review", id)));
+        }
+    }
+
+    for item in TOMBSTONED_NODES.keys(deps.storage, None, None, Order::Ascending) {
+        let node = item?;
+        if WHITELISTED_NODES.has(deps.storage, node.to_string()) {
+            return Err(violation(format!("tombstoned node {} is still registered", node)));
+        }
+    }
+
+    for item in DISPUTES.range(deps.storage, None, None, Order::Ascending) {
+        let (id, dispute) = item?;
+        let indexed = OPEN_DISPUTES.may_load(deps.storage, (dispute.proof_id, &dispute.raised_by))? == Some(id);
+        if !proofs().has(deps.storage, dispute.proof_id) || indexed != (dispute.status == DisputeStatus::Open) {
+            return Err(violation(format!("dispute {} is inconsistent with its proof or the open dispute index", id)));
+        }
+    }
+
+    for item in OPEN_DISPUTES.range(deps.storage, None, None, Order::Ascending) {
+        let ((proof_id, raised_by), id) = item?;
+        let open = DISPUTES.may_load(deps.storage, id)?.is_some_and(|d| {
+            d.status == DisputeStatus::Open && d.proof_id == proof_id && d.raised_by == raised_by
+        });
+        if !open {
+            return Err(violation(format!("open dispute index entry ({}, {}) is stale", proof_id, raised_by)));
+        }
+    }
+
+    for item in NODE_OPEN_DISPUTES.keys(deps.storage, None, None, Order::Ascending) {
+        let (node, id) = item?;
+        let open = match DISPUTES.may_load(deps.storage, id)? {
+            Some(d) if d.status == DisputeStatus::Open => {
+                proofs().may_load(deps.storage, d.proof_id)?.is_some_and(|p| p.stored_by == node)
+            }
+            _ => false,
+        };
+        if !open {
+            return Err(violation(format!("node open dispute index entry ({}, {}) is stale", node, id)));
+        }
+    }
+
+    for item in PROOF_BONDS.keys(deps.storage, None, None, Order::Ascending) {
+        let id = item?;
+        if !proofs().has(deps.storage, id) {
+            return Err(violation(format!("bond held for unknown proof {}", id)));
+        }
+    }
+
+    Ok(())
+}
@@ -5,6 +5,10 @@ pub mod msg;
 pub mod state;
 pub mod execute;
 pub mod query;
+#[cfg(feature = "debug-invariants")]
+pub mod invariants;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 #[cfg(test)]
 mod tests;
 
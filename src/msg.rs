@@ -1,5 +1,5 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Timestamp, Uint128};
+use cosmwasm_std::{to_json_binary, Binary, Coin, CosmosMsg, Decimal, StdResult, Timestamp, Uint128, WasmMsg};
 
 /// BatchInfo - Information about a single batch aggregated into a proof
 /// Phase 1b: Multi-batch aggregation support
@@ -17,6 +17,360 @@ pub struct BatchInfo {
     pub original_data_reference: Option<String>,
     /// Optional JSON string for additional, application-specific metadata related to the proof.
     pub metadata_json: Option<String>,
+    /// Optional per-gateway batch sequence number. Sequenced batches of a gateway must arrive
+    /// in increasing order; skipped numbers are recorded as gaps.
+    #[serde(default)]
+    pub sequence: Option<u64>,
+}
+
+/// SequenceGap - A run of sequence numbers skipped by a gateway.
+#[cw_serde]
+pub struct SequenceGap {
+    pub first_missing: u64,
+    pub last_missing: u64,
+    /// Proof whose batch jumped past the gap.
+    pub proof_id: u64,
+}
+
+/// Site - Structured location of the facility a proof's measurements come from
+#[cw_serde]
+pub struct Site {
+    /// Identifier of the facility (e.g., registry facility ID)
+    pub facility_id: String,
+    /// Optional grid connection point identifier (e.g., EAN/metering point code)
+    pub grid_connection_point: Option<String>,
+    /// Optional SHA-256 hash (64 hex characters) of the facility coordinates
+    pub coordinates_hash: Option<String>,
+}
+
+/// OwnerShare - One co-owner of a proof and their share of it
+#[cw_serde]
+pub struct OwnerShare {
+    /// Address of the co-owner
+    pub owner: String,
+    /// Fraction of the proof attributed to the owner; the shares of a proof sum to 1
+    pub share: Decimal,
+}
+
+/// DepositDenom - Additional denomination accepted for node deposits
+#[cw_serde]
+pub struct DepositDenom {
+    /// Bank denomination, e.g. an IBC denom of C4E
+    pub denom: String,
+    /// uc4e-equivalent value of one unit, used to count the deposit against tier requirements
+    pub weight: Decimal,
+}
+
+/// TierCapabilities - Per-tier limits consulted by `store_proof`
+#[cw_serde]
+pub struct TierCapabilities {
+    /// Node tier (1-3) the limits apply to
+    pub tier: u8,
+    /// Maximum number of batches in one proof, replacing `max_batch_size` for this tier
+    pub max_batch_size: u32,
+    /// Maximum proofs a node of this tier may store per 30-day period (`None` = unlimited)
+    pub max_proofs_per_period: Option<u64>,
+}
+
+/// ReputationBounds - Range every node reputation is kept within
+#[cw_serde]
+pub struct ReputationBounds {
+    /// Lowest reputation a node can reach (at most 0, the reputation of new nodes)
+    #[serde(deserialize_with = "crate::helpers::deserialize_int")]
+    pub min: i32,
+    /// Highest reputation a node can reach (at least 0)
+    #[serde(deserialize_with = "crate::helpers::deserialize_int")]
+    pub max: i32,
+}
+
+impl Default for ReputationBounds {
+    fn default() -> Self {
+        ReputationBounds { min: -10_000, max: 10_000 }
+    }
+}
+
+impl ReputationBounds {
+    /// Returns whether `reputation` lies within the bounds.
+    pub fn contains(&self, reputation: i32) -> bool {
+        (self.min..=self.max).contains(&reputation)
+    }
+
+    /// Applies a relative change to `reputation`, clamping the result to the bounds.
+    pub fn apply(&self, reputation: i32, delta: i64) -> i32 {
+        (i64::from(reputation) + delta).clamp(i64::from(self.min), i64::from(self.max)) as i32
+    }
+}
+
+/// CarryOverRules - What `CloseEpoch` carries into the next epoch. The default carries unclaimed
+/// rewards and open reviews and no unused quota.
+#[cw_serde]
+#[derive(Default)]
+pub struct CarryOverRules {
+    /// Return the unclaimed rewards of nodes that accrued none after the closed epoch to the
+    /// reward pool
+    pub expire_unclaimed_rewards: bool,
+    /// Most unused data-owner proofs carried from an epoch into the next one's quota (zero
+    /// carries none)
+    pub max_quota_carry_over: u64,
+    /// Close owner-flag reviews last flagged before the end of the closed epoch, unless a dispute
+    /// on the proof is open
+    pub expire_unfinished_reviews: bool,
+}
+
+/// AutoJailPolicy - Jails a node once upheld disputes against its proofs reach a threshold
+#[cw_serde]
+pub struct AutoJailPolicy {
+    /// `disputed_proofs` count from which each further upheld dispute jails the node
+    pub disputed_proofs: u64,
+    /// Blocks the node stays jailed
+    pub jail_blocks: u64,
+}
+
+/// ReputationScoring - Automatic reputation changes from proof activity, within `ReputationBounds`
+#[cw_serde]
+#[derive(Default)]
+pub struct ReputationScoring {
+    /// Reputation gained per stored proof
+    pub proof_reward: u32,
+    /// Highest reputation proof rewards can raise a node to (`None`: the bounds' maximum).
+    /// Nodes above it, e.g. through an admin override, keep their reputation.
+    pub proof_reward_cap: Option<i32>,
+    /// Reputation lost per upheld dispute, on top of the verdict's `reputation_penalty`
+    pub upheld_dispute_penalty: u32,
+}
+
+impl ReputationScoring {
+    /// Returns `reputation` after one stored proof, clamped to `bounds`.
+    pub fn after_proof(&self, bounds: &ReputationBounds, reputation: i32) -> i32 {
+        let cap = self.proof_reward_cap.unwrap_or(bounds.max);
+        if reputation >= cap {
+            return reputation;
+        }
+        bounds.apply(reputation, i64::from(self.proof_reward)).min(cap)
+    }
+}
+
+/// ProofRewards - uc4e accrued by nodes from the reward pool for each stored proof
+#[cw_serde]
+#[derive(Default)]
+pub struct ProofRewards {
+    /// uc4e accrued per stored proof before the tier multiplier (zero disables rewards)
+    pub reward_per_proof: Uint128,
+    /// Per-tier multipliers of `reward_per_proof`; tiers without an entry earn 1x
+    pub tier_multipliers: Vec<TierRewardMultiplier>,
+}
+
+/// SlashDistribution - Split of the deposit slashed by an upheld dispute, in basis points. The
+/// rest goes to the treasury.
+#[cw_serde]
+#[derive(Default)]
+pub struct SlashDistribution {
+    /// Share paid to the raiser of the dispute
+    pub challenger_bps: u16,
+    /// Share burned
+    pub burn_bps: u16,
+}
+
+/// TierRewardMultiplier - Factor applied to the proof reward of one node tier
+#[cw_serde]
+pub struct TierRewardMultiplier {
+    /// Node tier (1-3) the multiplier applies to
+    pub tier: u8,
+    pub multiplier: Decimal,
+}
+
+impl ProofRewards {
+    /// Returns the reward for one proof stored by a node of `tier`, before the pool limit.
+    pub fn for_tier(&self, tier: u8) -> Uint128 {
+        let multiplier = self
+            .tier_multipliers
+            .iter()
+            .find(|m| m.tier == tier)
+            .map_or(Decimal::one(), |m| m.multiplier);
+        self.reward_per_proof * multiplier
+    }
+}
+
+/// ProofFee - Protocol fee in uc4e attached to every `StoreProof`, on top of any proof bond
+#[cw_serde]
+pub enum ProofFee {
+    /// The same fee for every proof
+    Flat { amount: Uint128 },
+    /// A fee for each batch in the proof
+    PerBatch { amount: Uint128 },
+}
+
+impl ProofFee {
+    /// Returns the fee for a proof with `batch_count` batches.
+    pub fn for_batches(&self, batch_count: usize) -> Uint128 {
+        match self {
+            ProofFee::Flat { amount } => *amount,
+            ProofFee::PerBatch { amount } => amount.saturating_mul(Uint128::from(batch_count as u128)),
+        }
+    }
+}
+
+/// AlertThresholds - Limits `CheckAlerts` compares contract state against; `None` disables a check
+#[cw_serde]
+#[derive(Default)]
+pub struct AlertThresholds {
+    /// Alert when more disputes than this are open
+    pub max_open_disputes: Option<u64>,
+    /// Alert when the proof reward pool holds less uc4e than this
+    pub min_reward_pool: Option<Uint128>,
+    /// Alert when more uc4e-equivalent deposit than this is unlocking
+    pub max_unlocking_volume: Option<Uint128>,
+}
+
+/// Role - Delegated admin permission. The admin holds every role implicitly.
+#[cw_serde]
+#[derive(Copy, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// Day-to-day node management: whitelisting, removal, reputation, owner plans, flags
+    Operator,
+    /// Rulings against proofs and nodes: conflicts, disputes, bond forfeits, revocations
+    Slasher,
+    /// Protocol parameters: the `Configure*` messages, regions, tiers, plans
+    ConfigManager,
+}
+
+impl Role {
+    /// Every role, in key order
+    pub const ALL: [Role; 3] = [Role::Operator, Role::Slasher, Role::ConfigManager];
+
+    /// Storage key of the role in `ROLES`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Role::Operator => "operator",
+            Role::Slasher => "slasher",
+            Role::ConfigManager => "config_manager",
+        }
+    }
+}
+
+/// ProbationPolicy - Reduced capabilities of nodes that registered themselves recently
+#[cw_serde]
+#[derive(Default)]
+pub struct ProbationPolicy {
+    /// Blocks after registration a node stays on probation (zero disables probation)
+    pub period_blocks: u64,
+    /// Maximum proofs a node on probation may store per 30-day period, on top of its tier limit
+    pub max_proofs_per_period: Option<u64>,
+    /// Number of a new node's first proofs that another node must attest with `AttestProof`.
+    /// Probation also lasts until all of them are attested.
+    pub required_attestations: u32,
+}
+
+/// OnboardingStage - Step a node has reached on its way to storing proofs
+#[cw_serde]
+#[derive(Default, Eq, PartialOrd, Ord)]
+pub enum OnboardingStage {
+    /// Whitelisted by the admin, without an operational tier
+    #[default]
+    Whitelisted,
+    /// Holds the stake (or admin-assigned tier) for a tier, but not the tier's deposit
+    Staked,
+    /// Deposit covers the tier requirement; reputation is below the tier's minimum reputation
+    Deposited,
+    /// Meets every onboarding requirement. Later shortfalls are reported by their own errors.
+    Operational,
+}
+
+/// OnboardingStep - Requirement a node still has to meet to become operational
+#[cw_serde]
+pub enum OnboardingStep {
+    /// Stake at least `required` native tokens; `staked` is the current stake
+    Stake { required: Uint128, staked: Uint128 },
+    /// Lock `amount` more uc4e-equivalent deposit, with `RegisterNode` at tier 0 and `AddDeposit` after
+    Deposit { amount: Uint128 },
+    /// Reach reputation `required`; `reputation` is the current score
+    Reputation { required: i32, reputation: i32 },
+}
+
+/// TimelockedAction - Admin action that only takes effect through the timelock
+#[cw_serde]
+pub enum TimelockedAction {
+    /// Makes a node's unlocking deposit claimable immediately
+    EmergencyRelease { node_address: String },
+    /// Changes the number of blocks between scheduling and executing timelocked actions
+    SetAdminTimelock { blocks: u64 },
+    /// Sets a node's reputation, scheduled when the change exceeds `max_reputation_delta`
+    SetNodeReputation { node_address: String, reputation: i32 },
+    /// Changes the largest reputation change applied without the timelock
+    SetReputationDeltaLimit { max_delta: Option<u32> },
+}
+
+/// AuditEvent - Step of a timelocked action recorded in the admin audit log
+#[cw_serde]
+pub enum AuditEvent {
+    Scheduled { executable_at_block: u64 },
+    Executed,
+    Cancelled,
+}
+
+/// DisputeStatus - Stage of a dispute raised against a proof
+#[cw_serde]
+pub enum DisputeStatus {
+    Open,
+    /// The proof was ruled wrong
+    Upheld,
+    /// The dispute was ruled unfounded
+    Rejected,
+}
+
+/// DisputeVerdict - Admin ruling on an open dispute
+#[cw_serde]
+pub enum DisputeVerdict {
+    /// The proof is wrong: it is rejected, its submitter loses `reputation_penalty` (plus the
+    /// configured `upheld_dispute_penalty`) and, with `slash_bps`, that share (in basis points) of its deposit
+    Upheld { reputation_penalty: u32, slash_bps: Option<u16> },
+    /// The dispute is unfounded: its bond is forfeited
+    Rejected,
+    /// Severe fraud: upheld, and the submitter's whole deposit is slashed, the node is removed and
+    /// permanently barred from registering, and its proofs that are not yet final are flagged for review
+    TombstoneNode,
+}
+
+/// HookEvent - Kind of notification a hook subscriber receives
+#[cw_serde]
+#[derive(Copy, Eq, PartialOrd, Ord)]
+pub enum HookEvent {
+    ProofStored,
+    NodeSlashed,
+    DisputeOpened,
+    DisputeResolved,
+}
+
+impl HookEvent {
+    /// Name of the event in JSON and event attributes
+    pub fn key(&self) -> &'static str {
+        match self {
+            HookEvent::ProofStored => "proof_stored",
+            HookEvent::NodeSlashed => "node_slashed",
+            HookEvent::DisputeOpened => "dispute_opened",
+            HookEvent::DisputeResolved => "dispute_resolved",
+        }
+    }
+}
+
+/// ProofStatus - Lifecycle status of a stored proof
+#[cw_serde]
+#[derive(Default)]
+pub enum ProofStatus {
+    #[default]
+    Active,
+    /// Revoked by the admin. The record is kept as a tombstone; the proof no longer verifies
+    /// and its hash cannot be stored again.
+    Revoked { reason: String, revoked_at: Timestamp },
+}
+
+/// LegacyMapping - Admin-configured DIDs used for a node's `StoreProofLegacy` submissions
+#[cw_serde]
+pub struct LegacyMapping {
+    /// Worker DID recorded on the node's legacy proofs
+    pub worker_did: String,
+    /// Gateway DID of the single batch synthesized for each legacy proof
+    pub gateway_did: String,
 }
 
 /// Message type for `instantiate` entry_point
@@ -37,34 +391,283 @@ pub struct InstantiateMsg {
     pub deposit_unlock_period_blocks: u64,
     /// Maximum number of batches that can be aggregated in a single proof (default: 100)
     pub max_batch_size: u32,
+    /// Optional facility registry contract used to verify `site.facility_id` on proofs
+    pub facility_registry_address: Option<String>,
 }
 
 /// Message type for admin operations
+///
+/// With `preview: true`, the multi-field configuration messages only validate the change: nothing
+/// is saved and the resulting `ConfigResponse` is returned as `Response::data`.
 #[cw_serde]
 pub enum AdminExecuteMsg {
     /// Update the admin address
     UpdateAdmin { new_admin: String },
-    /// Whitelist a node address
-    WhitelistNode { node_address: String },
+    /// Grant a role to an address
+    GrantRole { role: Role, address: String },
+    /// Revoke a role from an address
+    RevokeRole { role: Role, address: String },
+    /// Pause the contract: proof submissions, registrations and deposit operations are rejected
+    Pause {},
+    /// Lift a contract pause
+    Unpause {},
+    /// Whitelist a node address, optionally at an operational tier (1-3) and starting reputation.
+    /// A node whitelisted at a tier must still top up its deposit with `AddDeposit`.
+    WhitelistNode {
+        node_address: String,
+        initial_tier: Option<u8>,
+        initial_reputation: Option<i32>,
+    },
     /// Remove a node from the whitelist
     RemoveNode { node_address: String },
-    /// Update node reputation
+    /// Whitelist several node addresses at tier 0, each independently of the others' outcome
+    WhitelistNodes { node_addresses: Vec<String> },
+    /// Remove several nodes from the whitelist, each independently of the others' outcome
+    RemoveNodes { node_addresses: Vec<String> },
+    /// Sets (or resets to the default of 50, with `None`) how many addresses `WhitelistNodes` and
+    /// `RemoveNodes` may list
+    ConfigureNodeBatchLimit { max_nodes: Option<u32> },
+    /// Update node reputation; changes larger than `max_reputation_delta` are scheduled through
+    /// the admin timelock instead
     UpdateNodeReputation { 
         node_address: String, 
         #[serde(deserialize_with = "crate::helpers::deserialize_int")]
         reputation: i32 
     },
-    /// Update the minimum reputation threshold
+    /// Update the minimum reputation of one tier (1-3), or of every tier when `tier` is omitted
     UpdateMinReputationThreshold { 
         #[serde(deserialize_with = "crate::helpers::deserialize_int")]
-        threshold: i32 
+        threshold: i32,
+        #[serde(default)]
+        tier: Option<u8>,
     },
     /// Configure the treasury address
     ConfigureTreasury { treasury_address: String },
+    /// Update instantiation parameters; fields left `None` keep their current value
+    UpdateConfig {
+        min_stake_tier1: Option<Uint128>,
+        min_stake_tier2: Option<Uint128>,
+        min_stake_tier3: Option<Uint128>,
+        deposit_tier1: Option<Uint128>,
+        deposit_tier2: Option<Uint128>,
+        deposit_tier3: Option<Uint128>,
+        deposit_unlock_period_blocks: Option<u64>,
+        max_batch_size: Option<u32>,
+        use_whitelist: Option<bool>,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Add a region/zone code to the list accepted on nodes and proofs
+    AddRegion { region: String },
+    /// Remove a region/zone code from the accepted list (existing records keep their region)
+    RemoveRegion { region: String },
+    /// Set or clear the facility registry contract used to verify proof facility IDs
+    ConfigureFacilityRegistry { registry_address: Option<String> },
+    /// Point the contract at a new DID contract, e.g. after the DID contract was redeployed
+    UpdateDidContract { address: String },
+    /// Accept DIDs starting with `prefix` (e.g. `did:web:`); `did:c4e:<type>:` prefixes only
+    /// match DIDs of that type, other methods match every DID type
+    AddDidPrefix { prefix: String },
+    /// Stop accepting DIDs starting with `prefix`
+    RemoveDidPrefix { prefix: String },
+    /// Sets the per-proof bond and the number of blocks until a proof is final
+    ConfigureProofBond {
+        amount: Uint128,
+        finalization_period_blocks: u64,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Sets (or removes, with `None`) the protocol fee nodes attach to every `StoreProof`
+    ConfigureProofFee {
+        fee: Option<ProofFee>,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Sends the collected protocol fees to the treasury
+    SweepFees {},
+    /// Limit the registered nodes per operator DID (`None` removes the limit)
+    ConfigureOperatorLimit { max_nodes_per_operator: Option<u32> },
+    /// Override the node limit of one operator DID (`None` removes the override)
+    SetOperatorNodeLimit { operator_did: String, max_nodes: Option<u32> },
+    /// Sets the probation newly registered nodes go through; applies to registrations from now on
+    ConfigureProbation {
+        policy: ProbationPolicy,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Sets the thresholds `CheckAlerts` emits `detrack_alert` events for
+    ConfigureAlertThresholds {
+        thresholds: AlertThresholds,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Revokes a proof, keeping its record as a tombstone
+    RevokeProof { proof_id: u64, reason: String },
+    /// Forfeits the bond of a non-final proof to the treasury (e.g., after an upheld dispute)
+    ForfeitProofBond { proof_id: u64 },
+    /// Enables or disables the `StoreProofLegacy` execute variant
+    ConfigureLegacyStoreProof { enabled: bool },
+    /// Sets (or clears, with `None`) the DIDs a node's legacy proofs are mapped to
+    SetLegacyMapping { node_address: String, mapping: Option<LegacyMapping> },
+    /// Sets (or clears, with `None`) the aggregate per-proof limits on snapshots and batch metadata size
+    ConfigureProofLimits {
+        max_snapshots_per_proof: Option<u64>,
+        max_batch_metadata_bytes: Option<u32>,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Sets the uc4e reward paid from the referral pool per referred registration
+    ConfigureReferralReward { amount: Uint128 },
+    /// Adds the attached uc4e to the referral reward pool
+    FundReferralPool {},
+    /// Sets the uc4e nodes accrue from the reward pool per stored proof
+    ConfigureProofRewards {
+        rewards: ProofRewards,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Adds the attached uc4e to the proof reward pool
+    FundRewards {},
+    /// Schedules making a node's unlocking deposit claimable immediately (timelocked)
+    EmergencyRelease { node_address: String },
+    /// Schedules a change of the admin timelock (timelocked)
+    ConfigureAdminTimelock { blocks: u64 },
+    /// Schedules a change of the largest reputation change applied without the timelock;
+    /// `None` applies every change immediately (timelocked)
+    ConfigureReputationDeltaLimit { max_delta: Option<u32> },
+    /// Executes a scheduled action once its timelock has passed
+    ExecutePendingAction { action_id: u64 },
+    /// Cancels a scheduled action
+    CancelPendingAction { action_id: u64 },
+    /// Sets how many blocks a node with a deposit shortfall may keep storing proofs while topping up
+    ConfigureDepositTopUpGrace { blocks: u64 },
+    /// Sets the range node reputations are kept within
+    ConfigureReputationBounds {
+        bounds: ReputationBounds,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Sets the automatic reputation changes applied on stored proofs and upheld disputes
+    ConfigureReputationScoring {
+        scoring: ReputationScoring,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Sets (or resets to the default, with `None`) the byte cap on variable-length `store_proof` event attributes
+    ConfigureEventAttributeCap { max_bytes: Option<u32> },
+    /// Sets (or resets to the default `ipfs`, `ar`, `https`, with `None`) the URI schemes accepted
+    /// for `original_data_reference`
+    ConfigureDataReferenceSchemes { schemes: Option<Vec<String>> },
+    /// Sets how long full proofs are kept before compaction when their owner's plan sets no
+    /// retention; `None` keeps them indefinitely
+    ConfigureProofRetention { retention_seconds: Option<u64> },
+    /// Sets the uc4e paid from a worker's escrow to the storing node per batch; zero disables payments
+    ConfigureEscrowPrice { price_per_batch: Uint128 },
+    /// Notifies `contract_addr` of the given events with `DetrackHookMsg`s, replacing the events
+    /// it was subscribed to
+    AddHookSubscriber { contract_addr: String, events: Vec<HookEvent> },
+    /// Stops notifying `contract_addr`
+    RemoveHookSubscriber { contract_addr: String },
+    /// Sets (or clears, with `None`) the largest run of skipped batch sequence numbers accepted per gateway
+    ConfigureSequenceGapTolerance { max_gap: Option<u64> },
+    /// Sets how many blocks a pre-registration escrow stays open (zero disables pre-registration)
+    ConfigurePreRegistration { period_blocks: u64 },
+    /// Rules on a flagged conflict: marks `canonical_proof_id` canonical and the other proof
+    /// rejected, raising the winner's and lowering the loser's reputation by `reputation_delta`
+    ResolveProofConflict { conflicting_proof_id: u64, canonical_proof_id: u64, reputation_delta: u32 },
+    /// Rejects (instead of flagging) proofs from a second node for an already covered worker window
+    ConfigureConflictingProofs { reject: bool },
+    /// Closes the review of a flagged proof, removing its owner flags
+    ClearProofFlags { proof_id: u64 },
+    /// Rules on an open dispute, settling its bond and, if upheld, penalizing the proof's submitter
+    ResolveDispute { dispute_id: u64, verdict: DisputeVerdict },
+    /// Slashes `bps` basis points of a node's deposit, including any deposit it is unlocking
+    SlashNode { node_address: String, bps: u16, reason: String },
+    /// Sets the uc4e bond required to raise a dispute (zero makes disputes free)
+    ConfigureDisputeBond { amount: Uint128 },
+    /// Sets the share (basis points per open dispute) of a removed node's deposit held until its
+    /// disputes resolve; `None` holds the whole deposit
+    ConfigureDisputeHold { bps: Option<u16> },
+    /// Sets the shares of deposit slashed by upheld disputes paid to the challenger and burned
+    ConfigureSlashDistribution {
+        distribution: SlashDistribution,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Sets what closing an epoch carries over: unclaimed rewards, unused quota, open reviews
+    ConfigureCarryOver {
+        rules: CarryOverRules,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Jails a node for `blocks` blocks: it cannot store or verify proofs until it unjails itself
+    JailNode { node_address: String, blocks: u64 },
+    /// Sets (or removes, with `None`) the threshold of upheld disputes that jails a node
+    ConfigureAutoJail {
+        policy: Option<AutoJailPolicy>,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Sets the capability limits of one node tier
+    SetTierCapabilities {
+        capabilities: TierCapabilities,
+        #[serde(default)]
+        preview: bool,
+    },
+    /// Removes a tier's capability limits (the tier falls back to `max_batch_size`, no proof cap)
+    RemoveTierCapabilities { tier: u8 },
+    /// Accepts (or, with `None`, stops accepting) a non-native deposit denom at the given weight;
+    /// CW20 tokens are given as `cw20:<token address>`
+    SetDepositDenom { denom: String, weight: Option<Decimal> },
+    /// Create or update a data-owner subscription plan
+    SetPlan {
+        plan_id: String,
+        /// Maximum proofs attributed to the owner per 30-day quota period
+        proofs_per_period: u64,
+        /// Maximum metadata bytes (proof + batch `metadata_json`) per proof
+        max_metadata_bytes: u32,
+        /// Price in uc4e for one 30-day subscription; zero means admin-assigned only
+        price: Uint128,
+        /// Seconds the owner's full proofs are kept before compaction; `None` uses the
+        /// contract-wide proof retention
+        retention_seconds: Option<u64>,
+    },
+    /// Remove a subscription plan (owners assigned to it lose their quota)
+    RemovePlan { plan_id: String },
+    /// Assign a plan to a data owner, optionally until a given time
+    AssignOwnerPlan {
+        data_owner: String,
+        plan_id: String,
+        expires_at: Option<Timestamp>,
+    },
+}
+
+/// StoreProofData - One proof of a `StoreProofs` submission, with the fields of `StoreProof`
+#[cw_serde]
+pub struct StoreProofData {
+    /// W3C DID of the Worker Node storing this proof
+    pub worker_did: String,
+    /// SHA-256 hash of the blockchain Merkle root (aggregates all batches)
+    pub data_hash: String,
+    pub tw_start: Timestamp,
+    pub tw_end: Timestamp,
+    /// Array of batch metadata (one entry per gateway batch)
+    pub batch_metadata: Vec<BatchInfo>,
+    pub original_data_reference: Option<String>,
+    pub metadata_json: Option<String>,
+    pub region: Option<String>,
+    pub site: Option<Site>,
+    pub data_owner: Option<String>,
+    #[serde(default)]
+    pub owner_shares: Vec<OwnerShare>,
+    pub value_in: Option<Uint128>,
+    pub value_out: Option<Uint128>,
+    pub unit: Option<String>,
 }
 
 /// Message type for node operations
 #[cw_serde]
+#[allow(clippy::large_enum_variant)]
 pub enum NodeExecuteMsg {
     /// Store a new proof on the blockchain (Phase 1b: Multi-batch aggregation)
     StoreProof {
@@ -82,26 +685,237 @@ pub enum NodeExecuteMsg {
         original_data_reference: Option<String>,
         /// Optional JSON metadata for additional information
         metadata_json: Option<String>,
+        /// Optional region/zone code; must be in the admin-managed region list
+        region: Option<String>,
+        /// Optional structured site/location of the measured facility
+        site: Option<Site>,
+        /// Optional data owner address; enforces the owner's plan quota when set
+        data_owner: Option<String>,
+        /// Optional co-owners with their shares (summing to 1); must include `data_owner` when set
+        #[serde(default)]
+        owner_shares: Vec<OwnerShare>,
+        /// Optional energy input value for the time window
+        value_in: Option<Uint128>,
+        /// Optional energy output value for the time window
+        value_out: Option<Uint128>,
+        /// Measurement unit (e.g., "Wh", "kWh"); required when a value is set
+        unit: Option<String>,
+    },
+    /// Store several proofs in one transaction; if any proof is rejected, none is stored.
+    /// The attached uc4e must cover the bonds and fees of all proofs.
+    StoreProofs { proofs: Vec<StoreProofData> },
+    /// Store a proof in the pre-DID message shape (mapped onto the admin-configured DIDs).
+    /// Only accepted while legacy submissions are enabled in config.
+    StoreProofLegacy {
+        data_hash: String,
+        original_data_reference: Option<String>,
+        data_owner: Option<String>,
+        metadata_json: Option<String>,
+        tw_start: Timestamp,
+        tw_end: Timestamp,
+        value_in: Option<Uint128>,
+        value_out: Option<Uint128>,
+        unit: String,
     },
-    /// Register a new node
-    RegisterNode {},
+    /// Register a new node, optionally attributing the onboarding to a registered `referrer`
+    /// and declaring the `operator_did` (`did:c4e:operator:...`) of the entity running it
+    RegisterNode { referrer: Option<String>, operator_did: Option<String> },
+    /// Escrow a deposit while the operator's native stake is still being set up
+    PreRegister {},
+    /// Register using the escrowed `PreRegister` deposit once the stake meets a tier
+    CompleteRegistration {},
+    /// Emit `deposit_shortfall` events for nodes whose deposit is below their tier requirement
+    /// (callable by anyone; scans at most `limit` nodes after `start_after`)
+    CheckDepositShortfalls { start_after: Option<String>, limit: Option<u32> },
+    /// Emit a `detrack_alert` event for every configured alert threshold that is currently
+    /// breached (callable by anyone, e.g. by a keeper at the end of each monitoring period)
+    CheckAlerts {},
+    /// Refund expired pre-registration escrows (callable by anyone)
+    RefundExpiredPreRegistrations { limit: Option<u32> },
     /// Add to an existing node's deposit
     AddDeposit {}, // Added
+    /// Move the calling node to the tier its current native stake qualifies for. Attached
+    /// funds are added to the deposit, which must cover the new tier.
+    UpdateTier {},
     /// Verify a proof
     VerifyProof { data_hash: String },
+    /// Verify several proofs at once. Unknown or rejected hashes do not fail the transaction;
+    /// the result of each hash is reported in an event and in the `VerifyProofsResponse` data.
+    VerifyProofs { data_hashes: Vec<String> },
     /// Initiate unlocking of the node's deposit
     UnlockDeposit {},
     /// Claim unlocked deposit after the unbonding period
     ClaimUnlockedDeposit {},
+    /// Set or clear the calling node's region/zone code
+    SetRegion { region: Option<String> },
+    /// Mark the calling node non-operational for maintenance; it cannot store or verify proofs until resumed
+    PauseSelf {},
+    /// Make the calling paused node operational again
+    ResumeSelf {},
+    /// Release the calling node from jail once its jail term has ended
+    Unjail {},
+    /// Reclaim the bonds of the caller's proofs that have reached finality
+    ClaimProofBonds { proof_ids: Vec<u64> },
+    /// Retire a worker DID, sealing its proof chain at its latest proof. Allowed for the node
+    /// that stored that proof, or the admin.
+    DecommissionWorker { worker_did: String },
+    /// Dispute another node's proof (or, as one of its owners, any proof attributed to the caller),
+    /// attaching `Config.dispute_bond`
+    RaiseDispute { proof_id: u64, evidence_uri: String },
+    /// Pay out the uc4e proof rewards accrued by the calling node
+    ClaimRewards {},
+    /// Emit the `detrack_epoch_closed` summary of an ended epoch (callable by anyone, once per epoch)
+    CloseEpoch { epoch: u64 },
+    /// Attest one of the first proofs of a node on probation (not allowed for nodes on probation)
+    AttestProof { proof_id: u64 },
+    /// Declare how often a gateway controlled by the calling node is expected to report, so
+    /// `GatewaysMissingData` can detect its downtime; `None` withdraws the declaration
+    DeclareGateway { gateway_did: String, reporting_interval_seconds: Option<u64> },
+    /// Bind a worker DID to the node allowed to store its proofs, or with `None` release it.
+    /// Allowed for the admin and the worker DID's controller.
+    AssignWorker { worker_did: String, node_address: Option<String> },
+    /// Strip the metadata and data references of proofs past their retention (callable by
+    /// anyone; scans at most `limit` proofs after `start_after`)
+    CompactProofs { start_after: Option<u64>, limit: Option<u32> },
+}
+
+impl NodeExecuteMsg {
+    /// Returns whether the message is rejected while the contract is paused: proof
+    /// submissions, registrations and deposit operations.
+    pub fn is_pausable(&self) -> bool {
+        matches!(
+            self,
+            NodeExecuteMsg::StoreProof { .. }
+                | NodeExecuteMsg::StoreProofs { .. }
+                | NodeExecuteMsg::StoreProofLegacy { .. }
+                | NodeExecuteMsg::RegisterNode { .. }
+                | NodeExecuteMsg::PreRegister {}
+                | NodeExecuteMsg::CompleteRegistration {}
+                | NodeExecuteMsg::AddDeposit {}
+                | NodeExecuteMsg::UpdateTier {}
+                | NodeExecuteMsg::UnlockDeposit {}
+                | NodeExecuteMsg::ClaimUnlockedDeposit {}
+        )
+    }
 }
 
-/// Main execute message type that wraps admin and node messages
+/// Message type for data owner operations
 #[cw_serde]
+pub enum DataOwnerExecuteMsg {
+    /// Purchase (or extend by 30 days) a subscription plan; requires the plan price in uc4e
+    SubscribePlan { plan_id: String },
+    /// Flag a proof attributed to the caller (as data owner or co-owner) as incorrect,
+    /// marking it for admin review. Attaching `Config.dispute_bond` also opens a dispute.
+    /// A delegate sets `owner` to flag on that owner's behalf.
+    FlagProof { proof_id: u64, reason: String, owner: Option<String> },
+    /// Approve proofs attributed to the caller (as data owner or co-owner). Proofs the caller
+    /// already approved are skipped. A delegate sets `owner` to approve on that owner's behalf.
+    ApproveProofs { proof_ids: Vec<u64>, owner: Option<String> },
+    /// Authorize an address to approve and flag proofs on the caller's behalf
+    AddDelegate { delegate: String },
+    /// Withdraw a delegate's authorization
+    RemoveDelegate { delegate: String },
+    /// Add the attached uc4e to the escrow paying for the storage of `worker_did`'s proofs
+    FundWorkerEscrow { worker_did: String },
+    /// Withdraw `amount` (or, with `None`, all) of the caller's escrow for `worker_did`
+    WithdrawWorkerEscrow { worker_did: String, amount: Option<Uint128> },
+}
+
+/// Main execute message type that wraps admin, node and data owner messages
+#[cw_serde]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     /// Admin operations
     Admin(AdminExecuteMsg),
     /// Node operations
     Node(NodeExecuteMsg),
+    /// Data owner operations
+    DataOwner(DataOwnerExecuteMsg),
+    /// Deposit of CW20 tokens, sent by the token contract on behalf of the node
+    Receive(Cw20ReceiveMsg),
+}
+
+/// Cw20ReceiveMsg - Hook a CW20 token contract calls when tokens are sent to this contract
+/// (wire-compatible with `cw20::Cw20ReceiveMsg`)
+#[cw_serde]
+pub struct Cw20ReceiveMsg {
+    /// Address that sent the tokens
+    pub sender: String,
+    pub amount: Uint128,
+    /// JSON-encoded `Cw20HookMsg`
+    pub msg: Binary,
+}
+
+/// Cw20HookMsg - Deposit operation paid with the tokens of a `Cw20ReceiveMsg`. The token must be
+/// accepted as the deposit denom `cw20:<token address>` (see `SetDepositDenom`).
+#[cw_serde]
+pub enum Cw20HookMsg {
+    /// Register the sender as a node, depositing the tokens
+    RegisterNode { referrer: Option<String>, operator_did: Option<String> },
+    /// Add the tokens to the sender's deposit
+    AddDeposit {},
+    /// Recompute the sender's tier, adding the tokens to its deposit
+    UpdateTier {},
+}
+
+/// DetrackHookMsg - Notification sent to hook subscribers (see `AddHookSubscriber`), wrapped in
+/// `HookExecuteMsg`
+#[cw_serde]
+pub enum DetrackHookMsg {
+    /// A proof was stored
+    ProofStored { proof_id: u64, node: String, worker_did: String, data_hash: String },
+    /// A node's deposit was slashed, by `SlashNode` or an upheld dispute (`dispute_id`)
+    NodeSlashed { node: String, slashed: Vec<Coin>, dispute_id: Option<u64> },
+    /// A dispute was raised against a proof stored by `node`
+    DisputeOpened { dispute_id: u64, proof_id: u64, node: String, raised_by: String },
+    /// A dispute was ruled on
+    DisputeResolved { dispute_id: u64, proof_id: u64, node: String, upheld: bool },
+}
+
+impl DetrackHookMsg {
+    /// The event subscribers opt into to receive this notification
+    pub fn event(&self) -> HookEvent {
+        match self {
+            DetrackHookMsg::ProofStored { .. } => HookEvent::ProofStored,
+            DetrackHookMsg::NodeSlashed { .. } => HookEvent::NodeSlashed,
+            DetrackHookMsg::DisputeOpened { .. } => HookEvent::DisputeOpened,
+            DetrackHookMsg::DisputeResolved { .. } => HookEvent::DisputeResolved,
+        }
+    }
+
+    /// Message delivering the notification to `contract_addr`
+    pub fn into_cosmos_msg(self, contract_addr: impl Into<String>) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: to_json_binary(&HookExecuteMsg::DetrackHook(self))?,
+            funds: vec![],
+        }
+        .into())
+    }
+}
+
+/// HookExecuteMsg - The execute variant hook subscribers implement, i.e. `{"detrack_hook": {...}}`
+#[cw_serde]
+pub enum HookExecuteMsg {
+    DetrackHook(DetrackHookMsg),
+}
+
+/// Cw20ExecuteMsg - The part of the CW20 execute interface used to pay out and burn token deposits
+#[cw_serde]
+pub enum Cw20ExecuteMsg {
+    Transfer { recipient: String, amount: Uint128 },
+    Burn { amount: Uint128 },
+}
+
+/// Cw20QueryMsg - The part of the CW20 query interface used to check token balances
+#[cw_serde]
+pub enum Cw20QueryMsg {
+    Balance { address: String },
+}
+
+#[cw_serde]
+pub struct Cw20BalanceResponse {
+    pub balance: Uint128,
 }
 
 /// Message type for `migrate` entry_point
@@ -122,6 +936,13 @@ pub enum QueryMsg {
     /// Returns a specific proof by data hash
     #[returns(ProofResponse)]
     ProofByHash { data_hash: String },
+    /// Looks up to 50 data hashes at once, reporting each as found (with its proof) or missing
+    #[returns(ProofsByHashesResponse)]
+    ProofsByHashes { data_hashes: Vec<String> },
+    /// Stable verification interface for other contracts: whether a data hash is stored and
+    /// final. Never fails for unknown hashes. See `helpers::query_verify_hash`.
+    #[returns(VerifyHashResponse)]
+    VerifyHash { data_hash: String },
     /// Returns a list of all proofs
     #[returns(ProofsResponse)]
     Proofs { start_after: Option<u64>, limit: Option<u32> },
@@ -134,6 +955,12 @@ pub enum QueryMsg {
     /// Returns node information including whitelisted status and reputation
     #[returns(NodeInfoResponse)]
     NodeInfo { address: String },
+    /// Returns the onboarding stage of an address and the steps left before it is operational
+    #[returns(OnboardingStatusResponse)]
+    OnboardingStatus { address: String },
+    /// Previews slashing `bps` basis points of a registered node's deposit without executing it
+    #[returns(SimulateSlashResponse)]
+    SimulateSlash { node_address: String, bps: u16 },
     /// Returns proofs submitted by a specific Worker Node DID
     #[returns(ProofsResponse)]
     ProofsByWorker { 
@@ -141,12 +968,223 @@ pub enum QueryMsg {
         start_after: Option<u64>, 
         limit: Option<u32> 
     },
-    /// Returns proofs that include batches from a specific Gateway DID
+    /// Returns proofs that include batches from a specific Gateway DID.
+    /// With `from` and/or `to` set, only proofs whose `tw_start` falls in `[from, to)` are
+    /// returned, ordered by `tw_start` and then proof ID.
     #[returns(ProofsResponse)]
     ProofsByGateway { 
         gateway_did: String, 
         start_after: Option<u64>, 
-        limit: Option<u32> 
+        limit: Option<u32>,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    },
+    /// Returns proofs stored by a node, ordered by proof ID
+    #[returns(ProofsResponse)]
+    ProofsByNode {
+        node_address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns proofs whose `tw_start` falls in `[start, end)`, ordered by `tw_start` and then
+    /// proof ID
+    #[returns(ProofsResponse)]
+    ProofsByTimeRange {
+        start: Timestamp,
+        end: Timestamp,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the summed energy values of a worker's active, non-rejected proofs per unit, for
+    /// proofs whose `tw_start` day falls in `[from, to)` (both rounded down to their UTC day)
+    #[returns(EnergyStatsResponse)]
+    EnergyStats {
+        worker_did: String,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    },
+    /// Returns the admin-managed list of accepted region codes
+    #[returns(RegionsResponse)]
+    Regions {},
+    /// Returns proofs stored at or after block `height`, in storage order, for incremental resync
+    #[returns(ProofsResponse)]
+    ProofsSinceHeight {
+        height: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns proofs tagged with a specific region
+    #[returns(ProofsResponse)]
+    ProofsByRegion {
+        region: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns proofs attributed to `owner`, as data owner or co-owner
+    #[returns(ProofsResponse)]
+    ProofsByOwner {
+        owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns proofs attributed to `data_owner` (as data owner or co-owner) that the owner has
+    /// not approved yet
+    #[returns(ProofsResponse)]
+    UnapprovedProofs {
+        data_owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the delegates authorized to approve and flag proofs on behalf of `owner`
+    #[returns(OwnerDelegatesResponse)]
+    OwnerDelegates { owner: String },
+    /// Returns proofs whose site references a specific grid connection point
+    #[returns(ProofsResponse)]
+    ProofsByGridConnectionPoint {
+        grid_connection_point: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns all data-owner subscription plans
+    #[returns(PlansResponse)]
+    Plans {},
+    /// Returns the plan and current quota usage of a data owner
+    #[returns(OwnerQuotaResponse)]
+    OwnerQuota { data_owner: String },
+    /// Returns addresses of nodes located in a specific region
+    #[returns(NodeAddressesResponse)]
+    NodesByRegion {
+        region: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns addresses of nodes that registered with the given referrer
+    #[returns(NodeAddressesResponse)]
+    NodesByReferrer {
+        referrer: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns registered nodes ordered by address, optionally only those of one tier and with
+    /// at least `min_reputation`
+    #[returns(NodesResponse)]
+    Nodes {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        tier: Option<u8>,
+        min_reputation: Option<i32>,
+    },
+    /// Returns the legacy DID mapping of a node, if any
+    #[returns(LegacyMappingResponse)]
+    LegacyMapping { node_address: String },
+    /// Returns the bond escrowed for a proof, if any
+    #[returns(ProofBondResponse)]
+    ProofBond { proof_id: u64 },
+    /// Returns per-gateway proof summaries (time window and energy values) straight from the
+    /// gateway index, without loading full proofs. Filters and ordering match `ProofsByGateway`.
+    #[returns(GatewayProofSummariesResponse)]
+    GatewayProofSummaries {
+        gateway_did: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    },
+    /// Returns flagged conflicts: proofs from a second node for the same worker and window
+    #[returns(ProofConflictsResponse)]
+    ProofConflicts { start_after: Option<u64>, limit: Option<u32> },
+    /// Returns proofs flagged by their owners, with the flags, ordered by proof ID
+    #[returns(ProofFlagsResponse)]
+    ProofFlags { start_after: Option<u64>, limit: Option<u32> },
+    /// Returns a dispute by ID
+    #[returns(DisputeResponse)]
+    Dispute { dispute_id: u64 },
+    /// Returns disputes ordered by ID
+    #[returns(DisputesResponse)]
+    Disputes { start_after: Option<u64>, limit: Option<u32> },
+    /// Returns the deposit portion held for a removed node's open disputes, if any
+    #[returns(HeldDepositResponse)]
+    HeldDeposit { node_address: String },
+    /// Reports what `migrate` to `target_version` would transform and which records it could not migrate
+    #[returns(MigrationPreviewResponse)]
+    MigrationPreview { target_version: String },
+    /// Returns the admin audit log of timelocked actions, oldest first
+    #[returns(AdminAuditLogResponse)]
+    AdminAuditLog { start_after: Option<u64>, limit: Option<u32> },
+    /// Returns the scheduled timelocked actions that were neither executed nor cancelled, by action ID
+    #[returns(PendingActionsResponse)]
+    PendingActions { start_after: Option<u64>, limit: Option<u32> },
+    /// Returns the nodes assigned to audit a proof, selected deterministically from the nodes
+    /// registered when it was stored and kept with the proof
+    #[returns(AuditCommitteeResponse)]
+    AuditCommittee { proof_id: u64 },
+    /// Returns the registered nodes of an operator DID and the node limit that applies to it
+    #[returns(OperatorNodesResponse)]
+    OperatorNodes { operator_did: String },
+    /// Returns the roles granted to an address (the admin implicitly holds all of them)
+    #[returns(RolesResponse)]
+    Roles { address: String },
+    /// Returns the open pre-registration escrow of an operator, if any
+    #[returns(PreRegistrationResponse)]
+    PreRegistration { address: String },
+    /// Returns whether a worker DID is decommissioned and, if so, its final covered window
+    #[returns(WorkerStatusResponse)]
+    WorkerStatus { worker_did: String },
+    /// Summarizes a worker's proof history: proof count, first and last proof, time covered by
+    /// its proof windows starting in `[from, to)` versus gaps, and the head of its proof chain
+    #[returns(WorkerActivityResponse)]
+    WorkerActivity {
+        worker_did: String,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    },
+    /// Returns the escrow funding the storage of a worker's proofs
+    #[returns(WorkerEscrowResponse)]
+    WorkerEscrow { worker_did: String },
+    /// Returns the contracts notified of hook events, with the events each receives
+    #[returns(HookSubscribersResponse)]
+    HookSubscribers {},
+    /// Returns the node a worker DID is assigned to, if any
+    #[returns(WorkerAssignmentResponse)]
+    WorkerAssignment { worker_did: String },
+    /// Returns declared gateways whose latest batch window ended more than their reporting
+    /// interval before `since` (typically the current time), by gateway DID
+    #[returns(GatewaysMissingDataResponse)]
+    GatewaysMissingData { since: Timestamp, start_after: Option<String>, limit: Option<u32> },
+    /// Returns the batch sequence tracking of a gateway DID: the next expected sequence and recorded gaps
+    #[returns(GatewaySequenceResponse)]
+    GatewaySequence { gateway_did: String },
+    /// Returns the query API versions served and which of them are deprecated
+    #[returns(ApiVersionsResponse)]
+    ApiVersions {},
+    /// Version 2 of the query API, served alongside the version 1 queries above. Each query
+    /// returns the response type listed on `QueryMsgV2`.
+    #[returns(serde_json::Value)]
+    V2(QueryMsgV2),
+}
+
+/// Latest query API version; `QueryMsg::V2` serves it
+pub const QUERY_API_VERSION: u8 = 2;
+
+/// Query API versions still served but due to be removed
+pub const DEPRECATED_QUERY_API_VERSIONS: &[u8] = &[];
+
+/// Queries of version 2 of the query API, sent as `{"v2": {...}}`. Responses may change shape
+/// against their version 1 counterparts; version 1 keeps its shapes until it is removed.
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsgV2 {
+    /// Returns a specific proof by ID
+    #[returns(ProofResponseV2)]
+    Proof { id: u64 },
+    /// Returns a specific proof by data hash
+    #[returns(ProofResponseV2)]
+    ProofByHash { data_hash: String },
+    /// Returns all proofs with pagination
+    #[returns(ProofsResponseV2)]
+    Proofs {
+        start_after: Option<u64>,
+        limit: Option<u32>,
     },
 }
 
@@ -155,7 +1193,9 @@ pub enum QueryMsg {
 pub struct ConfigResponse {
     pub admin: String,
     pub proof_count: u64,
-    pub min_reputation_threshold: i32,
+    pub min_reputation_tier1: i32,
+    pub min_reputation_tier2: i32,
+    pub min_reputation_tier3: i32,
     pub treasury: Option<String>,
     pub did_contract_address: String,
     // Add fields from InstantiateMsg
@@ -168,6 +1208,92 @@ pub struct ConfigResponse {
     pub use_whitelist: bool,
     pub deposit_unlock_period_blocks: u64,
     pub max_batch_size: u32,
+    pub facility_registry_address: Option<String>,
+    pub proof_bond: Uint128,
+    pub proof_finalization_period_blocks: u64,
+    pub max_snapshots_per_proof: Option<u64>,
+    pub max_batch_metadata_bytes: Option<u32>,
+    pub legacy_store_proof_enabled: bool,
+    pub deposit_denoms: Vec<DepositDenom>,
+    pub tier_capabilities: Vec<TierCapabilities>,
+    pub reject_conflicting_proofs: bool,
+    pub pre_registration_period_blocks: u64,
+    pub referral_reward: Uint128,
+    pub referral_pool: Uint128,
+    pub admin_timelock_blocks: u64,
+    pub deposit_topup_grace_blocks: u64,
+    pub reputation_bounds: ReputationBounds,
+    pub max_event_attribute_bytes: Option<u32>,
+    pub max_sequence_gap: Option<u64>,
+    pub dispute_bond: Uint128,
+    pub reputation_scoring: ReputationScoring,
+    pub dispute_hold_bps: Option<u16>,
+    pub proof_rewards: ProofRewards,
+    pub reward_pool: Uint128,
+    pub proof_fee: Option<ProofFee>,
+    pub collected_fees: Uint128,
+    pub alert_thresholds: AlertThresholds,
+    pub paused: bool,
+    pub probation: ProbationPolicy,
+    pub max_nodes_per_operator: Option<u32>,
+    /// Accepted `original_data_reference` schemes, including the default when unset
+    pub data_reference_schemes: Vec<String>,
+    pub max_reputation_delta: Option<u32>,
+    /// Accepted DID prefixes, including the default when unset
+    pub did_prefixes: Vec<String>,
+    pub proof_retention_seconds: Option<u64>,
+    pub escrow_price_per_batch: Uint128,
+    pub slash_distribution: SlashDistribution,
+    pub carry_over: CarryOverRules,
+    pub auto_jail: Option<AutoJailPolicy>,
+    pub node_batch_limit: Option<u32>,
+}
+
+/// Response of `QueryMsg::VerifyHash`. Part of the stable contract-to-contract interface:
+/// fields are only ever added, never renamed or removed.
+/// ProofLookup - Result of one hash of a `ProofsByHashes` query
+#[cw_serde]
+pub struct ProofLookup {
+    pub data_hash: String,
+    /// The proof stored with the hash (revoked ones included), or `None` when missing
+    pub proof: Option<ProofResponse>,
+}
+
+#[cw_serde]
+pub struct ProofsByHashesResponse {
+    /// One result per requested hash, in request order
+    pub results: Vec<ProofLookup>,
+}
+
+#[cw_serde]
+pub struct VerifyHashResponse {
+    /// Whether a proof with the hash is stored
+    pub exists: bool,
+    pub proof_id: Option<u64>,
+    /// Whether the proof's finalization period has passed and it is neither rejected, revoked nor under review
+    pub finalized: bool,
+    /// Whether the proof was revoked by the admin
+    #[serde(default)]
+    pub revoked: bool,
+    /// Whether every owner of the proof approved it (false for proofs without owners)
+    #[serde(default)]
+    pub owner_approved: bool,
+}
+
+/// Execute response data of `NodeExecuteMsg::VerifyProofs`, one result per submitted hash
+#[cw_serde]
+pub struct VerifyProofsResponse {
+    pub results: Vec<ProofVerification>,
+}
+
+/// ProofVerification - Result of verifying one hash in `VerifyProofs`
+#[cw_serde]
+pub struct ProofVerification {
+    pub data_hash: String,
+    /// Proof stored under the hash, if any
+    pub proof_id: Option<u64>,
+    /// Whether the proof exists and is not rejected
+    pub verified: bool,
 }
 
 #[cw_serde]
@@ -189,8 +1315,36 @@ pub struct ProofResponse {
     pub metadata_json: Option<String>,
     /// Blockchain timestamp when proof was stored
     pub stored_at: Timestamp,
+    /// Block height when proof was stored (0 if stored before heights were recorded)
+    pub stored_height: u64,
     /// Address of the node that stored this proof
     pub stored_by: String,
+    /// Optional region/zone code of the proof
+    pub region: Option<String>,
+    /// Optional structured site/location of the measured facility
+    pub site: Option<Site>,
+    /// Optional data owner the proof is attributed to
+    pub data_owner: Option<String>,
+    /// Co-owners with their shares (empty unless the proof was split)
+    pub owner_shares: Vec<OwnerShare>,
+    /// Optional energy input value for the time window
+    pub value_in: Option<Uint128>,
+    /// Optional energy output value for the time window
+    pub value_out: Option<Uint128>,
+    /// Measurement unit of `value_in` / `value_out`
+    pub unit: Option<String>,
+    /// True if the proof lost a conflict ruling or an upheld dispute
+    pub rejected: bool,
+    /// True while owner flags on the proof await review
+    pub under_review: bool,
+    /// Layout version the proof record was written with
+    pub schema_version: u16,
+    /// `Revoked` once the admin revoked the proof
+    pub status: ProofStatus,
+    /// Until when the full proof is kept (`None`: indefinitely)
+    pub retain_until: Option<Timestamp>,
+    /// True once the metadata and data references were stripped after the retention ended
+    pub compacted: bool,
 }
 
 #[cw_serde]
@@ -198,6 +1352,133 @@ pub struct ProofsResponse {
     pub proofs: Vec<ProofResponse>,
 }
 
+/// Energy values reported for a proof's time window
+#[cw_serde]
+pub struct EnergyValues {
+    pub value_in: Option<Uint128>,
+    pub value_out: Option<Uint128>,
+    pub unit: String,
+}
+
+/// Energy summed over a worker's proofs in one unit
+#[cw_serde]
+pub struct UnitEnergyTotal {
+    pub unit: String,
+    pub value_in: Uint128,
+    pub value_out: Uint128,
+    /// Proofs whose values are included
+    pub proof_count: u64,
+}
+
+#[cw_serde]
+pub struct EnergyStatsResponse {
+    pub worker_did: String,
+    /// Totals ordered by unit
+    pub totals: Vec<UnitEnergyTotal>,
+}
+
+/// Version 2 proof: `data_owner` and `owner_shares` are merged into `owners`, and the energy
+/// values are grouped into `energy`
+#[cw_serde]
+pub struct ProofResponseV2 {
+    pub id: u64,
+    pub worker_did: String,
+    pub data_hash: String,
+    pub tw_start: Timestamp,
+    pub tw_end: Timestamp,
+    pub batch_metadata: Vec<BatchInfo>,
+    pub original_data_reference: Option<String>,
+    pub metadata_json: Option<String>,
+    pub stored_at: Timestamp,
+    pub stored_height: u64,
+    pub stored_by: String,
+    pub region: Option<String>,
+    pub site: Option<Site>,
+    /// Owners with their shares: the data owner alone with share 1 unless the proof was split
+    pub owners: Vec<OwnerShare>,
+    /// `None` when the proof reports no energy values
+    pub energy: Option<EnergyValues>,
+    pub rejected: bool,
+    pub under_review: bool,
+    pub schema_version: u16,
+    pub status: ProofStatus,
+    pub retain_until: Option<Timestamp>,
+    pub compacted: bool,
+}
+
+#[cw_serde]
+pub struct ProofsResponseV2 {
+    pub proofs: Vec<ProofResponseV2>,
+}
+
+#[cw_serde]
+pub struct ApiVersionsResponse {
+    /// Latest query API version
+    pub latest: u8,
+    /// Every version served, oldest first
+    pub supported: Vec<u8>,
+    /// Served versions due to be removed; clients should move to `latest`
+    pub deprecated: Vec<u8>,
+}
+
+#[cw_serde]
+pub struct RegionsResponse {
+    pub regions: Vec<String>,
+}
+
+#[cw_serde]
+pub struct NodeAddressesResponse {
+    pub addresses: Vec<String>,
+}
+
+/// NodeSummary - A registered node as listed by the `Nodes` query
+#[cw_serde]
+pub struct NodeSummary {
+    pub address: String,
+    pub tier: u8,
+    pub reputation: i32,
+    pub deposit: Uint128,
+    pub proof_count: u64,
+    pub disputed_proofs: u64,
+    pub added_at: Timestamp,
+    pub region: Option<String>,
+    pub operator_did: Option<String>,
+    pub paused: bool,
+    pub jailed_until: Option<u64>,
+}
+
+#[cw_serde]
+pub struct NodesResponse {
+    pub nodes: Vec<NodeSummary>,
+}
+
+#[cw_serde]
+pub struct PlanResponse {
+    pub plan_id: String,
+    pub proofs_per_period: u64,
+    pub max_metadata_bytes: u32,
+    pub price: Uint128,
+    pub retention_seconds: Option<u64>,
+}
+
+#[cw_serde]
+pub struct PlansResponse {
+    pub plans: Vec<PlanResponse>,
+}
+
+#[cw_serde]
+pub struct OwnerQuotaResponse {
+    pub data_owner: String,
+    pub plan: Option<PlanResponse>, // None if the owner has no plan or the plan was removed
+    pub expires_at: Option<Timestamp>, // None for plans assigned without expiry
+    pub active: bool,
+    pub period: u64, // Current 30-day quota period index
+    pub proofs_used: u64,
+    /// Unused proofs carried over from the previous period, included in `proofs_remaining`
+    pub carried_over: u64,
+    pub proofs_remaining: u64,
+}
+
 #[cw_serde]
 pub struct WhitelistedResponse {
     pub is_whitelisted: bool,
@@ -209,6 +1490,24 @@ pub struct NodeReputationResponse {
     pub reputation: i32,
 }
 
+#[cw_serde]
+pub struct SimulateSlashResponse {
+    pub node_address: String,
+    /// Coins the slash would remove from the deposit
+    pub slashed_coins: Vec<Coin>,
+    /// Coins the slash would remove from the deposit the node is unlocking
+    pub slashed_unlocking_coins: Vec<Coin>,
+    /// uc4e-equivalent deposit value after the slash
+    pub resulting_deposit: Uint128,
+    pub required_deposit: Uint128,
+    /// Whether the node would fall below its tier requirement (starting its top-up deadline)
+    pub below_tier_requirement: bool,
+    /// Configured treasury; `None` means slashed funds would stay in the contract
+    pub treasury: Option<String>,
+    /// Coins the treasury would receive
+    pub treasury_amount: Vec<Coin>,
+}
+
 #[cw_serde]
 pub struct NodeInfoResponse {
     pub address: String,
@@ -216,6 +1515,7 @@ pub struct NodeInfoResponse {
     pub reputation: i32,
     pub added_at: Option<Timestamp>, // Timestamp of registration or when added by admin
     pub deposit: Option<Uint128>, // Current locked deposit in the contract
+    pub deposit_coins: Option<Vec<Coin>>, // Coins held for the deposit, refunded as-is
     pub native_staked_amount: Option<Uint128>, // Calculated native stake from the staking module
     pub tier: Option<u8>, // Current operational tier
     pub last_updated: Option<Timestamp>, // Last time the node's record was updated
@@ -223,4 +1523,306 @@ pub struct NodeInfoResponse {
     pub disputed_proofs: Option<u64>,
     pub unlocking_deposit_amount: Option<Uint128>, // Amount of deposit currently unlocking
     pub unlocking_deposit_release_at_block: Option<u64>, // Block height when the deposit will be claimable
-}
\ No newline at end of file
+    pub unlocking_deposit_coins: Option<Vec<Coin>>, // Coins that will be returned by the claim
+    pub region: Option<String>, // Region/zone code set by the node operator
+    pub referrer: Option<String>, // Node that onboarded this node
+    pub referral_count: Option<u64>, // Nodes registered with this node as referrer
+    pub topup_deadline_block: Option<u64>, // Deadline to top up a deposit shortfall
+    pub paused: Option<bool>, // Paused by the operator for maintenance
+    pub accrued_rewards: Option<Uint128>, // Proof rewards claimable with ClaimRewards
+    pub escrow_earnings: Option<Uint128>, // Worker escrow payments claimable with ClaimRewards
+    pub probation_until_block: Option<u64>, // End of the probation period set at registration
+    pub pending_attestations: Option<u32>, // Attestations still needed before probation can end
+    pub operator_did: Option<String>, // DID of the entity operating the node
+    pub jailed_until: Option<u64>, // Block height the node's jail term ends; it stays jailed until it unjails
+}
+
+#[cw_serde]
+pub struct ProofBondResponse {
+    pub proof_id: u64,
+    /// `None` when no bond is held (bond disabled, already claimed, or forfeited)
+    pub node: Option<String>,
+    pub amount: Uint128,
+    pub release_at_block: Option<u64>,
+}
+
+#[cw_serde]
+pub struct MigrationPreviewResponse {
+    /// Contract version recorded in state
+    pub stored_version: String,
+    /// Version of the code answering the query; `migrate` can only move state to this version
+    pub code_version: String,
+    /// False when `target_version` is not `code_version`; the counts below are for `code_version`
+    pub target_supported: bool,
+    /// Proofs written with an older record layout
+    pub proofs_to_upgrade: u64,
+    /// Gateway index entries still in the old `()` value format
+    pub gateway_entries_to_rewrite: u64,
+    /// Gateway time, worker window and height index entries missing for stored proofs
+    pub index_entries_to_add: u64,
+    /// Nodes and unlocking deposits whose held coins must be backfilled
+    pub deposits_to_backfill: u64,
+    /// Entries left in the unused `"nodes"` namespace, merged into the node registry
+    pub legacy_nodes_to_merge: u64,
+    /// Number of records `migrate` would fail on
+    pub unmigratable_count: u64,
+    /// Descriptions of the first unmigratable records
+    pub unmigratable: Vec<String>,
+}
+
+#[cw_serde]
+pub struct AdminAuditEntryResponse {
+    pub entry_id: u64,
+    pub action_id: u64,
+    pub action: TimelockedAction,
+    pub event: AuditEvent,
+    pub admin: String,
+    pub block_height: u64,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct AdminAuditLogResponse {
+    pub entries: Vec<AdminAuditEntryResponse>,
+}
+
+#[cw_serde]
+pub struct PendingActionResponse {
+    pub action_id: u64,
+    pub action: TimelockedAction,
+    pub scheduled_by: String,
+    pub scheduled_at_block: u64,
+    pub executable_at_block: u64,
+}
+
+#[cw_serde]
+pub struct PendingActionsResponse {
+    pub actions: Vec<PendingActionResponse>,
+}
+
+#[cw_serde]
+pub struct OwnerDelegatesResponse {
+    pub owner: String,
+    pub delegates: Vec<String>,
+}
+
+#[cw_serde]
+pub struct AuditCommitteeResponse {
+    pub proof_id: u64,
+    /// Hex SHA-256 seed the members are ranked by
+    pub seed: String,
+    /// Committee members, best-ranked first
+    pub members: Vec<String>,
+}
+
+#[cw_serde]
+pub struct OperatorNodesResponse {
+    pub operator_did: String,
+    pub nodes: Vec<String>,
+    /// Node limit of the operator: its override, else `max_nodes_per_operator`
+    pub limit: Option<u32>,
+    /// Whether `limit` is an admin override for this operator
+    pub limit_overridden: bool,
+}
+
+#[cw_serde]
+pub struct RolesResponse {
+    pub address: String,
+    /// Whether the address is the admin, which holds every role
+    pub is_admin: bool,
+    /// Roles granted explicitly with `GrantRole`
+    pub roles: Vec<Role>,
+}
+
+#[cw_serde]
+pub struct PreRegistrationResponse {
+    pub address: String,
+    /// `None` when no escrow is open
+    pub value: Option<Uint128>,
+    pub coins: Vec<Coin>,
+    pub expires_at_block: Option<u64>,
+}
+
+#[cw_serde]
+pub struct ProofConflictResponse {
+    /// The later proof that duplicates an existing worker window
+    pub conflicting_proof_id: u64,
+    /// The proof first stored for the window
+    pub original_proof_id: u64,
+    pub worker_did: String,
+    pub tw_start: Timestamp,
+    pub tw_end: Timestamp,
+    pub detected_at: Timestamp,
+    /// Set once the conflict is resolved
+    pub canonical_proof_id: Option<u64>,
+    pub resolved_at: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct ProofConflictsResponse {
+    pub conflicts: Vec<ProofConflictResponse>,
+}
+
+#[cw_serde]
+pub struct DisputeResponse {
+    pub dispute_id: u64,
+    pub proof_id: u64,
+    pub raised_by: String,
+    pub evidence_uri: String,
+    /// uc4e escrowed by the raiser
+    pub bond: Uint128,
+    pub status: DisputeStatus,
+    pub raised_at: Timestamp,
+    pub resolved_at: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct DisputesResponse {
+    pub disputes: Vec<DisputeResponse>,
+}
+
+#[cw_serde]
+pub struct OnboardingStatusResponse {
+    pub address: String,
+    /// `None` for an address that is neither whitelisted nor registered
+    pub stage: Option<OnboardingStage>,
+    /// Requirements left before the node is operational, in onboarding order; empty once it is
+    pub remaining_steps: Vec<OnboardingStep>,
+}
+
+#[cw_serde]
+pub struct HeldDepositResponse {
+    pub node_address: String,
+    pub amount: Uint128,
+    pub coins: Vec<Coin>,
+    pub held_at: Option<Timestamp>,
+    /// Disputes against the node's proofs still open; the coins are refunded when this reaches zero
+    pub open_disputes: u64,
+}
+
+#[cw_serde]
+pub struct ProofFlagResponse {
+    pub flagged_by: String,
+    pub reason: String,
+    pub flagged_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct FlaggedProofResponse {
+    pub proof_id: u64,
+    pub flags: Vec<ProofFlagResponse>,
+}
+
+#[cw_serde]
+pub struct ProofFlagsResponse {
+    pub proofs: Vec<FlaggedProofResponse>,
+}
+
+#[cw_serde]
+pub struct WorkerStatusResponse {
+    pub worker_did: String,
+    pub retired: bool,
+    pub retired_at: Option<Timestamp>,
+    pub retired_by: Option<String>,
+    /// Last proof of the sealed chain, with its time window (`None` if the worker had no proofs)
+    pub final_proof_id: Option<u64>,
+    pub final_tw_start: Option<Timestamp>,
+    pub final_tw_end: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct WorkerEscrowResponse {
+    pub worker_did: String,
+    /// Data owner funding the escrow (`None` if the worker has no escrow)
+    pub owner: Option<String>,
+    pub balance: Uint128,
+}
+
+#[cw_serde]
+pub struct HookSubscriber {
+    pub contract_addr: String,
+    pub events: Vec<HookEvent>,
+}
+
+#[cw_serde]
+pub struct HookSubscribersResponse {
+    pub subscribers: Vec<HookSubscriber>,
+}
+
+#[cw_serde]
+pub struct WorkerAssignmentResponse {
+    pub worker_did: String,
+    /// Node allowed to store the worker's proofs (`None` if any node may)
+    pub node: Option<String>,
+    pub assigned_by: Option<String>,
+    pub assigned_at: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct MissingGatewayData {
+    pub gateway_did: String,
+    pub node: String,
+    pub reporting_interval_seconds: u64,
+    /// End of the gateway's latest batch window (`None` if it never reported)
+    pub last_data_at: Option<Timestamp>,
+    /// Seconds past the expected next report, as of `since`
+    pub overdue_seconds: u64,
+}
+
+#[cw_serde]
+pub struct GatewaysMissingDataResponse {
+    pub gateways: Vec<MissingGatewayData>,
+    /// Last gateway examined when the scan stopped before the end, to pass as `start_after`;
+    /// set even when fewer than `limit` gateways are returned
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct WorkerActivityResponse {
+    pub worker_did: String,
+    pub total_proofs: u64,
+    /// Block times at which the worker's first and last proofs were stored
+    pub first_proof_at: Option<Timestamp>,
+    pub last_proof_at: Option<Timestamp>,
+    /// Earliest start and latest end of the worker's proof windows
+    pub period_start: Option<Timestamp>,
+    pub period_end: Option<Timestamp>,
+    /// Seconds of the period covered by at least one proof window, and the remainder
+    pub covered_seconds: u64,
+    pub gap_seconds: u64,
+    /// Start of the first window left out once `MAX_ACTIVITY_WINDOWS` windows were examined,
+    /// to pass as `from` for the rest of the period
+    pub next_from: Option<Timestamp>,
+    /// Latest proof of the worker, which the next proof extends (`None` if it has no proofs)
+    pub chain_head_proof_id: Option<u64>,
+    pub chain_head_hash: Option<String>,
+}
+
+#[cw_serde]
+pub struct GatewaySequenceResponse {
+    pub gateway_did: String,
+    /// Sequence expected next (`None` if the gateway never submitted a sequenced batch)
+    pub next_sequence: Option<u64>,
+    pub missed_sequences: u64,
+    pub last_gap: Option<SequenceGap>,
+}
+
+#[cw_serde]
+pub struct GatewayProofSummary {
+    pub proof_id: u64,
+    pub tw_start: Timestamp,
+    pub tw_end: Timestamp,
+    pub value_in: Option<Uint128>,
+    pub value_out: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct GatewayProofSummariesResponse {
+    pub summaries: Vec<GatewayProofSummary>,
+}
+
+#[cw_serde]
+pub struct LegacyMappingResponse {
+    pub node_address: String,
+    pub mapping: Option<LegacyMapping>,
+}
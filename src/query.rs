@@ -1,9 +1,12 @@
-use cosmwasm_std::{Deps, StdResult, Order, Uint128};
-use cw_storage_plus::Bound;
+use cosmwasm_std::{Addr, Deps, Env, StdError, StdResult, Order, Timestamp, Uint128};
+use cw_storage_plus::{Bound, Map};
+use std::collections::BTreeMap;
 
-use crate::msg::{ConfigResponse, NodeInfoResponse, ProofResponse, ProofsResponse, WhitelistedResponse, NodeReputationResponse};
-use crate::state::{CONFIG, WHITELISTED_NODES, proofs, GATEWAY_PROOFS, UNLOCKING_DEPOSITS, PROOF_BY_HASH};
-use crate::helpers::get_native_staked_amount;
+use crate::msg::{ApiVersionsResponse, EnergyValues, ProofResponseV2, ProofsResponseV2, DEPRECATED_QUERY_API_VERSIONS, QUERY_API_VERSION, OnboardingStage, OnboardingStatusResponse, OnboardingStep, OwnerShare, ConfigResponse, NodeInfoResponse, ProofResponse, ProofsResponse, WhitelistedResponse, NodeReputationResponse, RegionsResponse, NodeAddressesResponse, PlanResponse, PlansResponse, OwnerQuotaResponse, ProofBondResponse, LegacyMappingResponse, GatewayProofSummary, GatewayProofSummariesResponse, WorkerStatusResponse, WorkerActivityResponse, WorkerAssignmentResponse, WorkerEscrowResponse, HookSubscriber, HookSubscribersResponse, MissingGatewayData, GatewaysMissingDataResponse, GatewaySequenceResponse, VerifyHashResponse, DisputeResponse, DisputesResponse, SimulateSlashResponse, HeldDepositResponse, ProofConflictResponse, ProofConflictsResponse, ProofFlagResponse, FlaggedProofResponse, ProofFlagsResponse, PreRegistrationResponse, AdminAuditEntryResponse, AdminAuditLogResponse, PendingActionResponse, PendingActionsResponse, MigrationPreviewResponse, AuditCommitteeResponse, OwnerDelegatesResponse, OperatorNodesResponse, NodeSummary, NodesResponse, EnergyStatsResponse, UnitEnergyTotal, ProofLookup, ProofsByHashesResponse, Role, RolesResponse};
+use crate::state::{CONFIG, GATEWAY_SEQUENCES, Dispute, DISPUTES, HELD_DEPOSITS, NODE_OPEN_DISPUTES, Node, WHITELISTED_NODES, proofs, GatewayProofEntry, GATEWAY_PROOFS, GATEWAY_TIME_PROOFS, UNLOCKING_DEPOSITS, PROOF_BY_HASH, REVOKED_PROOF_HASHES, Proof, REGIONS, REGION_PROOFS, REGION_NODES, GRID_POINT_PROOFS, HEIGHT_PROOFS, OWNER_PROOFS, OWNER_DELEGATES, PROOF_APPROVALS, PROOF_SCHEMA_VERSION, WORKER_WINDOWS, Plan, PLANS, OWNER_PLANS, QuotaUsage, QUOTA_USAGE, QUOTA_PERIOD_SECONDS, PROOF_BONDS, LEGACY_MAPPINGS, RETIRED_WORKERS, PROOF_CONFLICTS, PROOF_FLAGS, PRE_REGISTRATIONS, REFERRALS, ADMIN_AUDIT_LOG, PENDING_ACTIONS, ROLES, OPERATOR_NODES, OPERATOR_NODE_LIMITS, GATEWAY_CADENCES, WORKER_ASSIGNMENTS, WORKER_ESCROWS, HOOK_SUBSCRIBERS, WORKER_ENERGY, ENERGY_BUCKET_SECONDS, WORKER_PROOF_COUNTS, AUDIT_COMMITTEES};
+use crate::contract::{CONTRACT_VERSION, LEGACY_NODES_NAMESPACE};
+use crate::execute::{DEFAULT_DATA_REFERENCE_SCHEMES, DEFAULT_DID_PREFIXES, add_coins, onboarding_stage, slash_deposit, slash_unlocking, stake_tier, tier_deposit_requirement, tier_reputation_requirement};
+use crate::helpers::{audit_committee_seed, get_native_staked_amount, select_audit_committee};
 
 const DEFAULT_LIMIT: u32 = 10;
 const MAX_LIMIT: u32 = 30;
@@ -18,7 +21,9 @@ pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
     Ok(ConfigResponse {
         admin: config.admin.to_string(),
         proof_count: config.proof_count,
-        min_reputation_threshold: config.min_reputation_threshold,
+        min_reputation_tier1: config.min_reputation_tier1,
+        min_reputation_tier2: config.min_reputation_tier2,
+        min_reputation_tier3: config.min_reputation_tier3,
         treasury: config.treasury.map(|addr| addr.to_string()),
         did_contract_address: config.did_contract_address.to_string(),
         min_stake_tier1: config.min_stake_tier1,
@@ -30,15 +35,53 @@ pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
         use_whitelist: config.use_whitelist,
         deposit_unlock_period_blocks: config.deposit_unlock_period_blocks,
         max_batch_size: config.max_batch_size,
+        facility_registry_address: config.facility_registry_address.map(|addr| addr.to_string()),
+        proof_bond: config.proof_bond,
+        proof_finalization_period_blocks: config.proof_finalization_period_blocks,
+        max_snapshots_per_proof: config.max_snapshots_per_proof,
+        max_batch_metadata_bytes: config.max_batch_metadata_bytes,
+        legacy_store_proof_enabled: config.legacy_store_proof_enabled,
+        deposit_denoms: config.deposit_denoms,
+        tier_capabilities: config.tier_capabilities,
+        reject_conflicting_proofs: config.reject_conflicting_proofs,
+        pre_registration_period_blocks: config.pre_registration_period_blocks,
+        referral_reward: config.referral_reward,
+        referral_pool: config.referral_pool,
+        admin_timelock_blocks: config.admin_timelock_blocks,
+        deposit_topup_grace_blocks: config.deposit_topup_grace_blocks,
+        reputation_bounds: config.reputation_bounds,
+        max_event_attribute_bytes: config.max_event_attribute_bytes,
+        max_sequence_gap: config.max_sequence_gap,
+        dispute_bond: config.dispute_bond,
+        reputation_scoring: config.reputation_scoring,
+        dispute_hold_bps: config.dispute_hold_bps,
+        proof_rewards: config.proof_rewards,
+        reward_pool: config.reward_pool,
+        proof_fee: config.proof_fee,
+        collected_fees: config.collected_fees,
+        alert_thresholds: config.alert_thresholds,
+        paused: config.paused,
+        probation: config.probation,
+        max_nodes_per_operator: config.max_nodes_per_operator,
+        data_reference_schemes: config.data_reference_schemes.clone().unwrap_or_else(|| {
+            DEFAULT_DATA_REFERENCE_SCHEMES.iter().map(|s| s.to_string()).collect()
+        }),
+        max_reputation_delta: config.max_reputation_delta,
+        did_prefixes: config.did_prefixes.clone().unwrap_or_else(|| {
+            DEFAULT_DID_PREFIXES.iter().map(|p| p.to_string()).collect()
+        }),
+        proof_retention_seconds: config.proof_retention_seconds,
+        escrow_price_per_batch: config.escrow_price_per_batch,
+        slash_distribution: config.slash_distribution,
+        carry_over: config.carry_over,
+        auto_jail: config.auto_jail,
+        node_batch_limit: config.node_batch_limit,
     })
 }
 
-/// Query proof by ID (Phase 1b).
-/// Returns detailed information about a specific proof, identified by its unique ID.
-pub fn proof(deps: Deps, id: u64) -> StdResult<ProofResponse> {
-    let proof = proofs().load(deps.storage, id)?;
-    
-    Ok(ProofResponse {
+/// Converts a stored proof into its query response shape.
+fn proof_response(proof: Proof) -> ProofResponse {
+    ProofResponse {
         id: proof.id,
         worker_did: proof.worker_did,
         data_hash: proof.data_hash,
@@ -48,16 +91,99 @@ pub fn proof(deps: Deps, id: u64) -> StdResult<ProofResponse> {
         original_data_reference: proof.original_data_reference,
         metadata_json: proof.metadata_json,
         stored_at: proof.stored_at,
+        stored_height: proof.stored_height,
         stored_by: proof.stored_by.to_string(),
-    })
+        region: proof.region,
+        site: proof.site,
+        data_owner: proof.data_owner.map(|owner| owner.to_string()),
+        owner_shares: proof.owner_shares.into_iter()
+            .map(|s| OwnerShare { owner: s.owner.to_string(), share: s.share })
+            .collect(),
+        value_in: proof.value_in,
+        value_out: proof.value_out,
+        unit: proof.unit,
+        rejected: proof.rejected,
+        under_review: proof.under_review,
+        schema_version: proof.schema_version,
+        status: proof.status,
+        retain_until: proof.retain_until,
+        compacted: proof.compacted,
+    }
+}
+
+/// Query proof by ID (Phase 1b).
+/// Returns detailed information about a specific proof, identified by its unique ID.
+pub fn proof(deps: Deps, id: u64) -> StdResult<ProofResponse> {
+    let proof = proofs().load(deps.storage, id)?;
+    
+    Ok(proof_response(proof))
 }
 
 /// Query proof by data hash.
 /// Returns detailed information about a specific proof, identified by its data hash.
 /// This is useful for verifying the existence and details of a proof when only the hash is known.
+/// Revoked proofs are still found, with their `Revoked` status.
 pub fn proof_by_hash(deps: Deps, data_hash: String) -> StdResult<ProofResponse> {
-    let id = PROOF_BY_HASH.load(deps.storage, &data_hash)?;
-    proof(deps, id)
+    proof(deps, proof_id_by_hash(deps, &data_hash)?)
+}
+
+/// Maximum number of hashes in one `ProofsByHashes` query
+const MAX_HASHES_PER_LOOKUP: usize = 50;
+
+/// Query several proofs by data hash at once. Unknown hashes are reported as missing instead
+/// of failing the query; revoked proofs are found like in `proof_by_hash`.
+pub fn proofs_by_hashes(deps: Deps, data_hashes: Vec<String>) -> StdResult<ProofsByHashesResponse> {
+    if data_hashes.is_empty() || data_hashes.len() > MAX_HASHES_PER_LOOKUP {
+        return Err(StdError::generic_err(format!("Between 1 and {} data hashes can be looked up at once", MAX_HASHES_PER_LOOKUP)));
+    }
+
+    let results = data_hashes
+        .into_iter()
+        .map(|data_hash| {
+            let id = match REVOKED_PROOF_HASHES.may_load(deps.storage, &data_hash)? {
+                Some(id) => Some(id),
+                None => PROOF_BY_HASH.may_load(deps.storage, &data_hash)?,
+            };
+            let proof = id.map(|id| proof(deps, id)).transpose()?;
+            Ok(ProofLookup { data_hash, proof })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ProofsByHashesResponse { results })
+}
+
+/// ID of the proof stored with `data_hash`, revoked or not
+fn proof_id_by_hash(deps: Deps, data_hash: &str) -> StdResult<u64> {
+    match REVOKED_PROOF_HASHES.may_load(deps.storage, data_hash)? {
+        Some(id) => Ok(id),
+        None => PROOF_BY_HASH.load(deps.storage, data_hash),
+    }
+}
+
+/// Query whether a data hash is stored and final, for other contracts.
+/// A proof is final once `proof_finalization_period_blocks` have passed since it was stored,
+/// unless it was rejected (in a conflict ruling or an upheld dispute) or is under review.
+/// Revoked proofs exist but are never final.
+pub fn verify_hash(deps: Deps, env: Env, data_hash: String) -> StdResult<VerifyHashResponse> {
+    if let Some(id) = REVOKED_PROOF_HASHES.may_load(deps.storage, &data_hash)? {
+        return Ok(VerifyHashResponse { exists: true, proof_id: Some(id), finalized: false, revoked: true, owner_approved: false });
+    }
+    let Some(id) = PROOF_BY_HASH.may_load(deps.storage, &data_hash)? else {
+        return Ok(VerifyHashResponse { exists: false, proof_id: None, finalized: false, revoked: false, owner_approved: false });
+    };
+    let proof = proofs().load(deps.storage, id)?;
+    let final_at = proof.stored_height + CONFIG.load(deps.storage)?.proof_finalization_period_blocks;
+    let ownership = proof.ownership();
+    let owner_approved = !ownership.is_empty()
+        && ownership.iter().all(|(owner, _)| PROOF_APPROVALS.has(deps.storage, (owner, id)));
+
+    Ok(VerifyHashResponse {
+        exists: true,
+        proof_id: Some(id),
+        finalized: env.block.height >= final_at && !proof.rejected && !proof.under_review,
+        revoked: false,
+        owner_approved,
+    })
 }
 
 /// Query all proofs with pagination (Phase 1b).
@@ -70,24 +196,13 @@ pub fn query_proofs(
 ) -> StdResult<ProofsResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     
-    let start = start_after.map(|id| Bound::exclusive(id));
+    let start = start_after.map(Bound::exclusive);
     
     let proofs_list = proofs()
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
-            item.map(|(_, proof)| ProofResponse {
-                id: proof.id,
-                worker_did: proof.worker_did,
-                data_hash: proof.data_hash,
-                batch_metadata: proof.batch_metadata,
-                original_data_reference: proof.original_data_reference,
-                metadata_json: proof.metadata_json,
-                stored_at: proof.stored_at,
-                stored_by: proof.stored_by.to_string(),
-                tw_start: proof.tw_start,
-                tw_end: proof.tw_end,
-            })
+            item.map(|(_, proof)| proof_response(proof))
         })
         .collect::<StdResult<Vec<_>>>()?;
     
@@ -103,7 +218,7 @@ pub fn query_proofs_by_worker(
     limit: Option<u32>,
 ) -> StdResult<ProofsResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(|id| Bound::exclusive(id));
+    let start = start_after.map(Bound::exclusive);
     
     let proofs_list = proofs()
         .idx
@@ -112,62 +227,475 @@ pub fn query_proofs_by_worker(
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
-            item.map(|(_, proof)| ProofResponse {
-                id: proof.id,
-                worker_did: proof.worker_did,
-                data_hash: proof.data_hash,
-                batch_metadata: proof.batch_metadata,
-                original_data_reference: proof.original_data_reference,
-                metadata_json: proof.metadata_json,
-                stored_at: proof.stored_at,
-                stored_by: proof.stored_by.to_string(),
-                tw_start: proof.tw_start,
-                tw_end: proof.tw_end,
-            })
+            item.map(|(_, proof)| proof_response(proof))
         })
         .collect::<StdResult<Vec<_>>>()?;
     
     Ok(ProofsResponse { proofs: proofs_list })
 }
 
-/// Query proofs by gateway DID with pagination (Phase 1b).
-/// Uses manual GATEWAY_PROOFS index for efficient gateway_did lookups.
-pub fn query_proofs_by_gateway(
+/// Query a worker's energy totals per unit from the daily `WORKER_ENERGY` buckets, so no proofs
+/// are read. `from` and `to` select buckets and are rounded down to their day.
+pub fn energy_stats(
     deps: Deps,
-    gateway_did: String,
+    worker_did: String,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+) -> StdResult<EnergyStatsResponse> {
+    let bucket = |t: Timestamp| t.seconds() / ENERGY_BUCKET_SECONDS;
+    let min = from.map(|t| Bound::inclusive((bucket(t), "")));
+    let max = to.map(|t| Bound::exclusive((bucket(t), "")));
+
+    let mut totals: BTreeMap<String, UnitEnergyTotal> = BTreeMap::new();
+    for item in WORKER_ENERGY.sub_prefix(&worker_did).range(deps.storage, min, max, Order::Ascending) {
+        let ((_, unit), energy) = item?;
+        let total = totals.entry(unit.clone()).or_insert_with(|| UnitEnergyTotal {
+            unit,
+            value_in: Uint128::zero(),
+            value_out: Uint128::zero(),
+            proof_count: 0,
+        });
+        total.value_in += energy.value_in;
+        total.value_out += energy.value_out;
+        total.proof_count += energy.proofs;
+    }
+
+    Ok(EnergyStatsResponse { worker_did, totals: totals.into_values().collect() })
+}
+
+/// Query proofs stored by `node_address` with pagination, through the `node` index. Proofs of
+/// a removed node stay listed.
+pub fn query_proofs_by_node(
+    deps: Deps,
+    node_address: String,
     start_after: Option<u64>,
     limit: Option<u32>,
 ) -> StdResult<ProofsResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(|id| Bound::exclusive(id));
-    
-    let proof_ids: Vec<u64> = GATEWAY_PROOFS
-        .prefix(&gateway_did)
+    let start = start_after.map(Bound::exclusive);
+    let node = deps.api.addr_validate(&node_address)?;
+
+    let proofs_list = proofs()
+        .idx
+        .node
+        .prefix(node.to_string())
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
-        .map(|item| item.map(|(id, _)| id))
+        .map(|item| item.map(|(_, proof)| proof_response(proof)))
         .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ProofsResponse { proofs: proofs_list })
+}
+
+/// Query proofs whose `tw_start` falls in `[start, end)` through the `tw_start` index, ordered
+/// by `tw_start` then ID. `start_after` is resolved to its `(tw_start, id)` position.
+pub fn query_proofs_by_time_range(
+    deps: Deps,
+    start: Timestamp,
+    end: Timestamp,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProofsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let lower = (start.nanos(), 0u64);
+    let cursor = match start_after {
+        Some(id) => Some((proofs().load(deps.storage, id)?.tw_start.nanos(), id)),
+        None => None,
+    };
+    let min = match cursor {
+        Some(cursor) if cursor >= lower => Bound::exclusive(cursor),
+        _ => Bound::inclusive(lower),
+    };
+    let max = Bound::exclusive((end.nanos(), 0u64));
+
+    let proofs_list = proofs()
+        .idx
+        .tw_start
+        .range(deps.storage, Some(min), Some(max), Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, proof)| proof_response(proof)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ProofsResponse { proofs: proofs_list })
+}
+
+/// Query proofs by gateway DID with pagination (Phase 1b).
+/// See [`gateway_proof_entries`] for the index used and the result order.
+pub fn query_proofs_by_gateway(
+    deps: Deps,
+    gateway_did: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+) -> StdResult<ProofsResponse> {
+    let entries = gateway_proof_entries(deps, &gateway_did, start_after, limit, from, to)?;
     
-    let mut proofs_list = Vec::with_capacity(proof_ids.len());
-    for id in proof_ids {
+    let mut proofs_list = Vec::with_capacity(entries.len());
+    for (id, _) in entries {
         let proof = proofs().load(deps.storage, id)?;
-        proofs_list.push(ProofResponse {
-            id: proof.id,
-            worker_did: proof.worker_did,
-            data_hash: proof.data_hash,
-            batch_metadata: proof.batch_metadata,
-            original_data_reference: proof.original_data_reference,
-            metadata_json: proof.metadata_json,
-            stored_at: proof.stored_at,
-            stored_by: proof.stored_by.to_string(),
-            tw_start: proof.tw_start,
-            tw_end: proof.tw_end,
-        });
+        proofs_list.push(proof_response(proof));
     }
     
     Ok(ProofsResponse { proofs: proofs_list })
 }
 
+/// Query per-gateway proof summaries straight from the GATEWAY_PROOFS index values.
+pub fn gateway_proof_summaries(
+    deps: Deps,
+    gateway_did: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+) -> StdResult<GatewayProofSummariesResponse> {
+    let summaries = gateway_proof_entries(deps, &gateway_did, start_after, limit, from, to)?
+        .into_iter()
+        .map(|(proof_id, entry)| GatewayProofSummary {
+            proof_id,
+            tw_start: entry.tw_start,
+            tw_end: entry.tw_end,
+            value_in: entry.value_in,
+            value_out: entry.value_out,
+        })
+        .collect();
+
+    Ok(GatewayProofSummariesResponse { summaries })
+}
+
+/// Pages through a gateway's index entries. Without a window, GATEWAY_PROOFS is ranged in ID
+/// order. With `from`/`to`, GATEWAY_TIME_PROOFS is ranged instead so only proofs with
+/// `tw_start` in `[from, to)` are read, ordered by `tw_start` then ID; `start_after` is then
+/// resolved to its `(tw_start, id)` position through the gateway entry.
+fn gateway_proof_entries(
+    deps: Deps,
+    gateway_did: &str,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+) -> StdResult<Vec<(u64, GatewayProofEntry)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    if from.is_none() && to.is_none() {
+        let start = start_after.map(Bound::exclusive);
+        return GATEWAY_PROOFS
+            .prefix(gateway_did)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect();
+    }
+
+    let lower = (from.map_or(0, |t| t.nanos()), 0u64);
+    let cursor = match start_after {
+        Some(id) => Some((GATEWAY_PROOFS.load(deps.storage, (gateway_did, id))?.tw_start.nanos(), id)),
+        None => None,
+    };
+    let min = match cursor {
+        Some(cursor) if cursor >= lower => Bound::exclusive(cursor),
+        _ => Bound::inclusive(lower),
+    };
+    let max = to.map(|t| Bound::exclusive((t.nanos(), 0u64)));
+    GATEWAY_TIME_PROOFS
+        .sub_prefix(gateway_did)
+        .keys(deps.storage, Some(min), max, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, id) = item?;
+            Ok((id, GATEWAY_PROOFS.load(deps.storage, (gateway_did, id))?))
+        })
+        .collect()
+}
+
+/// Query the admin-managed list of accepted region codes.
+pub fn regions(deps: Deps) -> StdResult<RegionsResponse> {
+    let regions = REGIONS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(RegionsResponse { regions })
+}
+
+/// Query proofs by region with pagination.
+/// Uses the manual REGION_PROOFS index, so only proofs stored with a region are returned.
+pub fn query_proofs_by_region(
+    deps: Deps,
+    region: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProofsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let proof_ids: Vec<u64> = REGION_PROOFS
+        .prefix(&region)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let proofs_list = proof_ids
+        .into_iter()
+        .map(|id| proofs().load(deps.storage, id).map(proof_response))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ProofsResponse { proofs: proofs_list })
+}
+
+/// Query proofs attributed to an owner with pagination.
+/// Uses the manual OWNER_PROOFS index, which covers both sole data owners and co-owners.
+pub fn query_proofs_by_owner(
+    deps: Deps,
+    owner: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProofsResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let proof_ids: Vec<u64> = OWNER_PROOFS
+        .prefix(&owner)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let proofs_list = proof_ids
+        .into_iter()
+        .map(|id| proofs().load(deps.storage, id).map(proof_response))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ProofsResponse { proofs: proofs_list })
+}
+
+/// Query proofs attributed to an owner that the owner has not approved, with pagination.
+/// Walks the owner's OWNER_PROOFS entries and skips those with a PROOF_APPROVALS entry.
+pub fn query_unapproved_proofs(
+    deps: Deps,
+    data_owner: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProofsResponse> {
+    let owner = deps.api.addr_validate(&data_owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let proof_ids: Vec<u64> = OWNER_PROOFS
+        .prefix(&owner)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .filter(|id| id.as_ref().map_or(true, |id| !PROOF_APPROVALS.has(deps.storage, (&owner, *id))))
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let proofs_list = proof_ids
+        .into_iter()
+        .map(|id| proofs().load(deps.storage, id).map(proof_response))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ProofsResponse { proofs: proofs_list })
+}
+
+/// Query proofs stored at or after block `height`, with pagination by proof ID.
+/// The HEIGHT_PROOFS index locates the first proof at or after `height`; since IDs are
+/// assigned in block order, every later ID was stored at or after it as well.
+pub fn query_proofs_since_height(
+    deps: Deps,
+    height: u64,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProofsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let first_id = HEIGHT_PROOFS
+        .keys(deps.storage, Some(Bound::inclusive((height, 0))), None, Order::Ascending)
+        .next()
+        .transpose()?
+        .map(|(_, id)| id);
+    let Some(first_id) = first_id else {
+        return Ok(ProofsResponse { proofs: vec![] });
+    };
+    let start = match start_after {
+        Some(id) if id >= first_id => Bound::exclusive(id),
+        _ => Bound::inclusive(first_id),
+    };
+
+    let proofs_list = proofs()
+        .range(deps.storage, Some(start), None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, proof)| proof_response(proof)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ProofsResponse { proofs: proofs_list })
+}
+
+/// Query proofs by the grid connection point of their site, with pagination.
+/// Uses the manual GRID_POINT_PROOFS index.
+pub fn query_proofs_by_grid_connection_point(
+    deps: Deps,
+    grid_connection_point: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProofsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let proof_ids: Vec<u64> = GRID_POINT_PROOFS
+        .prefix(&grid_connection_point)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let proofs_list = proof_ids
+        .into_iter()
+        .map(|id| proofs().load(deps.storage, id).map(proof_response))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ProofsResponse { proofs: proofs_list })
+}
+
+fn plan_response(plan_id: String, plan: Plan) -> PlanResponse {
+    PlanResponse {
+        plan_id,
+        proofs_per_period: plan.proofs_per_period,
+        max_metadata_bytes: plan.max_metadata_bytes,
+        price: plan.price,
+        retention_seconds: plan.retention_seconds,
+    }
+}
+
+/// Query all data-owner subscription plans.
+pub fn plans(deps: Deps) -> StdResult<PlansResponse> {
+    let plans = PLANS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(plan_id, plan)| plan_response(plan_id, plan)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PlansResponse { plans })
+}
+
+/// Query a data owner's plan and usage in the current 30-day quota period.
+pub fn owner_quota(deps: Deps, env: Env, data_owner: String) -> StdResult<OwnerQuotaResponse> {
+    let owner = deps.api.addr_validate(&data_owner)?;
+    let period = env.block.time.seconds() / QUOTA_PERIOD_SECONDS;
+
+    let owner_plan = OWNER_PLANS.may_load(deps.storage, &owner)?;
+    let plan = match &owner_plan {
+        Some(owner_plan) => PLANS
+            .may_load(deps.storage, &owner_plan.plan_id)?
+            .map(|plan| plan_response(owner_plan.plan_id.clone(), plan)),
+        None => None,
+    };
+    let expires_at = owner_plan.and_then(|p| p.expires_at);
+    let active = plan.is_some() && expires_at.is_none_or(|expiry| expiry > env.block.time);
+
+    let usage = match (QUOTA_USAGE.may_load(deps.storage, &owner)?, &plan) {
+        (Some(usage), _) if usage.period == period => usage,
+        (previous, plan) => {
+            let allowance = plan.as_ref().map_or(0, |plan| plan.proofs_per_period);
+            let max_carry_over = CONFIG.load(deps.storage)?.carry_over.max_quota_carry_over;
+            QuotaUsage::start(period, previous.as_ref(), allowance, max_carry_over)
+        }
+    };
+    let proofs_remaining = match (&plan, active) {
+        (Some(plan), true) => (plan.proofs_per_period + usage.carried_over).saturating_sub(usage.proofs_used),
+        _ => 0,
+    };
+
+    Ok(OwnerQuotaResponse {
+        data_owner: owner.to_string(),
+        plan,
+        expires_at,
+        active,
+        period,
+        proofs_used: usage.proofs_used,
+        carried_over: usage.carried_over,
+        proofs_remaining,
+    })
+}
+
+/// Query node addresses by region with pagination.
+/// `start_after` is a node address; results are ordered by address.
+pub fn query_nodes_by_region(
+    deps: Deps,
+    region: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<NodeAddressesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_addr = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+    let start = start_addr.as_ref().map(Bound::exclusive);
+
+    let addresses = REGION_NODES
+        .prefix(&region)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|addr| addr.to_string()))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(NodeAddressesResponse { addresses })
+}
+
+/// Query registered nodes ordered by address. `tier` and `min_reputation` filter the nodes;
+/// `limit` counts the nodes returned, so a page may scan past filtered-out nodes.
+pub fn query_nodes(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    tier: Option<u8>,
+    min_reputation: Option<i32>,
+) -> StdResult<NodesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let nodes = WHITELISTED_NODES
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| {
+            item.as_ref().map_or(true, |(_, node)| {
+                tier.is_none_or(|tier| node.tier == tier) && min_reputation.is_none_or(|min| node.reputation >= min)
+            })
+        })
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, node)| NodeSummary {
+                address,
+                tier: node.tier,
+                reputation: node.reputation,
+                deposit: node.deposit,
+                proof_count: node.proof_count,
+                disputed_proofs: node.disputed_proofs,
+                added_at: node.added_at,
+                region: node.region,
+                operator_did: node.operator_did,
+                paused: node.paused,
+                jailed_until: node.jailed_until,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(NodesResponse { nodes })
+}
+
+/// Query addresses of nodes referred by `referrer`, ordered by address.
+pub fn query_nodes_by_referrer(
+    deps: Deps,
+    referrer: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<NodeAddressesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let referrer = deps.api.addr_validate(&referrer)?;
+    let start_addr = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+    let start = start_addr.as_ref().map(Bound::exclusive);
+
+    let addresses = REFERRALS
+        .prefix(&referrer)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|addr| addr.to_string()))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(NodeAddressesResponse { addresses })
+}
+
 /// Query if an address is a whitelisted (or registered) node.
 /// Returns true if the address is present in the `WHITELISTED_NODES` map, false otherwise.
 /// Note: `WHITELISTED_NODES` now serves as the central registry for all active nodes.
@@ -201,15 +729,58 @@ pub fn node_reputation(deps: Deps, address: String) -> StdResult<NodeReputationR
 /// Query comprehensive node information.
 /// Returns detailed information about a node, including its reputation, and when it was added (registered).
 /// Unlocking deposit information is also included if available.
+/// Reports the recorded onboarding stage, advanced to what the node's record and current stake
+/// meet, and the requirements left. Unknown addresses get the steps of a fresh registration.
+pub fn onboarding_status(deps: Deps, address: String) -> StdResult<OnboardingStatusResponse> {
+    let validated_address = deps.api.addr_validate(&address)?;
+    let config = CONFIG.load(deps.storage)?;
+    let node = WHITELISTED_NODES.may_load(deps.storage, validated_address.to_string())?;
+    let (tier, deposit, reputation) = node.as_ref().map_or((0, Uint128::zero(), 0), |n| (n.tier, n.deposit, n.reputation));
+
+    // Only nodes without a tier still depend on their stake
+    let staked = if tier == 0 {
+        get_native_staked_amount(&deps.querier, &validated_address).map_err(|e| StdError::generic_err(e.to_string()))?
+    } else {
+        Uint128::zero()
+    };
+    let stage = node.as_ref().map(|n| n.onboarding_stage.clone().max(onboarding_stage(&config, n, staked)));
+    let mut remaining_steps = vec![];
+    if stage != Some(OnboardingStage::Operational) {
+        let target_tier = if tier == 0 {
+            let tier = stake_tier(&config, staked);
+            if tier == 0 {
+                remaining_steps.push(OnboardingStep::Stake { required: config.min_stake_tier1, staked });
+            }
+            tier
+        } else {
+            tier
+        };
+        let required_deposit = tier_deposit_requirement(&config, target_tier);
+        if deposit < required_deposit {
+            remaining_steps.push(OnboardingStep::Deposit { amount: required_deposit - deposit });
+        }
+        let required_reputation = tier_reputation_requirement(&config, target_tier);
+        if reputation < required_reputation {
+            remaining_steps.push(OnboardingStep::Reputation { required: required_reputation, reputation });
+        }
+    }
+
+    Ok(OnboardingStatusResponse { address: validated_address.to_string(), stage, remaining_steps })
+}
+
 pub fn node_info(deps: Deps, node_address: String) -> StdResult<NodeInfoResponse> {
     //let config = CONFIG.load(deps.storage)?;
     let validated_address = deps.api.addr_validate(&node_address)?;
 
     // Check for unlocking deposit information
     let unlocking_info = UNLOCKING_DEPOSITS.may_load(deps.storage, validated_address.to_string())?;
-    let (unlocking_deposit_amount, unlocking_deposit_release_at_block) = match unlocking_info {
-        Some(unlocking_deposit) => (Some(unlocking_deposit.amount), Some(unlocking_deposit.release_at_block)),
-        None => (None, None),
+    let (unlocking_deposit_amount, unlocking_deposit_release_at_block, unlocking_deposit_coins) = match unlocking_info {
+        Some(unlocking_deposit) => (
+            Some(unlocking_deposit.amount),
+            Some(unlocking_deposit.release_at_block),
+            Some(unlocking_deposit.coins),
+        ),
+        None => (None, None, None),
     };
 
     match WHITELISTED_NODES.may_load(deps.storage, node_address.clone())? {
@@ -221,6 +792,7 @@ pub fn node_info(deps: Deps, node_address: String) -> StdResult<NodeInfoResponse
             // Use the stored tier instead of recalculating it
             // The tier was determined at registration time based on stake requirements
             let current_tier = node.tier;
+            let pending_attestations = node.pending_attestations();
 
             Ok(NodeInfoResponse {
                 address: node.address.to_string(),
@@ -228,6 +800,7 @@ pub fn node_info(deps: Deps, node_address: String) -> StdResult<NodeInfoResponse
                 reputation: node.reputation,
                 added_at: Some(node.added_at),
                 deposit: Some(node.deposit), // This is the active, locked deposit
+                deposit_coins: Some(node.deposit_coins),
                 native_staked_amount: Some(native_staked_amount),
                 tier: Some(current_tier), // Use the stored tier
                 last_updated: Some(node.last_updated),
@@ -235,6 +808,18 @@ pub fn node_info(deps: Deps, node_address: String) -> StdResult<NodeInfoResponse
                 disputed_proofs: Some(node.disputed_proofs),
                 unlocking_deposit_amount, // Added
                 unlocking_deposit_release_at_block, // Added
+                unlocking_deposit_coins,
+                region: node.region,
+                referrer: node.referrer.map(|r| r.to_string()),
+                referral_count: Some(node.referral_count),
+                topup_deadline_block: node.topup_deadline_block,
+                paused: Some(node.paused),
+                accrued_rewards: Some(node.accrued_rewards),
+                escrow_earnings: Some(node.escrow_earnings),
+                probation_until_block: node.probation_until_block,
+                pending_attestations: Some(pending_attestations),
+                operator_did: node.operator_did,
+                jailed_until: node.jailed_until,
             })
         }
         None => Ok(NodeInfoResponse {
@@ -243,6 +828,7 @@ pub fn node_info(deps: Deps, node_address: String) -> StdResult<NodeInfoResponse
             reputation: 0, // Default reputation for non-existent node
             added_at: None,
             deposit: None,
+            deposit_coins: None,
             native_staked_amount: None,
             tier: None,
             last_updated: None,
@@ -250,6 +836,18 @@ pub fn node_info(deps: Deps, node_address: String) -> StdResult<NodeInfoResponse
             disputed_proofs: None,
             unlocking_deposit_amount, // Still include this, could be Some if node was removed but deposit is unlocking
             unlocking_deposit_release_at_block, // Same as above
+            unlocking_deposit_coins,
+            region: None,
+            referrer: None,
+            referral_count: None,
+            topup_deadline_block: None,
+            paused: None,
+            accrued_rewards: None,
+            escrow_earnings: None,
+            probation_until_block: None,
+            pending_attestations: None,
+            operator_did: None,
+            jailed_until: None,
         }),
     }
 }
@@ -257,4 +855,658 @@ pub fn node_info(deps: Deps, node_address: String) -> StdResult<NodeInfoResponse
 // TODO: Implement GetStakedAmount query as per HLD.
 // This query would likely take a node address and return their natively staked C4E amount
 // by querying the chain\'s staking module, similar to `get_native_staked_amount` in `execute.rs`.
-// pub fn get_staked_amount(deps: Deps, node_address: String) -> StdResult<StakedAmountResponse> { ... }
\ No newline at end of file
+// pub fn get_staked_amount(deps: Deps, node_address: String) -> StdResult<StakedAmountResponse> { ... }
+
+/// Previews `ResolveDispute` slashing `bps` basis points of a node's deposit: the coins removed
+/// from its active and unlocking deposits, the resulting deposit against the tier requirement,
+/// and what the treasury would receive.
+pub fn simulate_slash(deps: Deps, node_address: String, bps: u16) -> StdResult<SimulateSlashResponse> {
+    if bps == 0 || bps > 10_000 {
+        return Err(StdError::generic_err("bps must be between 1 and 10000"));
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let mut node = WHITELISTED_NODES.load(deps.storage, node_address.clone())?;
+    let slashed_coins = slash_deposit(&mut node, bps);
+    let slashed_unlocking_coins = match UNLOCKING_DEPOSITS.may_load(deps.storage, node_address.clone())? {
+        Some(mut unlocking) => slash_unlocking(&mut unlocking, bps).1,
+        None => vec![],
+    };
+    let required_deposit = tier_deposit_requirement(&config, node.tier);
+    let mut treasury_amount = vec![];
+    if config.treasury.is_some() {
+        treasury_amount = slashed_coins.clone();
+        add_coins(&mut treasury_amount, slashed_unlocking_coins.clone());
+    }
+
+    Ok(SimulateSlashResponse {
+        node_address,
+        treasury_amount,
+        slashed_coins,
+        slashed_unlocking_coins,
+        resulting_deposit: node.deposit,
+        required_deposit,
+        below_tier_requirement: node.tier != 0 && node.deposit < required_deposit,
+        treasury: config.treasury.map(|addr| addr.to_string()),
+    })
+}
+
+/// Query the legacy DID mapping of a node.
+pub fn legacy_mapping(deps: Deps, node_address: String) -> StdResult<LegacyMappingResponse> {
+    let addr = deps.api.addr_validate(&node_address)?;
+    let mapping = LEGACY_MAPPINGS.may_load(deps.storage, &addr)?;
+
+    Ok(LegacyMappingResponse { node_address: addr.to_string(), mapping })
+}
+
+/// Query flagged proof conflicts, ordered by conflicting proof ID, with pagination.
+pub fn proof_conflicts(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProofConflictsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let conflicts = PROOF_CONFLICTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (conflicting_proof_id, conflict) = item?;
+            let proof = proofs().load(deps.storage, conflicting_proof_id)?;
+            Ok(ProofConflictResponse {
+                conflicting_proof_id,
+                original_proof_id: conflict.original_proof_id,
+                worker_did: proof.worker_did,
+                tw_start: proof.tw_start,
+                tw_end: proof.tw_end,
+                detected_at: conflict.detected_at,
+                canonical_proof_id: conflict.resolution.as_ref().map(|r| r.canonical_proof_id),
+                resolved_at: conflict.resolution.map(|r| r.resolved_at),
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ProofConflictsResponse { conflicts })
+}
+
+/// Converts a stored dispute into its query response shape.
+fn dispute_response(dispute_id: u64, dispute: Dispute) -> DisputeResponse {
+    DisputeResponse {
+        dispute_id,
+        proof_id: dispute.proof_id,
+        raised_by: dispute.raised_by.to_string(),
+        evidence_uri: dispute.evidence_uri,
+        bond: dispute.bond,
+        status: dispute.status,
+        raised_at: dispute.raised_at,
+        resolved_at: dispute.resolved_at,
+    }
+}
+
+/// Query a dispute by ID.
+pub fn dispute(deps: Deps, dispute_id: u64) -> StdResult<DisputeResponse> {
+    Ok(dispute_response(dispute_id, DISPUTES.load(deps.storage, dispute_id)?))
+}
+
+/// Query disputes ordered by ID with pagination.
+pub fn disputes(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<DisputesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let disputes = DISPUTES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(id, dispute)| dispute_response(id, dispute)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(DisputesResponse { disputes })
+}
+
+/// Query the deposit portion held for a removed node's open disputes.
+pub fn held_deposit(deps: Deps, node_address: String) -> StdResult<HeldDepositResponse> {
+    let address = deps.api.addr_validate(&node_address)?;
+    let held = HELD_DEPOSITS.may_load(deps.storage, &address)?;
+    let open_disputes = NODE_OPEN_DISPUTES
+        .prefix(&address)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count() as u64;
+
+    Ok(HeldDepositResponse {
+        node_address,
+        amount: held.as_ref().map_or(Uint128::zero(), |h| h.amount),
+        coins: held.as_ref().map_or(vec![], |h| h.coins.clone()),
+        held_at: held.map(|h| h.held_at),
+        open_disputes,
+    })
+}
+
+/// Query proofs flagged by their owners with pagination, including every flag.
+pub fn proof_flags(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProofFlagsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let proofs = PROOF_FLAGS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (proof_id, flags) = item?;
+            Ok(FlaggedProofResponse {
+                proof_id,
+                flags: flags
+                    .into_iter()
+                    .map(|f| ProofFlagResponse { flagged_by: f.flagged_by.to_string(), reason: f.reason, flagged_at: f.flagged_at })
+                    .collect(),
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ProofFlagsResponse { proofs })
+}
+
+/// Dry run of `migrate`: counts the records it would transform and lists the ones it could
+/// not migrate (unreadable records, or proofs written by a newer layout). Mirrors the steps
+/// of `contract::migrate`, which must be kept in sync with it.
+pub fn migration_preview(deps: Deps, target_version: String) -> StdResult<MigrationPreviewResponse> {
+    let mut unmigratable = Vec::new();
+    let mut flag = |description: String| unmigratable.push(description);
+
+    let mut proofs_to_upgrade = 0u64;
+    let mut index_entries_to_add = 0u64;
+    let proof_ids = proofs().keys(deps.storage, None, None, Order::Ascending).collect::<StdResult<Vec<_>>>()?;
+    for id in proof_ids {
+        let proof = match proofs().load(deps.storage, id) {
+            Ok(proof) => proof,
+            Err(err) => {
+                flag(format!("proof {}: {}", id, err));
+                continue;
+            }
+        };
+        if proof.schema_version > PROOF_SCHEMA_VERSION {
+            flag(format!("proof {}: written with newer schema version {}", id, proof.schema_version));
+        } else if proof.schema_version < PROOF_SCHEMA_VERSION {
+            proofs_to_upgrade += 1;
+        }
+        for batch in &proof.batch_metadata {
+            if !GATEWAY_TIME_PROOFS.has(deps.storage, (&batch.gateway_did, proof.tw_start.nanos(), id)) {
+                index_entries_to_add += 1;
+            }
+        }
+        if !WORKER_WINDOWS.has(deps.storage, (&proof.worker_did, proof.tw_start.nanos(), proof.tw_end.nanos())) {
+            index_entries_to_add += 1;
+        }
+        for (owner, _) in proof.ownership() {
+            if !OWNER_PROOFS.has(deps.storage, (&owner, id)) {
+                index_entries_to_add += 1;
+            }
+        }
+        // Height index entries of proofs on older layouts are part of their upgrade
+        if proof.schema_version == PROOF_SCHEMA_VERSION && !HEIGHT_PROOFS.has(deps.storage, (proof.stored_height, id)) {
+            index_entries_to_add += 1;
+        }
+    }
+
+    // Old entries stored `()`, which reads back as `None`
+    let legacy_gateway_index: Map<(&str, u64), Option<GatewayProofEntry>> = Map::new("gateway_proofs");
+    let mut gateway_entries_to_rewrite = 0u64;
+    for item in legacy_gateway_index.range(deps.storage, None, None, Order::Ascending) {
+        match item {
+            Ok((_, None)) => gateway_entries_to_rewrite += 1,
+            Ok(_) => {}
+            Err(err) => flag(format!("gateway index entry: {}", err)),
+        }
+    }
+
+    let legacy_nodes: Map<&Addr, Node> = Map::new(LEGACY_NODES_NAMESPACE);
+    let mut legacy_nodes_to_merge = 0u64;
+    for item in legacy_nodes.range(deps.storage, None, None, Order::Ascending) {
+        match item {
+            Ok(_) => legacy_nodes_to_merge += 1,
+            Err(err) => flag(format!("legacy node entry: {}", err)),
+        }
+    }
+
+    let mut deposits_to_backfill = 0u64;
+    let node_keys = WHITELISTED_NODES.keys(deps.storage, None, None, Order::Ascending).collect::<StdResult<Vec<_>>>()?;
+    for address in node_keys {
+        match WHITELISTED_NODES.load(deps.storage, address.clone()) {
+            Ok(node) if node.deposit_coins.is_empty() && !node.deposit.is_zero() => deposits_to_backfill += 1,
+            Ok(_) => {}
+            Err(err) => flag(format!("node {}: {}", address, err)),
+        }
+    }
+    let unlocking_keys = UNLOCKING_DEPOSITS.keys(deps.storage, None, None, Order::Ascending).collect::<StdResult<Vec<_>>>()?;
+    for address in unlocking_keys {
+        match UNLOCKING_DEPOSITS.load(deps.storage, address.clone()) {
+            Ok(entry) if entry.coins.is_empty() && !entry.amount.is_zero() => deposits_to_backfill += 1,
+            Ok(_) => {}
+            Err(err) => flag(format!("unlocking deposit {}: {}", address, err)),
+        }
+    }
+
+    let unmigratable_count = unmigratable.len() as u64;
+    unmigratable.truncate(MAX_LIMIT as usize);
+
+    Ok(MigrationPreviewResponse {
+        stored_version: cw2::get_contract_version(deps.storage)?.version,
+        code_version: CONTRACT_VERSION.to_string(),
+        target_supported: target_version == CONTRACT_VERSION,
+        proofs_to_upgrade,
+        gateway_entries_to_rewrite,
+        index_entries_to_add,
+        deposits_to_backfill,
+        legacy_nodes_to_merge,
+        unmigratable_count,
+        unmigratable,
+    })
+}
+
+/// Query the admin audit log of timelocked actions, in the order the entries were recorded.
+pub fn admin_audit_log(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<AdminAuditLogResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let entries = ADMIN_AUDIT_LOG
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (entry_id, entry) = item?;
+            Ok(AdminAuditEntryResponse {
+                entry_id,
+                action_id: entry.action_id,
+                action: entry.action,
+                event: entry.event,
+                admin: entry.admin.to_string(),
+                block_height: entry.block_height,
+                time: entry.time,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AdminAuditLogResponse { entries })
+}
+
+/// Query the timelocked actions awaiting `ExecutePendingAction`, by action ID.
+pub fn pending_actions(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PendingActionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let actions = PENDING_ACTIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (action_id, pending) = item?;
+            Ok(PendingActionResponse {
+                action_id,
+                action: pending.action,
+                scheduled_by: pending.scheduled_by.to_string(),
+                scheduled_at_block: pending.scheduled_at_block,
+                executable_at_block: pending.executable_at_block,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PendingActionsResponse { actions })
+}
+
+/// Query the audit committee of a proof, selected by `select_audit_committee` when the proof
+/// was stored and kept in `AUDIT_COMMITTEES`, so off-chain nodes can re-derive it from the
+/// registry as it was then.
+///
+/// Pausing, jailing, a tier or reputation change or removal from the registry does not
+/// reshuffle the committee; callers check whether a member can currently act. Proofs stored
+/// before committees were saved have theirs selected from the current registry on each query,
+/// which reads every registered node.
+pub fn audit_committee(deps: Deps, proof_id: u64) -> StdResult<AuditCommitteeResponse> {
+    let proof = proofs().load(deps.storage, proof_id)?;
+    let members = match AUDIT_COMMITTEES.may_load(deps.storage, proof_id)? {
+        Some(members) => members,
+        None => select_audit_committee(deps.storage, &proof)?,
+    };
+
+    Ok(AuditCommitteeResponse {
+        proof_id,
+        seed: audit_committee_seed(&proof),
+        members: members.into_iter().map(|member| member.to_string()).collect(),
+    })
+}
+
+/// Query the delegates of a data owner.
+pub fn owner_delegates(deps: Deps, owner: String) -> StdResult<OwnerDelegatesResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let delegates = OWNER_DELEGATES
+        .prefix(&owner)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|delegate| delegate.map(|d| d.to_string()))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(OwnerDelegatesResponse { owner: owner.to_string(), delegates })
+}
+
+/// Query the registered nodes of an operator DID and the node limit that applies to it.
+pub fn operator_nodes(deps: Deps, operator_did: String) -> StdResult<OperatorNodesResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let nodes = OPERATOR_NODES
+        .prefix(&operator_did)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|address| address.map(|a| a.to_string()))
+        .collect::<StdResult<Vec<_>>>()?;
+    let overridden = OPERATOR_NODE_LIMITS.may_load(deps.storage, &operator_did)?;
+
+    Ok(OperatorNodesResponse {
+        operator_did,
+        nodes,
+        limit: overridden.or(config.max_nodes_per_operator),
+        limit_overridden: overridden.is_some(),
+    })
+}
+
+/// Query the roles granted to an address.
+pub fn roles(deps: Deps, address: String) -> StdResult<RolesResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    let roles = Role::ALL
+        .into_iter()
+        .filter(|role| ROLES.has(deps.storage, (role.key(), &addr)))
+        .collect();
+
+    Ok(RolesResponse {
+        is_admin: addr == config.admin,
+        address: addr.to_string(),
+        roles,
+    })
+}
+
+/// Query the open pre-registration escrow of an operator.
+pub fn pre_registration(deps: Deps, address: String) -> StdResult<PreRegistrationResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let escrow = PRE_REGISTRATIONS.may_load(deps.storage, &addr)?;
+
+    Ok(PreRegistrationResponse {
+        address: addr.to_string(),
+        value: escrow.as_ref().map(|e| e.value),
+        coins: escrow.as_ref().map_or_else(Vec::new, |e| e.coins.clone()),
+        expires_at_block: escrow.map(|e| e.expires_at_block),
+    })
+}
+
+/// Query the decommissioning status of a worker DID.
+pub fn worker_status(deps: Deps, worker_did: String) -> StdResult<WorkerStatusResponse> {
+    let retirement = RETIRED_WORKERS.may_load(deps.storage, &worker_did)?;
+
+    Ok(WorkerStatusResponse {
+        worker_did,
+        retired: retirement.is_some(),
+        retired_at: retirement.as_ref().map(|r| r.retired_at),
+        retired_by: retirement.as_ref().map(|r| r.retired_by.to_string()),
+        final_proof_id: retirement.as_ref().and_then(|r| r.final_proof_id),
+        final_tw_start: retirement.as_ref().and_then(|r| r.final_tw_start),
+        final_tw_end: retirement.and_then(|r| r.final_tw_end),
+    })
+}
+
+/// Query the escrow funding the storage of a worker's proofs.
+pub fn worker_escrow(deps: Deps, worker_did: String) -> StdResult<WorkerEscrowResponse> {
+    let escrow = WORKER_ESCROWS.may_load(deps.storage, &worker_did)?;
+
+    Ok(WorkerEscrowResponse {
+        worker_did,
+        owner: escrow.as_ref().map(|e| e.owner.to_string()),
+        balance: escrow.map_or(Uint128::zero(), |e| e.balance),
+    })
+}
+
+/// Query the contracts notified of hook events.
+pub fn hook_subscribers(deps: Deps) -> StdResult<HookSubscribersResponse> {
+    let subscribers = HOOK_SUBSCRIBERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (contract_addr, events) = item?;
+            Ok(HookSubscriber { contract_addr: contract_addr.to_string(), events })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(HookSubscribersResponse { subscribers })
+}
+
+/// Query the node a worker DID is assigned to.
+pub fn worker_assignment(deps: Deps, worker_did: String) -> StdResult<WorkerAssignmentResponse> {
+    let assignment = WORKER_ASSIGNMENTS.may_load(deps.storage, &worker_did)?;
+
+    Ok(WorkerAssignmentResponse {
+        worker_did,
+        node: assignment.as_ref().map(|a| a.node.to_string()),
+        assigned_by: assignment.as_ref().map(|a| a.assigned_by.to_string()),
+        assigned_at: assignment.map(|a| a.assigned_at),
+    })
+}
+
+/// Windows examined per `worker_activity` call
+pub const MAX_ACTIVITY_WINDOWS: usize = 500;
+
+/// Query a summary of a worker's proofs. Coverage is the union of the windows recorded in
+/// `WORKER_WINDOWS` whose `tw_start` falls in `[from, to)` (reversed windows count from their
+/// earlier end); the gap is the rest of the period from the earliest window start to the latest
+/// window end. At most `MAX_ACTIVITY_WINDOWS` windows are examined, with `next_from` marking
+/// where the rest of the period continues.
+pub fn worker_activity(
+    deps: Deps,
+    worker_did: String,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+) -> StdResult<WorkerActivityResponse> {
+    let worker_proofs = || proofs().idx.worker.prefix(worker_did.clone());
+    let first = worker_proofs().range(deps.storage, None, None, Order::Ascending).next().transpose()?;
+    let last = worker_proofs().range(deps.storage, None, None, Order::Descending).next().transpose()?;
+    let total_proofs = WORKER_PROOF_COUNTS.may_load(deps.storage, &worker_did)?.unwrap_or_default();
+
+    let min = from.map(|from| Bound::inclusive((from.nanos(), 0u64)));
+    let max = to.map(|to| Bound::exclusive((to.nanos(), 0u64)));
+    let mut windows: Vec<(u64, u64)> = WORKER_WINDOWS
+        .sub_prefix(&worker_did)
+        .keys(deps.storage, min, max, Order::Ascending)
+        .take(MAX_ACTIVITY_WINDOWS + 1)
+        .collect::<StdResult<_>>()?;
+    let next_from = if windows.len() > MAX_ACTIVITY_WINDOWS {
+        windows.pop().map(|(start, _)| Timestamp::from_nanos(start))
+    } else {
+        None
+    };
+    for window in windows.iter_mut() {
+        *window = (window.0.min(window.1), window.0.max(window.1));
+    }
+    windows.sort_unstable();
+    let mut covered_nanos = 0u64;
+    let mut current: Option<(u64, u64)> = None;
+    for (start, end) in windows.iter().copied() {
+        current = match current {
+            Some((s, e)) if start <= e => Some((s, e.max(end))),
+            Some((s, e)) => {
+                covered_nanos += e - s;
+                Some((start, end))
+            }
+            None => Some((start, end)),
+        };
+    }
+    if let Some((s, e)) = current {
+        covered_nanos += e - s;
+    }
+    let period_start = windows.first().map(|(start, _)| *start);
+    let period_end = windows.iter().map(|(_, end)| *end).max();
+    let span_nanos = period_end.zip(period_start).map_or(0, |(end, start)| end - start);
+
+    Ok(WorkerActivityResponse {
+        worker_did,
+        total_proofs,
+        first_proof_at: first.map(|(_, proof)| proof.stored_at),
+        last_proof_at: last.as_ref().map(|(_, proof)| proof.stored_at),
+        period_start: period_start.map(Timestamp::from_nanos),
+        period_end: period_end.map(Timestamp::from_nanos),
+        covered_seconds: covered_nanos / 1_000_000_000,
+        gap_seconds: (span_nanos - covered_nanos) / 1_000_000_000,
+        next_from,
+        chain_head_proof_id: last.as_ref().map(|(id, _)| *id),
+        chain_head_hash: last.map(|(_, proof)| proof.data_hash),
+    })
+}
+
+/// Gateways examined per returned gateway by `gateways_missing_data`
+pub const MISSING_DATA_SCAN_FACTOR: usize = 10;
+
+/// Query declared gateways overdue as of `since`, with pagination by gateway DID. A gateway's
+/// latest data is the end of the window of its latest proof in `GATEWAY_TIME_PROOFS`; gateways
+/// that never reported are overdue one interval after their declaration.
+///
+/// At most `limit * MISSING_DATA_SCAN_FACTOR` gateways are examined per call, so a page may
+/// come back short while most gateways are healthy; `next_start_after` then tells the caller
+/// where to continue.
+pub fn gateways_missing_data(
+    deps: Deps,
+    since: Timestamp,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GatewaysMissingDataResponse> {
+    // At least one gateway is examined, so the cursor always moves
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let mut gateways = vec![];
+    let mut last_examined = None;
+    for (examined, item) in GATEWAY_CADENCES.range(deps.storage, start, None, Order::Ascending).enumerate() {
+        if gateways.len() == limit || examined == limit * MISSING_DATA_SCAN_FACTOR {
+            return Ok(GatewaysMissingDataResponse { gateways, next_start_after: last_examined });
+        }
+        let (gateway_did, cadence) = item?;
+        let latest = GATEWAY_TIME_PROOFS
+            .sub_prefix(&gateway_did)
+            .keys(deps.storage, None, None, Order::Descending)
+            .next()
+            .transpose()?;
+        let last_data_at = match latest {
+            Some((_, proof_id)) => {
+                let entry = GATEWAY_PROOFS.load(deps.storage, (&gateway_did, proof_id))?;
+                Some(entry.tw_start.max(entry.tw_end))
+            }
+            None => None,
+        };
+        let due = last_data_at.unwrap_or(cadence.declared_at).plus_seconds(cadence.reporting_interval_seconds);
+        let overdue_seconds = since.seconds().saturating_sub(due.seconds());
+        last_examined = Some(gateway_did.clone());
+        if overdue_seconds > 0 {
+            gateways.push(MissingGatewayData {
+                gateway_did,
+                node: cadence.node.to_string(),
+                reporting_interval_seconds: cadence.reporting_interval_seconds,
+                last_data_at,
+                overdue_seconds,
+            });
+        }
+    }
+
+    Ok(GatewaysMissingDataResponse { gateways, next_start_after: None })
+}
+
+/// Query the batch sequence tracking of a gateway.
+pub fn gateway_sequence(deps: Deps, gateway_did: String) -> StdResult<GatewaySequenceResponse> {
+    let sequence = GATEWAY_SEQUENCES.may_load(deps.storage, &gateway_did)?;
+
+    Ok(GatewaySequenceResponse {
+        gateway_did,
+        next_sequence: sequence.as_ref().map(|s| s.next_sequence),
+        missed_sequences: sequence.as_ref().map_or(0, |s| s.missed_sequences),
+        last_gap: sequence.and_then(|s| s.last_gap),
+    })
+}
+
+/// Query the bond escrowed for a proof.
+pub fn proof_bond(deps: Deps, proof_id: u64) -> StdResult<ProofBondResponse> {
+    let bond = PROOF_BONDS.may_load(deps.storage, proof_id)?;
+
+    Ok(ProofBondResponse {
+        proof_id,
+        node: bond.as_ref().map(|b| b.node.to_string()),
+        amount: bond.as_ref().map_or(Uint128::zero(), |b| b.amount),
+        release_at_block: bond.map(|b| b.release_at_block),
+    })
+}
+
+/// Query the query API versions served. Version 1 is the unprefixed `QueryMsg`.
+pub fn api_versions() -> ApiVersionsResponse {
+    ApiVersionsResponse {
+        latest: QUERY_API_VERSION,
+        supported: (1..=QUERY_API_VERSION).collect(),
+        deprecated: DEPRECATED_QUERY_API_VERSIONS.to_vec(),
+    }
+}
+
+// ============================================================================
+// Query API version 2
+// ============================================================================
+
+fn proof_response_v2(proof: Proof) -> ProofResponseV2 {
+    let owners = proof.ownership()
+        .into_iter()
+        .map(|(owner, share)| OwnerShare { owner: owner.to_string(), share })
+        .collect();
+    let energy = proof.unit.map(|unit| EnergyValues { value_in: proof.value_in, value_out: proof.value_out, unit });
+    ProofResponseV2 {
+        id: proof.id,
+        worker_did: proof.worker_did,
+        data_hash: proof.data_hash,
+        tw_start: proof.tw_start,
+        tw_end: proof.tw_end,
+        batch_metadata: proof.batch_metadata,
+        original_data_reference: proof.original_data_reference,
+        metadata_json: proof.metadata_json,
+        stored_at: proof.stored_at,
+        stored_height: proof.stored_height,
+        stored_by: proof.stored_by.to_string(),
+        region: proof.region,
+        site: proof.site,
+        owners,
+        energy,
+        rejected: proof.rejected,
+        under_review: proof.under_review,
+        schema_version: proof.schema_version,
+        status: proof.status,
+        retain_until: proof.retain_until,
+        compacted: proof.compacted,
+    }
+}
+
+/// Version 2 of `proof`.
+pub fn proof_v2(deps: Deps, id: u64) -> StdResult<ProofResponseV2> {
+    Ok(proof_response_v2(proofs().load(deps.storage, id)?))
+}
+
+/// Version 2 of `proof_by_hash`.
+pub fn proof_by_hash_v2(deps: Deps, data_hash: String) -> StdResult<ProofResponseV2> {
+    proof_v2(deps, proof_id_by_hash(deps, &data_hash)?)
+}
+
+/// Version 2 of `query_proofs`.
+pub fn query_proofs_v2(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<ProofsResponseV2> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let proofs_list = proofs()
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, proof)| proof_response_v2(proof)))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ProofsResponseV2 { proofs: proofs_list })
+}
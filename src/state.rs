@@ -1,7 +1,7 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Timestamp, Uint128};
+use cosmwasm_std::{Addr, Coin, Decimal, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map, IndexedMap, MultiIndex, Index, IndexList};
-use crate::msg::BatchInfo;
+use crate::msg::{AlertThresholds, AuditEvent, AutoJailPolicy, BatchInfo, CarryOverRules, DepositDenom, DisputeStatus, HookEvent, LegacyMapping, OnboardingStage, ProbationPolicy, ProofFee, ProofRewards, ProofStatus, ReputationBounds, ReputationScoring, SequenceGap, Site, SlashDistribution, TierCapabilities, TimelockedAction};
 
 #[cw_serde]
 pub struct Config {
@@ -9,8 +9,12 @@ pub struct Config {
     pub admin: Addr,
     /// A counter for the total number of proofs stored, used to assign unique IDs.
     pub proof_count: u64,
-    /// The minimum reputation a node must have to perform certain actions (e.g., store proofs).
-    pub min_reputation_threshold: i32,
+    /// The minimum reputation a Tier 1 node must have to perform certain actions (e.g., store proofs).
+    pub min_reputation_tier1: i32,
+    /// The minimum reputation a Tier 2 node must have.
+    pub min_reputation_tier2: i32,
+    /// The minimum reputation a Tier 3 node must have.
+    pub min_reputation_tier3: i32,
     /// The address of the treasury contract/wallet where slashed funds or fees might be sent.
     pub treasury: Option<Addr>,
     /// The address of the DID Contract for identity verification
@@ -35,8 +39,147 @@ pub struct Config {
     /// The maximum batch size (in number of snapshots) that a node can submit in a single proof.
     /// This helps prevent excessively large proofs that could strain contract resources.
     pub max_batch_size: u32,
+    /// Optional facility registry contract. When set, proofs carrying a `site` must reference
+    /// a `facility_id` known to the registry.
+    pub facility_registry_address: Option<Addr>,
+    /// Per-proof bond (uc4e) a node must attach to `StoreProof`. Zero disables the bond.
+    #[serde(default)]
+    pub proof_bond: Uint128,
+    /// Number of blocks after which a stored proof is final and its bond can be reclaimed.
+    #[serde(default)]
+    pub proof_finalization_period_blocks: u64,
+    /// Optional cap on the total `snapshot_count` across all batches of one proof.
+    #[serde(default)]
+    pub max_snapshots_per_proof: Option<u64>,
+    /// Optional cap on the serialized size (JSON bytes) of a proof's `batch_metadata`.
+    #[serde(default)]
+    pub max_batch_metadata_bytes: Option<u32>,
+    /// If true, nodes with a `LEGACY_MAPPINGS` entry may submit `StoreProofLegacy`.
+    #[serde(default)]
+    pub legacy_store_proof_enabled: bool,
+    /// Non-native denoms accepted for deposits, with their uc4e-equivalent weights.
+    /// uc4e is always accepted at face value.
+    #[serde(default)]
+    pub deposit_denoms: Vec<DepositDenom>,
+    /// Per-tier capability table. Tiers without an entry use `max_batch_size` and have no
+    /// proof cap.
+    #[serde(default)]
+    pub tier_capabilities: Vec<TierCapabilities>,
+    /// If true, a proof from a second node for an already covered worker window is rejected;
+    /// otherwise it is stored and flagged in `PROOF_CONFLICTS`.
+    #[serde(default)]
+    pub reject_conflicting_proofs: bool,
+    /// Blocks a `PreRegister` escrow stays open for `CompleteRegistration`. Zero disables
+    /// pre-registration.
+    #[serde(default)]
+    pub pre_registration_period_blocks: u64,
+    /// uc4e paid from `referral_pool` to the referrer of each newly registered node. Zero
+    /// records referrals without rewards.
+    #[serde(default)]
+    pub referral_reward: Uint128,
+    /// uc4e funded by the admin and held for referral rewards.
+    #[serde(default)]
+    pub referral_pool: Uint128,
+    /// Blocks between scheduling a timelocked admin action and being able to execute it.
+    #[serde(default)]
+    pub admin_timelock_blocks: u64,
+    /// Number of timelocked admin actions scheduled so far, used to assign action IDs.
+    #[serde(default)]
+    pub pending_action_count: u64,
+    /// Blocks a node whose deposit fell below its tier requirement may keep storing proofs
+    /// while it tops up. Zero means no grace period.
+    #[serde(default)]
+    pub deposit_topup_grace_blocks: u64,
+    /// Range every reputation mutation keeps node reputations within.
+    #[serde(default)]
+    pub reputation_bounds: ReputationBounds,
+    /// Cap on the byte length of variable-length `store_proof` event attributes; larger values
+    /// are left out of the event. `None` uses `DEFAULT_MAX_EVENT_ATTRIBUTE_BYTES`.
+    #[serde(default)]
+    pub max_event_attribute_bytes: Option<u32>,
+    /// Largest run of skipped batch sequence numbers `store_proof` accepts for a gateway.
+    /// `None` accepts any gap (gaps are still recorded in `GATEWAY_SEQUENCES`).
+    #[serde(default)]
+    pub max_sequence_gap: Option<u64>,
+    /// uc4e a dispute raiser must escrow; refunded if the dispute is upheld. Zero makes disputes free.
+    #[serde(default)]
+    pub dispute_bond: Uint128,
+    /// Number of disputes raised so far, used to assign dispute IDs.
+    #[serde(default)]
+    pub dispute_count: u64,
+    /// Automatic reputation changes from proof activity. The default changes nothing.
+    #[serde(default)]
+    pub reputation_scoring: ReputationScoring,
+    /// Share of a removed node's deposit held per open dispute against its proofs, in basis
+    /// points. `None` holds the whole deposit while any dispute is open.
+    #[serde(default)]
+    pub dispute_hold_bps: Option<u16>,
+    /// uc4e accrued by nodes per stored proof. The default accrues nothing.
+    #[serde(default)]
+    pub proof_rewards: ProofRewards,
+    /// uc4e funded by the admin and not yet accrued to nodes as proof rewards.
+    #[serde(default)]
+    pub reward_pool: Uint128,
+    /// Protocol fee paid with every `StoreProof`. `None` charges no fee.
+    #[serde(default)]
+    pub proof_fee: Option<ProofFee>,
+    /// uc4e protocol fees collected and not yet swept to the treasury.
+    #[serde(default)]
+    pub collected_fees: Uint128,
+    /// Limits checked by `CheckAlerts`. The default checks nothing.
+    #[serde(default)]
+    pub alert_thresholds: AlertThresholds,
+    /// While true, the messages of `NodeExecuteMsg::is_pausable` are rejected.
+    #[serde(default)]
+    pub paused: bool,
+    /// Probation applied to nodes registering from now on. The default disables probation.
+    #[serde(default)]
+    pub probation: ProbationPolicy,
+    /// Maximum registered nodes per operator DID; `None` allows any number. Overridden per
+    /// operator by `OPERATOR_NODE_LIMITS`.
+    #[serde(default)]
+    pub max_nodes_per_operator: Option<u32>,
+    /// URI schemes accepted for `original_data_reference` on proofs and batches. `None` uses
+    /// `DEFAULT_DATA_REFERENCE_SCHEMES`.
+    #[serde(default)]
+    pub data_reference_schemes: Option<Vec<String>>,
+    /// Largest reputation change `update_node_reputation` applies immediately; larger changes
+    /// go through the admin timelock. `None` applies every change immediately.
+    #[serde(default)]
+    pub max_reputation_delta: Option<u32>,
+    /// DID prefixes accepted for worker, gateway and operator DIDs. `None` uses
+    /// `DEFAULT_DID_PREFIXES`.
+    #[serde(default)]
+    pub did_prefixes: Option<Vec<String>>,
+    /// Seconds full proofs are kept before they may be compacted, for proofs whose data owner's
+    /// plan sets no retention. `None` keeps them indefinitely.
+    #[serde(default)]
+    pub proof_retention_seconds: Option<u64>,
+    /// uc4e moved from a worker's escrow to the storing node per batch of each proof. Zero
+    /// leaves escrows untouched.
+    #[serde(default)]
+    pub escrow_price_per_batch: Uint128,
+    /// Split of deposit slashed by upheld disputes between challenger, burn and treasury. The
+    /// default sends it all to the treasury.
+    #[serde(default)]
+    pub slash_distribution: SlashDistribution,
+    /// What closing an epoch carries into the next one. The default carries unclaimed rewards
+    /// and open reviews and no unused quota.
+    #[serde(default)]
+    pub carry_over: CarryOverRules,
+    /// Jails nodes whose upheld disputes reach a threshold; `None` disables automatic jailing.
+    #[serde(default)]
+    pub auto_jail: Option<AutoJailPolicy>,
+    /// Most addresses one `WhitelistNodes` or `RemoveNodes` message may list. `None` uses
+    /// `DEFAULT_NODE_BATCH_LIMIT`.
+    #[serde(default)]
+    pub node_batch_limit: Option<u32>,
 }
 
+/// Layout version of `Proof` records written by this code. Bump when `migrate` has to rewrite
+/// existing proofs for a new layout.
+pub const PROOF_SCHEMA_VERSION: u16 = 1;
+
 #[cw_serde]
 pub struct Proof {
     /// Unique identifier for the proof.
@@ -51,6 +194,9 @@ pub struct Proof {
     pub tw_end: Timestamp,
     /// Timestamp of when the proof was stored in the contract.
     pub stored_at: Timestamp,
+    /// Block height at which the proof was stored (0 for proofs stored before it was recorded).
+    #[serde(default)]
+    pub stored_height: u64,
     /// Address of the node that stored this proof.
     pub stored_by: Addr,
 
@@ -60,6 +206,60 @@ pub struct Proof {
     pub original_data_reference: Option<String>,
     /// Optional JSON string for additional, application-specific metadata related to the proof.
     pub metadata_json: Option<String>,
+    /// Optional region/zone code, validated against `REGIONS` when the proof is stored.
+    pub region: Option<String>,
+    /// Optional structured site/location (facility, grid connection point, coordinates hash).
+    pub site: Option<Site>,
+    /// Optional data owner the proof is attributed to; counted against the owner's plan quota.
+    pub data_owner: Option<Addr>,
+    /// Co-owners and their shares (summing to 1); empty when the proof has at most one owner.
+    #[serde(default)]
+    pub owner_shares: Vec<ProofOwnerShare>,
+    /// Optional energy input value for the time window.
+    pub value_in: Option<Uint128>,
+    /// Optional energy output value for the time window.
+    pub value_out: Option<Uint128>,
+    /// Measurement unit of `value_in` / `value_out` (e.g., "Wh", "kWh").
+    pub unit: Option<String>,
+    /// Set when the proof lost a conflict ruling or an upheld dispute; rejected proofs no longer verify.
+    #[serde(default)]
+    pub rejected: bool,
+    /// Set while data owners' flags on the proof await review (see `PROOF_FLAGS`).
+    #[serde(default)]
+    pub under_review: bool,
+    /// `PROOF_SCHEMA_VERSION` the record was written with (0 for records older than versioning).
+    #[serde(default)]
+    pub schema_version: u16,
+    /// `Revoked` once the admin revoked the proof; the record then serves as its tombstone.
+    #[serde(default)]
+    pub status: ProofStatus,
+    /// Until when the full proof is kept; afterwards `CompactProofs` may strip its metadata and
+    /// data references. `None` keeps it indefinitely.
+    #[serde(default)]
+    pub retain_until: Option<Timestamp>,
+    /// Set once the metadata and data references were stripped by `CompactProofs`.
+    #[serde(default)]
+    pub compacted: bool,
+}
+
+#[cw_serde]
+pub struct ProofOwnerShare {
+    /// The co-owner's address.
+    pub owner: Addr,
+    /// Fraction of the proof attributed to the co-owner.
+    pub share: Decimal,
+}
+
+impl Proof {
+    /// Owners the proof is attributed to with their shares: the co-owners when the proof was
+    /// split, otherwise the sole data owner with a share of 1.
+    pub fn ownership(&self) -> Vec<(Addr, Decimal)> {
+        if self.owner_shares.is_empty() {
+            self.data_owner.iter().map(|owner| (owner.clone(), Decimal::one())).collect()
+        } else {
+            self.owner_shares.iter().map(|s| (s.owner.clone(), s.share)).collect()
+        }
+    }
 }
 
 #[cw_serde]
@@ -70,30 +270,382 @@ pub struct Node {
     pub reputation: i32,
     /// Timestamp of when the node was added or successfully registered.
     pub added_at: Timestamp,
-    /// The amount of tokens currently locked as an active deposit by the node in the contract.
-    /// This deposit is in the chain's native staking denomination (e.g., "uc4e").
+    /// The uc4e-equivalent value of the node's active deposit, checked against tier requirements.
+    /// Non-native coins are counted at their configured weight when deposited.
     pub deposit: Uint128,
+    /// The coins actually held for the active deposit, refunded in their original denoms.
+    #[serde(default)]
+    pub deposit_coins: Vec<Coin>,
     /// The operational tier of the node (1, 2, or 3), determined by their native stake.
     pub tier: u8,
     /// Number of proofs successfully stored by this node.
     pub proof_count: u64,
-    /// Number of proofs from this node that have been disputed.
-    /// // TODO: Implement dispute mechanism and link this to slashing logic.
+    /// Number of this node's proofs ruled wrong, in a conflict ruling or an upheld dispute.
     pub disputed_proofs: u64,
     /// Timestamp of the last update to any field in this node's record.
     pub last_updated: Timestamp,
+    /// Optional region/zone code the node operates in, validated against `REGIONS`.
+    pub region: Option<String>,
+    /// The registered node that onboarded this node, set once at registration.
+    #[serde(default)]
+    pub referrer: Option<Addr>,
+    /// Number of nodes that registered with this node as their referrer.
+    #[serde(default)]
+    pub referral_count: u64,
+    /// Block height by which a deposit shortfall must be topped up, set when the shortfall
+    /// is detected and cleared once the deposit covers the tier requirement again.
+    #[serde(default)]
+    pub topup_deadline_block: Option<u64>,
+    /// Set by the operator while the node is down for maintenance; a paused node is not
+    /// operational and cannot store or verify proofs.
+    #[serde(default)]
+    pub paused: bool,
+    /// uc4e proof rewards taken from the reward pool and not yet claimed.
+    #[serde(default)]
+    pub accrued_rewards: Uint128,
+    /// uc4e paid from worker escrows for the node's proofs and not yet claimed. Kept apart from
+    /// `accrued_rewards` because it is the data owners' money, not the reward pool's.
+    #[serde(default)]
+    pub escrow_earnings: Uint128,
+    /// Furthest onboarding stage reached; it only advances. Backfilled by `migrate` for older records.
+    #[serde(default)]
+    pub onboarding_stage: OnboardingStage,
+    /// Block height until which the node is on probation, set at registration.
+    #[serde(default)]
+    pub probation_until_block: Option<u64>,
+    /// Number of the node's first proofs (since registration) that need an attestation, set at
+    /// registration. The node stays on probation until all of them are attested.
+    #[serde(default)]
+    pub required_attestations: u32,
+    /// Attestations of the node's first proofs received so far.
+    #[serde(default)]
+    pub received_attestations: u32,
+    /// DID of the entity operating the node, declared at registration and counted against
+    /// the per-operator node limit.
+    #[serde(default)]
+    pub operator_did: Option<String>,
+    /// Block height until which the node is jailed. A jailed node fails `validate_node` until it
+    /// unjails itself with `Unjail` after that height.
+    #[serde(default)]
+    pub jailed_until: Option<u64>,
+}
+
+impl Node {
+    /// Returns whether the node is still on probation at `height`.
+    pub fn on_probation(&self, height: u64) -> bool {
+        self.probation_until_block.is_some_and(|until| height < until) || self.pending_attestations() > 0
+    }
+
+    /// Returns the number of attestations the node still needs to leave probation.
+    pub fn pending_attestations(&self) -> u32 {
+        self.required_attestations.saturating_sub(self.received_attestations)
+    }
 }
 
 #[cw_serde]
 pub struct UnlockingDeposit {
     /// The address of the node whose deposit is currently in the unbonding/unlocking period.
     pub owner: Addr,
-    /// The amount and denomination of the deposit being unlocked.
+    /// The uc4e-equivalent value of the deposit being unlocked.
     pub amount: Uint128, // Ensure this is Uint128
+    /// The coins returned to the owner on claim.
+    #[serde(default)]
+    pub coins: Vec<Coin>,
     /// The block height at which this deposit becomes claimable by the owner.
     pub release_at_block: u64,
 }
 
+#[cw_serde]
+pub struct GatewayProofEntry {
+    /// Start of the proof's time window.
+    pub tw_start: Timestamp,
+    /// End of the proof's time window.
+    pub tw_end: Timestamp,
+    /// Energy imported over the window, copied from the proof.
+    pub value_in: Option<Uint128>,
+    /// Energy exported over the window, copied from the proof.
+    pub value_out: Option<Uint128>,
+}
+
+impl GatewayProofEntry {
+    pub fn from_proof(proof: &Proof) -> Self {
+        GatewayProofEntry {
+            tw_start: proof.tw_start,
+            tw_end: proof.tw_end,
+            value_in: proof.value_in,
+            value_out: proof.value_out,
+        }
+    }
+}
+
+#[cw_serde]
+pub struct PendingAction {
+    pub action: TimelockedAction,
+    pub scheduled_by: Addr,
+    pub scheduled_at_block: u64,
+    /// First block height at which `ExecutePendingAction` may run the action.
+    pub executable_at_block: u64,
+}
+
+#[cw_serde]
+pub struct AdminAuditEntry {
+    /// ID of the timelocked action this entry belongs to.
+    pub action_id: u64,
+    pub action: TimelockedAction,
+    pub event: AuditEvent,
+    pub admin: Addr,
+    pub block_height: u64,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct PreRegistration {
+    /// uc4e-equivalent value of the escrowed coins at the time of pre-registration.
+    pub value: Uint128,
+    /// The escrowed coins, locked as the deposit on completion or refunded on expiry.
+    pub coins: Vec<Coin>,
+    /// First block height at which the escrow can no longer be completed and is refundable.
+    pub expires_at_block: u64,
+}
+
+#[cw_serde]
+pub struct ProofConflict {
+    /// The proof first stored for the worker window.
+    pub original_proof_id: u64,
+    /// Block time at which the conflicting proof was stored.
+    pub detected_at: Timestamp,
+    /// Outcome of the ruling, once resolved.
+    #[serde(default)]
+    pub resolution: Option<ConflictResolution>,
+}
+
+#[cw_serde]
+pub struct ConflictResolution {
+    /// The proof ruled canonical; the other proof of the conflict is rejected.
+    pub canonical_proof_id: u64,
+    pub resolved_at: Timestamp,
+}
+
+/// Batch sequence tracking of one gateway DID.
+#[cw_serde]
+pub struct GatewaySequence {
+    /// Sequence number the gateway's next batch is expected to carry.
+    pub next_sequence: u64,
+    /// Total sequence numbers skipped so far.
+    pub missed_sequences: u64,
+    /// Most recent gap, if any was ever accepted.
+    pub last_gap: Option<SequenceGap>,
+}
+
+/// Funds a data owner deposited to pay for the storage of a worker's proofs.
+#[cw_serde]
+pub struct WorkerEscrow {
+    /// Data owner that funds the escrow and may withdraw from it.
+    pub owner: Addr,
+    /// uc4e left to pay storing nodes.
+    pub balance: Uint128,
+}
+
+/// Node a worker DID is bound to; only that node may store the worker's proofs.
+#[cw_serde]
+pub struct WorkerAssignment {
+    /// The node allowed to store the worker's proofs.
+    pub node: Addr,
+    /// The admin or worker DID controller that made the assignment.
+    pub assigned_by: Addr,
+    /// Block time of the assignment.
+    pub assigned_at: Timestamp,
+}
+
+/// Reporting cadence a node declared for one of its gateways.
+#[cw_serde]
+pub struct GatewayCadence {
+    /// Node that declared the gateway and controls it.
+    pub node: Addr,
+    /// Longest expected time between the ends of consecutive batch windows.
+    pub reporting_interval_seconds: u64,
+    /// Block time of the declaration; a gateway that never reported is overdue from here.
+    pub declared_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct Dispute {
+    pub proof_id: u64,
+    pub raised_by: Addr,
+    pub evidence_uri: String,
+    /// uc4e escrowed by the raiser, refunded or forfeited on resolution.
+    pub bond: Uint128,
+    pub status: DisputeStatus,
+    pub raised_at: Timestamp,
+    pub resolved_at: Option<Timestamp>,
+}
+
+/// Part of a removed node's deposit withheld until the disputes against its proofs are resolved.
+#[cw_serde]
+pub struct HeldDeposit {
+    /// The uc4e-equivalent value of the held coins.
+    pub amount: Uint128,
+    /// The coins returned to the node once no dispute against its proofs is open.
+    pub coins: Vec<Coin>,
+    pub held_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct ProofFlag {
+    /// The owner (data owner or co-owner) reporting the proof as incorrect.
+    pub flagged_by: Addr,
+    /// Free-form explanation supplied by the owner.
+    pub reason: String,
+    /// Block time of the flag.
+    pub flagged_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct WorkerRetirement {
+    /// Block time of the decommissioning.
+    pub retired_at: Timestamp,
+    /// The node (or admin) that decommissioned the worker.
+    pub retired_by: Addr,
+    /// ID of the worker's last proof, which ends its proof chain.
+    pub final_proof_id: Option<u64>,
+    /// Time window covered by the final proof.
+    pub final_tw_start: Option<Timestamp>,
+    pub final_tw_end: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct ProofBond {
+    /// The node that escrowed the bond when storing the proof.
+    pub node: Addr,
+    /// The escrowed amount in uc4e.
+    pub amount: Uint128,
+    /// The block height at which the proof is final and the bond can be claimed.
+    pub release_at_block: u64,
+}
+
+#[cw_serde]
+pub struct Plan {
+    /// Maximum proofs attributed to a data owner per quota period.
+    pub proofs_per_period: u64,
+    /// Maximum metadata size in bytes (proof `metadata_json` plus all batch `metadata_json`).
+    pub max_metadata_bytes: u32,
+    /// Price in uc4e of one subscription period. Zero means the plan can only be assigned by the admin.
+    pub price: Uint128,
+    /// Seconds the owner's full proofs are kept before they may be compacted. `None` uses
+    /// `Config.proof_retention_seconds`.
+    #[serde(default)]
+    pub retention_seconds: Option<u64>,
+}
+
+#[cw_serde]
+pub struct OwnerPlan {
+    /// Identifier of the plan in `PLANS`.
+    pub plan_id: String,
+    /// When the subscription ends. `None` means it does not expire (admin-assigned).
+    pub expires_at: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct QuotaUsage {
+    /// Index of the quota period the counter belongs to (block time / `QUOTA_PERIOD_SECONDS`).
+    pub period: u64,
+    /// Number of proofs attributed to the owner (or stored by the node) during `period`.
+    pub proofs_used: u64,
+    /// Unused owner proofs carried over from the previous period (see `CarryOverRules`).
+    #[serde(default)]
+    pub carried_over: u64,
+}
+
+impl QuotaUsage {
+    /// Usage at the start of `period`, carrying up to `max_carry_over` of the allowance left
+    /// unused in `previous` when it is the preceding period.
+    pub fn start(period: u64, previous: Option<&QuotaUsage>, allowance: u64, max_carry_over: u64) -> Self {
+        let carried_over = previous
+            .filter(|previous| previous.period + 1 == period)
+            .map_or(0, |previous| (allowance + previous.carried_over).saturating_sub(previous.proofs_used).min(max_carry_over));
+        QuotaUsage { period, proofs_used: 0, carried_over }
+    }
+}
+
+/// Length of a data-owner (and node tier) quota period (30 days). Epochs follow the same periods.
+pub const QUOTA_PERIOD_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+/// Energy stored in one epoch for one measurement unit
+#[cw_serde]
+pub struct EnergyTotal {
+    pub unit: String,
+    pub value_in: Uint128,
+    pub value_out: Uint128,
+}
+
+/// Activity of one epoch (block time / `QUOTA_PERIOD_SECONDS`), summed as it happens and
+/// published by `close_epoch`.
+#[cw_serde]
+#[derive(Default)]
+pub struct EpochStats {
+    pub proofs_stored: u64,
+    /// Energy values of the stored proofs, per unit
+    pub energy: Vec<EnergyTotal>,
+    /// uc4e proof rewards accrued to nodes
+    pub rewards_distributed: Uint128,
+    /// Number of deposit slashes
+    pub slashes: u64,
+    /// uc4e-equivalent value removed from deposits by slashes
+    pub slashed_value: Uint128,
+    /// Set once `close_epoch` has emitted the summary
+    pub closed: bool,
+    /// Progress of a close spread over several `close_epoch` calls
+    #[serde(default)]
+    pub closing: Option<EpochClosing>,
+}
+
+/// Cursor and running totals of an epoch close, which handles at most `CLOSE_EPOCH_PAGE_SIZE`
+/// nodes and flagged proofs per call.
+#[cw_serde]
+#[derive(Default)]
+pub struct EpochClosing {
+    /// Last node handled; nodes are visited in address order
+    pub nodes_after: Option<String>,
+    /// Set once every node has been handled
+    pub nodes_done: bool,
+    /// Last flagged proof handled; proofs are visited in ID order after the nodes
+    pub reviews_after: Option<u64>,
+    pub active_nodes: u64,
+    pub rewards_expired: Uint128,
+    pub rewards_carried: Uint128,
+    pub reviews_closed: u64,
+    pub reviews_carried: u64,
+}
+
+impl EpochStats {
+    /// Adds a proof's energy values to the total of its unit.
+    pub fn add_energy(&mut self, unit: &str, value_in: Option<Uint128>, value_out: Option<Uint128>) {
+        let index = match self.energy.iter().position(|t| t.unit == unit) {
+            Some(index) => index,
+            None => {
+                self.energy.push(EnergyTotal { unit: unit.to_string(), value_in: Uint128::zero(), value_out: Uint128::zero() });
+                self.energy.len() - 1
+            }
+        };
+        let total = &mut self.energy[index];
+        total.value_in += value_in.unwrap_or_default();
+        total.value_out += value_out.unwrap_or_default();
+    }
+}
+
+/// Length of a `WORKER_ENERGY` bucket (one day)
+pub const ENERGY_BUCKET_SECONDS: u64 = 24 * 60 * 60;
+
+/// Energy of a worker's proofs in one bucket and unit
+#[cw_serde]
+#[derive(Default)]
+pub struct EnergyBucket {
+    pub value_in: Uint128,
+    pub value_out: Uint128,
+    /// Proofs whose values are included
+    pub proofs: u64,
+}
+
 // ============================================================================
 // Storage Structures
 // ============================================================================
@@ -102,22 +654,26 @@ pub struct UnlockingDeposit {
 pub const CONFIG: Item<Config> = Item::new("config");
 
 /// Phase 1b: IndexedMap with secondary indexes for efficient querying
-/// ProofIndexes enables querying proofs by worker_did
+/// ProofIndexes enables querying proofs by worker_did, by time window start and by storing node
 pub struct ProofIndexes<'a> {
     /// Index by worker_did for efficient Worker Node queries
     pub worker: MultiIndex<'a, String, Proof, u64>,
+    /// Index by tw_start in nanoseconds, for pulling the proofs of a billing period
+    pub tw_start: MultiIndex<'a, u64, Proof, u64>,
+    /// Index by stored_by, so operators can audit what their node wrote
+    pub node: MultiIndex<'a, String, Proof, u64>,
 }
 
 impl<'a> IndexList<Proof> for ProofIndexes<'a> {
     fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Proof>> + '_> {
-        let v: Vec<&dyn Index<Proof>> = vec![&self.worker];
+        let v: Vec<&dyn Index<Proof>> = vec![&self.worker, &self.tw_start, &self.node];
         Box::new(v.into_iter())
     }
 }
 
 /// Stores individual data proofs with secondary indexes
 /// Primary key: u64 (proof ID)
-/// Secondary index: worker_did (String)
+/// Secondary indexes: worker_did (String), tw_start (u64 nanoseconds), stored_by (String)
 pub fn proofs<'a>() -> IndexedMap<'a, u64, Proof, ProofIndexes<'a>> {
     let indexes = ProofIndexes {
         worker: MultiIndex::new(
@@ -125,28 +681,218 @@ pub fn proofs<'a>() -> IndexedMap<'a, u64, Proof, ProofIndexes<'a>> {
             "proofs",
             "proofs__worker"
         ),
+        tw_start: MultiIndex::new(
+            |_pk, d| d.tw_start.nanos(),
+            "proofs",
+            "proofs__tw_start"
+        ),
+        node: MultiIndex::new(
+            |_pk, d| d.stored_by.to_string(),
+            "proofs",
+            "proofs__node"
+        ),
     };
     IndexedMap::new("proofs", indexes)
 }
 
 /// Manual index for gateway_did (since multiple batches can have different gateways)
 /// Key: (gateway_did, proof_id)
-/// Value: () - just for membership checking
-pub const GATEWAY_PROOFS: Map<(&str, u64), ()> = Map::new("gateway_proofs");
+/// Value: compact proof summary, so per-gateway queries need not load the full proof
+pub const GATEWAY_PROOFS: Map<(&str, u64), GatewayProofEntry> = Map::new("gateway_proofs");
+
+/// Manual index for gateway proofs ordered by time window start
+/// Key: (gateway_did, tw_start in nanoseconds, proof_id)
+/// Used by ProofsByGateway when a `from`/`to` window is given.
+pub const GATEWAY_TIME_PROOFS: Map<(&str, u64, u64), ()> = Map::new("gateway_time_proofs");
+
+/// Admin-managed list of region/zone codes accepted on nodes and proofs.
+/// Key: region code
+pub const REGIONS: Map<&str, ()> = Map::new("regions");
+
+/// Manual index for proofs by region
+/// Key: (region, proof_id)
+pub const REGION_PROOFS: Map<(&str, u64), ()> = Map::new("region_proofs");
+
+/// Manual index for proofs by owner, holding the owner's share of the proof (see `Proof::ownership`)
+/// Key: (owner, proof_id)
+pub const OWNER_PROOFS: Map<(&Addr, u64), Decimal> = Map::new("owner_proofs");
+
+/// Manual index for nodes by region
+/// Key: (region, node_address)
+pub const REGION_NODES: Map<(&str, &Addr), ()> = Map::new("region_nodes");
+
+/// Manual index for nodes by operator DID
+/// Key: (operator_did, node_address)
+pub const OPERATOR_NODES: Map<(&str, &Addr), ()> = Map::new("operator_nodes");
+
+/// Admin overrides of `max_nodes_per_operator` for individual operators
+/// Key: operator_did, Value: maximum registered nodes
+pub const OPERATOR_NODE_LIMITS: Map<&str, u32> = Map::new("operator_node_limits");
+
+/// Manual index for proofs by the block height they were stored at. Proof IDs are assigned in
+/// block order, so the index is sorted by ID as well. Proofs stored before heights were
+/// recorded are indexed at height 0.
+/// Key: (stored_height, proof_id)
+pub const HEIGHT_PROOFS: Map<(u64, u64), ()> = Map::new("height_proofs");
+
+/// Manual index for proofs by the grid connection point of their site
+/// Key: (grid_connection_point, proof_id)
+pub const GRID_POINT_PROOFS: Map<(&str, u64), ()> = Map::new("grid_point_proofs");
 
 /// Provides an index to look up a proof ID (u64) by its data hash (String).
 /// This allows for quick checks of proof existence and retrieval by content hash.
 pub const PROOF_BY_HASH: Map<&str, u64> = Map::new("proof_by_hash");
 
-/// Stores information about registered nodes, keyed by their address (Addr).
-/// This is the primary registry for active nodes in the system.
-pub const NODES: Map<&Addr, Node> = Map::new("nodes");
+/// Proofs of nodes on probation that still await an `AttestProof`
+/// Key: proof ID, Value: address of the storing node
+pub const PROBATION_PROOFS: Map<u64, Addr> = Map::new("probation_proofs");
+
+/// Hashes of revoked proofs, moved here from `PROOF_BY_HASH` so the hash stays reserved
+/// Key: data hash, Value: proof ID
+pub const REVOKED_PROOF_HASHES: Map<&str, u64> = Map::new("revoked_proof_hashes");
 
-/// If `use_whitelist` in Config is true, this map stores addresses explicitly whitelisted by an admin.
-/// Being in this list might be a prerequisite for node registration or certain operations.
-/// The value is a boolean, typically true if the address is whitelisted.
+/// Registry of all nodes, keyed by address: admin-whitelisted nodes (tier 0) and registered
+/// nodes (tier 1-3). Supersedes the unused `"nodes"` namespace, which `migrate` drains.
 pub const WHITELISTED_NODES: Map<String, Node> = Map::new("whitelisted_nodes");
 
 /// Stores information about node deposits that are currently in the unbonding/unlocking period.
 /// Keyed by the node's address (Addr).
-pub const UNLOCKING_DEPOSITS: Map<String, UnlockingDeposit> = Map::new("unlocking_deposits");
\ No newline at end of file
+pub const UNLOCKING_DEPOSITS: Map<String, UnlockingDeposit> = Map::new("unlocking_deposits");
+
+/// Sum of the `amount` of every `UNLOCKING_DEPOSITS` entry, kept in step by
+/// `set_unlocking_deposit` so alert checks need not scan the map
+pub const UNLOCKING_TOTAL: Item<Uint128> = Item::new("unlocking_total");
+
+/// Bonds escrowed for proofs that are not yet final, keyed by proof ID.
+/// Entries are removed when the bond is claimed or forfeited.
+pub const PROOF_BONDS: Map<u64, ProofBond> = Map::new("proof_bonds");
+
+/// DIDs that `StoreProofLegacy` submissions of a node are mapped to, keyed by node address.
+pub const LEGACY_MAPPINGS: Map<&Addr, LegacyMapping> = Map::new("legacy_mappings");
+
+/// Data-owner subscription plans, keyed by plan ID.
+pub const PLANS: Map<&str, Plan> = Map::new("plans");
+
+/// Plan currently assigned to (or purchased by) each data owner.
+pub const OWNER_PLANS: Map<&Addr, OwnerPlan> = Map::new("owner_plans");
+
+/// Per-owner proof counter for the current quota period.
+pub const QUOTA_USAGE: Map<&Addr, QuotaUsage> = Map::new("quota_usage");
+
+/// Activity per epoch
+/// Key: epoch index (block time / `QUOTA_PERIOD_SECONDS`)
+pub const EPOCH_STATS: Map<u64, EpochStats> = Map::new("epoch_stats");
+
+/// Energy values of active, non-rejected proofs, summed as proofs are stored, rejected or revoked
+/// Key: (worker_did, tw_start / `ENERGY_BUCKET_SECONDS`, unit)
+pub const WORKER_ENERGY: Map<(&str, u64, &str), EnergyBucket> = Map::new("worker_energy");
+
+/// Epoch in which each node last accrued rewards, for `CarryOverRules::expire_unclaimed_rewards`
+pub const REWARD_EPOCHS: Map<&Addr, u64> = Map::new("reward_epochs");
+
+/// First proof stored for each worker time window, used to detect conflicting submissions
+/// Key: (worker_did, tw_start nanos, tw_end nanos)
+/// Value: proof_id
+pub const WORKER_WINDOWS: Map<(&str, u64, u64), u64> = Map::new("worker_windows");
+
+/// Number of proofs stored for each worker, including revoked and rejected ones
+pub const WORKER_PROOF_COUNTS: Map<&str, u64> = Map::new("worker_proof_counts");
+
+/// Number of nodes assigned to audit a proof
+pub const AUDIT_COMMITTEE_SIZE: usize = 5;
+
+/// Audit committee selected when each proof was stored, best-ranked member first
+/// Key: proof_id
+pub const AUDIT_COMMITTEES: Map<u64, Vec<Addr>> = Map::new("audit_committees");
+
+/// Flagged conflicts, one per proof that duplicated an already covered worker window
+/// Key: conflicting proof_id
+pub const PROOF_CONFLICTS: Map<u64, ProofConflict> = Map::new("proof_conflicts");
+
+/// Worker-to-node assignments; workers without an entry accept proofs from any node
+/// Key: worker_did
+pub const WORKER_ASSIGNMENTS: Map<&str, WorkerAssignment> = Map::new("worker_assignments");
+
+/// Data-owner escrows paying for the storage of each worker's proofs
+/// Key: worker_did
+pub const WORKER_ESCROWS: Map<&str, WorkerEscrow> = Map::new("worker_escrows");
+
+/// Nodes permanently barred by a `TombstoneNode` dispute ruling
+/// Key: node address, Value: ID of the dispute that tombstoned it
+pub const TOMBSTONED_NODES: Map<&Addr, u64> = Map::new("tombstoned_nodes");
+
+/// Contracts notified of hook events, with the events each subscribed to
+/// Key: subscriber contract address
+pub const HOOK_SUBSCRIBERS: Map<&Addr, Vec<HookEvent>> = Map::new("hook_subscribers");
+
+/// Reporting cadences declared by nodes for their gateways
+/// Key: gateway_did
+pub const GATEWAY_CADENCES: Map<&str, GatewayCadence> = Map::new("gateway_cadences");
+
+/// Batch sequence tracking per gateway, for gateways whose batches carry a `sequence`
+/// Key: gateway_did
+pub const GATEWAY_SEQUENCES: Map<&str, GatewaySequence> = Map::new("gateway_sequences");
+
+/// Disputes raised against proofs
+/// Key: dispute ID
+pub const DISPUTES: Map<u64, Dispute> = Map::new("disputes");
+
+/// Open disputes, at most one per proof and raiser
+/// Key: (proof_id, raised_by)
+/// Value: dispute ID
+pub const OPEN_DISPUTES: Map<(u64, &Addr), u64> = Map::new("open_disputes");
+
+/// Number of `OPEN_DISPUTES` entries, kept so alert checks need not count them
+pub const OPEN_DISPUTE_COUNT: Item<u64> = Item::new("open_dispute_count");
+
+/// Open disputes by submitter of the disputed proof
+/// Key: (node address, dispute ID)
+pub const NODE_OPEN_DISPUTES: Map<(&Addr, u64), ()> = Map::new("node_open_disputes");
+
+/// Funds the contract owes, per denom (`cw20:<addr>` for CW20 tokens): deposits, unlocking and
+/// held deposits, pre-registration escrows, proof and dispute bonds, reward and referral pools,
+/// accrued rewards and collected fees. Outgoing transfers may only spend the balance above it.
+pub const LIABILITIES: Map<&str, Uint128> = Map::new("liabilities");
+
+/// Deposit portions of removed nodes held for their open disputes
+/// Key: node address
+pub const HELD_DEPOSITS: Map<&Addr, HeldDeposit> = Map::new("held_deposits");
+
+/// Owner flags of proofs under review, at most one per owner
+/// Key: proof ID
+pub const PROOF_FLAGS: Map<u64, Vec<ProofFlag>> = Map::new("proof_flags");
+
+/// Delegates authorized to approve and flag proofs on an owner's behalf
+/// Key: (owner, delegate)
+pub const OWNER_DELEGATES: Map<(&Addr, &Addr), ()> = Map::new("owner_delegates");
+
+/// Owner approvals of proofs attributed to them, one per owner (see `Proof::ownership`)
+/// Key: (owner, proof_id), Value: approval time
+pub const PROOF_APPROVALS: Map<(&Addr, u64), Timestamp> = Map::new("proof_approvals");
+
+/// Decommissioned worker DIDs. `store_proof` rejects proofs for any worker listed here.
+/// Key: worker_did
+pub const RETIRED_WORKERS: Map<&str, WorkerRetirement> = Map::new("retired_workers");
+
+/// Timelocked admin actions awaiting execution or cancellation
+/// Key: action ID
+pub const PENDING_ACTIONS: Map<u64, PendingAction> = Map::new("pending_actions");
+
+/// Append-only log of scheduled, executed and cancelled timelocked actions
+/// Key: sequential entry ID
+pub const ADMIN_AUDIT_LOG: Map<u64, AdminAuditEntry> = Map::new("admin_audit_log");
+
+/// Roles delegated by the admin
+/// Key: (`Role::key`, address)
+pub const ROLES: Map<(&str, &Addr), ()> = Map::new("roles");
+
+/// Referral attribution index
+/// Key: (referrer address, referred node address)
+pub const REFERRALS: Map<(&Addr, &Addr), ()> = Map::new("referrals");
+
+/// Deposits escrowed by `PreRegister`, pending `CompleteRegistration`
+/// Key: operator address
+pub const PRE_REGISTRATIONS: Map<&Addr, PreRegistration> = Map::new("pre_registrations");
+
+/// Per-node proof counter for the current quota period, kept for tiers with a proof cap.
+pub const NODE_QUOTA_USAGE: Map<&Addr, QuotaUsage> = Map::new("node_quota_usage");
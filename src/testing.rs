@@ -0,0 +1,393 @@
+//! cw-multi-test harness for integration tests, compiled with the `testing` feature.
+//!
+//! [`mock_app`] builds an app whose staking module answers from the delegations given to
+//! [`MockStaking`] and deploys a mock DID contract at [`MOCK_DID_CONTRACT`], so tests run the
+//! real DID verification and native stake paths of the contract. Contracts building on DeTrack
+//! can depend on this crate with `features = ["library", "testing"]` to reuse it.
+
+use anyhow::{bail, Result as AnyResult};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{
+    coin, to_json_binary, Addr, AllDelegationsResponse, Api, Binary, BlockInfo, BondedDenomResponse, Coin, Delegation, Deps,
+    DepsMut, Empty, Env, MessageInfo, Querier, Response, StakingMsg, StakingQuery, StdError, StdResult, Storage, Timestamp, Uint128,
+};
+use cw_multi_test::custom_handler::CachingCustomHandler;
+use cw_multi_test::{
+    App, AppBuilder, AppResponse, BankKeeper, Contract, ContractWrapper, CosmosRouter, Executor, Module, Staking, StakingSudo,
+    WasmKeeper,
+};
+
+use cw_storage_plus::{Item, Map};
+
+use crate::contract::{execute, instantiate, query, reply};
+use crate::msg::{BatchInfo, DetrackHookMsg, ExecuteMsg, HookExecuteMsg, InstantiateMsg, NodeExecuteMsg};
+
+/// Address of the mock DID contract, the first contract [`mock_app`] instantiates
+pub const MOCK_DID_CONTRACT: &str = "contract0";
+
+/// Controller the mock DID contract reports for DIDs not reassigned with [`set_did_controller`]
+pub const MOCK_DID_CONTROLLER: &str = "user";
+
+/// The contract sends no custom messages, so any custom module works here
+pub type TestApp = App<
+    BankKeeper,
+    MockApi,
+    MockStorage,
+    CachingCustomHandler<Empty, Empty>,
+    WasmKeeper<Empty, Empty>,
+    MockStaking,
+>;
+
+/// Staking module answering `BondedDenom` and `AllDelegations` from fixed delegations
+pub struct MockStaking {
+    bonded_denom: String,
+    delegations: Vec<Delegation>,
+}
+
+impl MockStaking {
+    pub fn new(bonded_denom: &str) -> Self {
+        MockStaking { bonded_denom: bonded_denom.to_string(), delegations: vec![] }
+    }
+
+    /// Adds a delegation of `amount` bonded tokens from `delegator` to `validator`
+    pub fn with_delegation(self, delegator: &str, validator: &str, amount: u128) -> Self {
+        let denom = self.bonded_denom.clone();
+        self.with_delegation_in(delegator, validator, coin(amount, denom))
+    }
+
+    /// Adds a delegation in any denom, e.g. to check that only the bonded denom counts
+    pub fn with_delegation_in(mut self, delegator: &str, validator: &str, amount: Coin) -> Self {
+        self.delegations.push(Delegation {
+            delegator: Addr::unchecked(delegator),
+            validator: validator.to_string(),
+            amount,
+        });
+        self
+    }
+}
+
+impl Module for MockStaking {
+    type ExecT = StakingMsg;
+    type QueryT = StakingQuery;
+    type SudoT = StakingSudo;
+
+    fn execute<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        sender: Addr,
+        msg: StakingMsg,
+    ) -> AnyResult<AppResponse> {
+        bail!("Unexpected staking msg {:?} from {:?}", msg, sender)
+    }
+
+    fn sudo<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        msg: StakingSudo,
+    ) -> AnyResult<AppResponse> {
+        bail!("Unexpected staking sudo {:?}", msg)
+    }
+
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        request: StakingQuery,
+    ) -> AnyResult<Binary> {
+        match request {
+            StakingQuery::BondedDenom {} => Ok(to_json_binary(&BondedDenomResponse::new(self.bonded_denom.clone()))?),
+            StakingQuery::AllDelegations { delegator } => {
+                let delegations = self.delegations.iter().filter(|d| d.delegator == delegator).cloned().collect();
+                Ok(to_json_binary(&AllDelegationsResponse::new(delegations))?)
+            }
+            other => bail!("Unexpected staking query {:?}", other),
+        }
+    }
+}
+
+impl Staking for MockStaking {}
+
+/// Controllers assigned with [`set_did_controller`], keyed by DID
+const MOCK_DID_CONTROLLERS: Map<&str, String> = Map::new("controllers");
+
+#[cw_serde]
+enum MockDidExecuteMsg {
+    SetController { did: String, controller: String },
+}
+
+#[cw_serde]
+enum MockDidQueryMsg {
+    GetDidDocument { did: String },
+}
+
+#[cw_serde]
+struct MockDidDocument {
+    id: String,
+    controller: String,
+    service: Vec<serde_json::Value>,
+}
+
+fn mock_did_instantiate(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Ok(Response::new())
+}
+
+fn mock_did_execute(deps: DepsMut, _env: Env, _info: MessageInfo, msg: MockDidExecuteMsg) -> StdResult<Response> {
+    match msg {
+        MockDidExecuteMsg::SetController { did, controller } => MOCK_DID_CONTROLLERS.save(deps.storage, &did, &controller)?,
+    }
+    Ok(Response::new())
+}
+
+/// Knows every DID except those whose identifier starts with "unregistered"
+fn mock_did_query(deps: Deps, _env: Env, msg: MockDidQueryMsg) -> StdResult<Binary> {
+    match msg {
+        MockDidQueryMsg::GetDidDocument { did } if did.rsplit(':').next().is_some_and(|id| id.starts_with("unregistered")) => {
+            Err(StdError::not_found(did))
+        }
+        MockDidQueryMsg::GetDidDocument { did } => {
+            let controller = MOCK_DID_CONTROLLERS.may_load(deps.storage, &did)?;
+            to_json_binary(&MockDidDocument {
+                id: did,
+                controller: controller.unwrap_or_else(|| MOCK_DID_CONTROLLER.to_string()),
+                service: vec![],
+            })
+        }
+    }
+}
+
+/// DID contract resolving every DID except those whose identifier starts with "unregistered"
+pub fn mock_did_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(mock_did_execute, mock_did_instantiate, mock_did_query))
+}
+
+/// The DeTrack contract itself
+pub fn detrack_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query).with_reply(reply))
+}
+
+/// Notifications received by a [`mock_hook_contract`], in order
+const MOCK_HOOK_RECEIVED: Item<Vec<DetrackHookMsg>> = Item::new("received");
+/// Whether the [`mock_hook_contract`] fails every notification
+const MOCK_HOOK_REJECTS: Item<bool> = Item::new("rejects");
+
+#[cw_serde]
+pub struct MockHookInstantiateMsg {
+    pub reject: bool,
+}
+
+#[cw_serde]
+enum MockHookQueryMsg {
+    Received {},
+}
+
+fn mock_hook_instantiate(deps: DepsMut, _env: Env, _info: MessageInfo, msg: MockHookInstantiateMsg) -> StdResult<Response> {
+    MOCK_HOOK_REJECTS.save(deps.storage, &msg.reject)?;
+    MOCK_HOOK_RECEIVED.save(deps.storage, &vec![])?;
+    Ok(Response::new())
+}
+
+fn mock_hook_execute(deps: DepsMut, _env: Env, _info: MessageInfo, msg: HookExecuteMsg) -> StdResult<Response> {
+    if MOCK_HOOK_REJECTS.load(deps.storage)? {
+        return Err(StdError::generic_err("hook rejected"));
+    }
+    let HookExecuteMsg::DetrackHook(notification) = msg;
+    MOCK_HOOK_RECEIVED.update(deps.storage, |mut received| -> StdResult<_> {
+        received.push(notification);
+        Ok(received)
+    })?;
+    Ok(Response::new())
+}
+
+fn mock_hook_query(deps: Deps, _env: Env, msg: MockHookQueryMsg) -> StdResult<Binary> {
+    match msg {
+        MockHookQueryMsg::Received {} => to_json_binary(&MOCK_HOOK_RECEIVED.load(deps.storage)?),
+    }
+}
+
+/// Hook subscriber recording the `DetrackHookMsg`s it receives, or failing them all when
+/// instantiated with `reject: true`
+pub fn mock_hook_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(mock_hook_execute, mock_hook_instantiate, mock_hook_query))
+}
+
+/// Notifications received so far by the [`mock_hook_contract`] at `hook`
+pub fn received_hooks(app: &TestApp, hook: &Addr) -> Vec<DetrackHookMsg> {
+    app.wrap().query_wasm_smart(hook, &MockHookQueryMsg::Received {}).unwrap()
+}
+
+/// App with the given bank balances and staking module and the mock DID contract deployed at
+/// [`MOCK_DID_CONTRACT`]
+pub fn mock_app(balances: &[(&str, Vec<Coin>)], staking: MockStaking) -> TestApp {
+    let mut app = AppBuilder::new()
+        .with_custom(CachingCustomHandler::new())
+        .with_staking(staking)
+        .build(|router, _, storage| {
+            for (address, coins) in balances {
+                router.bank.init_balance(storage, &Addr::unchecked(*address), coins.clone()).unwrap();
+            }
+        });
+
+    let did_id = app.store_code(mock_did_contract());
+    let did_addr = app
+        .instantiate_contract(did_id, Addr::unchecked("admin"), &Empty {}, &[], "DID Contract", None)
+        .unwrap();
+    assert_eq!(did_addr, MOCK_DID_CONTRACT);
+    app
+}
+
+/// Makes `controller` (a node address or operator DID) the controller of `did` in the mock DID contract
+pub fn set_did_controller(app: &mut TestApp, did: &str, controller: &str) {
+    let msg = MockDidExecuteMsg::SetController { did: did.to_string(), controller: controller.to_string() };
+    app.execute_contract(Addr::unchecked("admin"), Addr::unchecked(MOCK_DID_CONTRACT), &msg, &[]).unwrap();
+}
+
+/// Stores and instantiates the DeTrack contract with `msg`, returning its address
+pub fn instantiate_detrack(app: &mut TestApp, sender: &str, msg: &InstantiateMsg) -> Addr {
+    let code_id = app.store_code(detrack_contract());
+    app.instantiate_contract(code_id, Addr::unchecked(sender), msg, &[], "DeTrack", None).unwrap()
+}
+
+/// Registers `node` with `deposit`; its tier follows from its delegations in [`MockStaking`]
+pub fn register_node(app: &mut TestApp, contract: &Addr, node: &str, deposit: &[Coin]) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        Addr::unchecked(node),
+        contract.clone(),
+        &ExecuteMsg::Node(NodeExecuteMsg::RegisterNode { referrer: None, operator_did: None }),
+        deposit,
+    )
+}
+
+/// Single-batch metadata from the given gateway
+pub fn single_batch(gateway_did: &str) -> Vec<BatchInfo> {
+    vec![BatchInfo {
+        batch_id: "batch-001".to_string(),
+        gateway_did: gateway_did.to_string(),
+        snapshot_count: 10,
+        batch_merkle_root: "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef".to_string(),
+        original_data_reference: None,
+        metadata_json: None,
+        sequence: None,
+    }]
+}
+
+/// Minimal StoreProof message for `did:c4e:worker:detrack1` with all optional fields unset
+pub fn store_proof_msg(data_hash: &str, batch_metadata: Vec<BatchInfo>) -> ExecuteMsg {
+    ProofBuilder::new(data_hash).batches(batch_metadata).build()
+}
+
+/// Builds `StoreProof` messages, starting from [`store_proof_msg`]'s defaults
+pub struct ProofBuilder {
+    worker_did: String,
+    data_hash: String,
+    tw_start: Timestamp,
+    tw_end: Timestamp,
+    batch_metadata: Vec<BatchInfo>,
+    original_data_reference: Option<String>,
+    metadata_json: Option<String>,
+    data_owner: Option<String>,
+    region: Option<String>,
+    energy: Option<(Option<Uint128>, Option<Uint128>, String)>,
+}
+
+impl ProofBuilder {
+    pub fn new(data_hash: &str) -> Self {
+        ProofBuilder {
+            worker_did: "did:c4e:worker:detrack1".to_string(),
+            data_hash: data_hash.to_string(),
+            tw_start: Timestamp::from_nanos(1704067200000000000),
+            tw_end: Timestamp::from_nanos(1704153600000000000),
+            batch_metadata: vec![],
+            original_data_reference: None,
+            metadata_json: None,
+            data_owner: None,
+            region: None,
+            energy: None,
+        }
+    }
+
+    pub fn worker_did(mut self, worker_did: &str) -> Self {
+        self.worker_did = worker_did.to_string();
+        self
+    }
+
+    pub fn window(mut self, tw_start: Timestamp, tw_end: Timestamp) -> Self {
+        self.tw_start = tw_start;
+        self.tw_end = tw_end;
+        self
+    }
+
+    /// Appends a single batch from `gateway_did`
+    pub fn batch(mut self, gateway_did: &str) -> Self {
+        self.batch_metadata.extend(single_batch(gateway_did));
+        self
+    }
+
+    pub fn batches(mut self, batch_metadata: Vec<BatchInfo>) -> Self {
+        self.batch_metadata.extend(batch_metadata);
+        self
+    }
+
+    pub fn original_data_reference(mut self, reference: &str) -> Self {
+        self.original_data_reference = Some(reference.to_string());
+        self
+    }
+
+    pub fn metadata_json(mut self, metadata_json: &str) -> Self {
+        self.metadata_json = Some(metadata_json.to_string());
+        self
+    }
+
+    pub fn data_owner(mut self, data_owner: &str) -> Self {
+        self.data_owner = Some(data_owner.to_string());
+        self
+    }
+
+    pub fn region(mut self, region: &str) -> Self {
+        self.region = Some(region.to_string());
+        self
+    }
+
+    pub fn energy(mut self, value_in: Option<u128>, value_out: Option<u128>, unit: &str) -> Self {
+        self.energy = Some((value_in.map(Uint128::new), value_out.map(Uint128::new), unit.to_string()));
+        self
+    }
+
+    pub fn build(self) -> ExecuteMsg {
+        let (value_in, value_out, unit) = match self.energy {
+            Some((value_in, value_out, unit)) => (value_in, value_out, Some(unit)),
+            None => (None, None, None),
+        };
+        ExecuteMsg::Node(NodeExecuteMsg::StoreProof {
+            worker_did: self.worker_did,
+            data_hash: self.data_hash,
+            tw_start: self.tw_start,
+            tw_end: self.tw_end,
+            batch_metadata: self.batch_metadata,
+            original_data_reference: self.original_data_reference,
+            metadata_json: self.metadata_json,
+            region: self.region,
+            site: None,
+            data_owner: self.data_owner,
+            owner_shares: vec![],
+            value_in,
+            value_out,
+            unit,
+        })
+    }
+}
+
+/// Stake of `amount` bonded tokens for each of `nodes`, all delegated to one validator
+pub fn staking_with(bonded_denom: &str, nodes: &[(&str, u128)]) -> MockStaking {
+    nodes.iter().fold(MockStaking::new(bonded_denom), |staking, (node, amount)| {
+        staking.with_delegation(node, "validator", *amount)
+    })
+}